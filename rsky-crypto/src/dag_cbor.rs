@@ -0,0 +1,205 @@
+use anyhow::{bail, Result};
+use base64::engine::general_purpose::{GeneralPurpose, GeneralPurposeConfig};
+use base64::engine::DecodePaddingMode;
+use base64::Engine as _;
+use cid::multihash::Multihash;
+use cid::Cid;
+use serde::Serialize;
+use serde_cbor::Value as CborValue;
+use serde_json::Value as JsonValue;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+pub const DAG_CBOR_CODEC: u64 = 0x71;
+pub const RAW_CODEC: u64 = 0x55;
+pub const SHA2_256_CODE: u64 = 0x12;
+/// CBOR tag for IPLD links.
+const CID_TAG: u64 = 42;
+
+const BYTES_ENGINE: GeneralPurpose = GeneralPurpose::new(
+    &base64::alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// Encodes a value in the atproto JSON data model as canonical DAG-CBOR.
+///
+/// `{"$link": "<cid>"}` objects become tag 42 links and `{"$bytes": "<base64>"}` objects become
+/// byte strings. Map keys are written length-first, then bytewise, and floats are rejected since
+/// atproto records can't contain them.
+pub fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>> {
+    encode_json(&serde_json::to_value(value)?)
+}
+
+pub fn encode_json(value: &JsonValue) -> Result<Vec<u8>> {
+    // serde_cbor orders `Value::Map` keys canonically, which for string keys is DAG-CBOR's order
+    Ok(serde_cbor::to_vec(&to_cbor_value(value)?)?)
+}
+
+fn to_cbor_value(value: &JsonValue) -> Result<CborValue> {
+    Ok(match value {
+        JsonValue::Null => CborValue::Null,
+        JsonValue::Bool(b) => CborValue::Bool(*b),
+        JsonValue::Number(n) => match (n.as_i64(), n.as_u64()) {
+            (Some(i), _) => CborValue::Integer(i.into()),
+            (None, Some(u)) => CborValue::Integer(u.into()),
+            _ => bail!("Floats are not allowed in DAG-CBOR records: {n}"),
+        },
+        JsonValue::String(s) => CborValue::Text(s.clone()),
+        JsonValue::Array(values) => CborValue::Array(
+            values
+                .iter()
+                .map(to_cbor_value)
+                .collect::<Result<Vec<_>>>()?,
+        ),
+        JsonValue::Object(map) => match (map.len(), map.get("$link"), map.get("$bytes")) {
+            (1, Some(JsonValue::String(link)), _) => {
+                // Links are the CID's binary form behind a 0x00 multibase identity prefix
+                let cid = Cid::from_str(link)?;
+                CborValue::Tag(
+                    CID_TAG,
+                    Box::new(CborValue::Bytes([&[0][..], &cid.to_bytes()].concat())),
+                )
+            }
+            (1, _, Some(JsonValue::String(bytes))) => CborValue::Bytes(BYTES_ENGINE.decode(bytes)?),
+            _ => CborValue::Map(
+                map.iter()
+                    .map(|(key, value)| Ok((CborValue::Text(key.clone()), to_cbor_value(value)?)))
+                    .collect::<Result<BTreeMap<_, _>>>()?,
+            ),
+        },
+    })
+}
+
+pub fn sha256_cid(codec: u64, bytes: &[u8]) -> Cid {
+    let digest = Sha256::digest(bytes);
+    let hash = Multihash::wrap(SHA2_256_CODE, &digest).expect("sha256 digest fits a multihash");
+    Cid::new_v1(codec, hash)
+}
+
+/// CIDv1 of already encoded DAG-CBOR bytes.
+pub fn cid_for_cbor(bytes: &[u8]) -> Cid {
+    sha256_cid(DAG_CBOR_CODEC, bytes)
+}
+
+/// CIDv1 of a blob or other raw bytes.
+pub fn cid_for_raw(bytes: &[u8]) -> Cid {
+    sha256_cid(RAW_CODEC, bytes)
+}
+
+pub fn cid_for_record<T: Serialize>(record: &T) -> Result<Cid> {
+    Ok(cid_for_cbor(&encode(record)?))
+}
+
+/// Whether `record` hashes to `claimed`, e.g. the `cid` of a firehose or Jetstream commit.
+pub fn verify_record_cid<T: Serialize>(record: &T, claimed: &str) -> Result<bool> {
+    let claimed = Cid::from_str(claimed)?;
+    let cid = match claimed.codec() {
+        DAG_CBOR_CODEC => cid_for_record(record)?,
+        codec => bail!("Unsupported record CID codec: {codec:#x}"),
+    };
+    Ok(cid == claimed)
+}
+
+/// Decodes DAG-CBOR into the atproto JSON data model, the inverse of [`encode_json`].
+pub fn decode_json(bytes: &[u8]) -> Result<JsonValue> {
+    to_json_value(serde_cbor::from_slice(bytes)?)
+}
+
+fn to_json_value(value: CborValue) -> Result<JsonValue> {
+    Ok(match value {
+        CborValue::Null => JsonValue::Null,
+        CborValue::Bool(b) => JsonValue::Bool(b),
+        CborValue::Integer(i) => match (i64::try_from(i), u64::try_from(i)) {
+            (Ok(i), _) => i.into(),
+            (_, Ok(u)) => u.into(),
+            _ => bail!("Integer out of range: {i}"),
+        },
+        CborValue::Text(s) => JsonValue::String(s),
+        CborValue::Bytes(bytes) => serde_json::json!({ "$bytes": BYTES_ENGINE.encode(bytes) }),
+        CborValue::Array(values) => JsonValue::Array(
+            values
+                .into_iter()
+                .map(to_json_value)
+                .collect::<Result<Vec<_>>>()?,
+        ),
+        CborValue::Map(map) => JsonValue::Object(
+            map.into_iter()
+                .map(|(key, value)| match key {
+                    CborValue::Text(key) => Ok((key, to_json_value(value)?)),
+                    key => bail!("DAG-CBOR map keys must be strings: {key:?}"),
+                })
+                .collect::<Result<serde_json::Map<_, _>>>()?,
+        ),
+        CborValue::Tag(CID_TAG, link) => match *link {
+            CborValue::Bytes(bytes) if bytes.first() == Some(&0) => {
+                serde_json::json!({ "$link": Cid::try_from(&bytes[1..])?.to_string() })
+            }
+            _ => bail!("Malformed DAG-CBOR link"),
+        },
+        CborValue::Tag(tag, _) => bail!("Unsupported CBOR tag: {tag}"),
+        CborValue::Float(f) => bail!("Floats are not allowed in DAG-CBOR records: {f}"),
+        _ => bail!("Unsupported CBOR value"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_like_record_cid() {
+        // A like as received from Jetstream, with the CID its repo committed it under
+        let record = json!({
+            "$type": "app.bsky.feed.like",
+            "createdAt": "2024-11-13T23:19:36.449Z",
+            "subject": {
+                "cid": "bafyreigw5ufnkavdzcczl2dusa3bcnkckhi4tscp6qsrsmg76s3ckseney",
+                "uri": "at://did:plc:6wthaiuqiys3y7eztkpsdam2/app.bsky.feed.post/3latjcehsho2n"
+            }
+        });
+        let claimed = "bafyreifsdaip3s5nm3hcz4fbgkxodnils75oi3rmqhipwtom34rxw4vwdi";
+
+        assert_eq!(cid_for_record(&record).unwrap().to_string(), claimed);
+        assert!(verify_record_cid(&record, claimed).unwrap());
+
+        let mut tampered = record.clone();
+        tampered["createdAt"] = json!("2024-11-13T23:19:36.450Z");
+        assert!(!verify_record_cid(&tampered, claimed).unwrap());
+    }
+
+    #[test]
+    fn test_raw_cid() {
+        assert_eq!(
+            cid_for_raw(b"hello world").to_string(),
+            "bafkreifzjut3te2nhyekklss27nh3k72ysco7y32koao5eei66wof36n5e"
+        );
+    }
+
+    #[test]
+    fn test_canonical_key_order() {
+        let encoded = encode(&json!({ "bb": 1, "c": 2, "a": 3 })).unwrap();
+
+        // Shorter keys first, then bytewise: a, c, bb
+        assert_eq!(
+            encoded,
+            vec![0xa3, 0x61, b'a', 0x03, 0x61, b'c', 0x02, 0x62, b'b', b'b', 0x01]
+        );
+    }
+
+    #[test]
+    fn test_links_bytes_and_floats() {
+        let value = json!({
+            "ref": { "$link": "bafkreifzjut3te2nhyekklss27nh3k72ysco7y32koao5eei66wof36n5e" },
+            "sig": { "$bytes": "AQID" },
+            "size": 3
+        });
+        let encoded = encode_json(&value).unwrap();
+
+        // The link is tag 42 (0xd8 0x2a) over the CID bytes
+        assert!(encoded.windows(2).any(|window| window == [0xd8, 0x2a]));
+        assert_eq!(decode_json(&encoded).unwrap(), value);
+        assert!(encode_json(&json!({ "ratio": 1.5 })).is_err());
+    }
+}
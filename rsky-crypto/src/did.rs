@@ -1,4 +1,4 @@
-use crate::constants::{BASE58_MULTIBASE_PREFIX, DID_KEY_PREFIX, PLUGINS};
+use crate::constants::{DID_KEY_PREFIX, PLUGINS};
 use crate::utils::{extract_multikey, extract_prefixed_bytes, has_prefix};
 use anyhow::{bail, Result};
 use multibase::{encode, Base};
@@ -31,11 +31,8 @@ pub fn format_multikey(jwt_alg: String, key_bytes: Vec<u8>) -> Result<String> {
         let prefixed_bytes: Vec<u8> =
             [plugin.prefix.to_vec(), (plugin.compress_pubkey)(key_bytes)?].concat();
 
-        Ok([
-            BASE58_MULTIBASE_PREFIX,
-            encode(Base::Base58Btc, prefixed_bytes).as_str(),
-        ]
-        .concat())
+        // `encode` already writes the `z` base58btc prefix
+        Ok(encode(Base::Base58Btc, prefixed_bytes))
     } else {
         bail!("Unsupported key type")
     }
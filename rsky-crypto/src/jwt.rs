@@ -0,0 +1,274 @@
+use crate::did::parse_did_key;
+use crate::types::{Keypair, VerifyOptions};
+use crate::utils::{base64url_decode, base64url_encode};
+use crate::verify::verify_signature;
+use anyhow::Result;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+/// How long a service JWT is valid for when no expiry is given, in seconds.
+pub const DEFAULT_SERVICE_JWT_TTL: u64 = 60;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum JwtError {
+    #[error("Poorly formatted jwt: `{0}`")]
+    BadJwt(String),
+    #[error("Jwt expired")]
+    JwtExpired,
+    #[error("Jwt audience does not match service did")]
+    BadJwtAudience,
+    #[error("Bad jwt lexicon method (\"lxm\"): `{0:?}`")]
+    BadJwtLexiconMethod(Option<String>),
+    #[error("Jwt signature does not match jwt issuer")]
+    BadJwtSignature,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JwtHeader {
+    pub alg: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub typ: Option<String>,
+}
+
+/// Claims of an atproto inter-service auth token.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServiceJwtPayload {
+    pub iss: String,
+    pub aud: String,
+    /// Expiry, in seconds since the epoch.
+    pub exp: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iat: Option<u64>,
+    /// NSID of the method the token is scoped to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lxm: Option<String>,
+    /// Unique nonce, so a token can't be replayed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jti: Option<String>,
+}
+
+pub struct ServiceJwtParams {
+    pub iss: String,
+    pub aud: String,
+    /// Expiry in seconds since the epoch. Defaults to a minute from now.
+    pub exp: Option<u64>,
+    pub lxm: Option<String>,
+}
+
+/// A JWT split into its decoded parts, before any of its claims are checked.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParsedJwt {
+    pub header: JwtHeader,
+    pub payload: ServiceJwtPayload,
+    /// The `header.payload` string the signature covers.
+    pub signing_input: String,
+    pub sig: Vec<u8>,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_secs()
+}
+
+/// Creates a service auth JWT signed by `keypair`, e.g. for an outbound XRPC call.
+pub fn create_service_jwt(params: ServiceJwtParams, keypair: &dyn Keypair) -> Result<String> {
+    let iat = now();
+    let mut jti = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut jti);
+    let header = JwtHeader {
+        alg: keypair.jwt_alg().to_string(),
+        typ: Some("JWT".to_string()),
+    };
+    let payload = ServiceJwtPayload {
+        iss: params.iss,
+        aud: params.aud,
+        exp: params.exp.unwrap_or(iat + DEFAULT_SERVICE_JWT_TTL),
+        iat: Some(iat),
+        lxm: params.lxm,
+        jti: Some(jti.iter().map(|byte| format!("{byte:02x}")).collect()),
+    };
+    let signing_input = format!(
+        "{}.{}",
+        base64url_encode(&serde_json::to_vec(&header)?),
+        base64url_encode(&serde_json::to_vec(&payload)?)
+    );
+    let sig = keypair.sign(signing_input.as_bytes())?;
+    Ok(format!("{signing_input}.{}", base64url_encode(&sig)))
+}
+
+/// Decodes a JWT without checking its claims or signature.
+pub fn parse_jwt(jwt: &str) -> Result<ParsedJwt, JwtError> {
+    let parts = jwt.split('.').collect::<Vec<_>>();
+    let [header, payload, sig] = parts[..] else {
+        return Err(JwtError::BadJwt("expected three segments".to_string()));
+    };
+    let decode = |segment: &str| {
+        base64url_decode(segment).map_err(|error| JwtError::BadJwt(error.to_string()))
+    };
+    Ok(ParsedJwt {
+        header: serde_json::from_slice(&decode(header)?)
+            .map_err(|error| JwtError::BadJwt(error.to_string()))?,
+        payload: serde_json::from_slice(&decode(payload)?)
+            .map_err(|error| JwtError::BadJwt(error.to_string()))?,
+        signing_input: format!("{header}.{payload}"),
+        sig: decode(sig)?,
+    })
+}
+
+/// Checks expiry, audience and, when `lxm` is given, the method the token is scoped to.
+pub fn verify_claims(
+    payload: &ServiceJwtPayload,
+    own_did: Option<&str>,
+    lxm: Option<&str>,
+) -> Result<(), JwtError> {
+    if now() > payload.exp {
+        return Err(JwtError::JwtExpired);
+    }
+    if let Some(own_did) = own_did {
+        if payload.aud != own_did {
+            return Err(JwtError::BadJwtAudience);
+        }
+    }
+    if let Some(lxm) = lxm {
+        if payload.lxm.as_deref() != Some(lxm) {
+            return Err(JwtError::BadJwtLexiconMethod(payload.lxm.clone()));
+        }
+    }
+    Ok(())
+}
+
+/// Fully verifies a service JWT: its claims, and its signature against the issuer's `did_key`.
+pub fn verify_jwt(
+    jwt: &str,
+    own_did: Option<&str>,
+    lxm: Option<&str>,
+    did_key: &String,
+) -> Result<ServiceJwtPayload, JwtError> {
+    let parsed = parse_jwt(jwt)?;
+    verify_claims(&parsed.payload, own_did, lxm)?;
+
+    let key_alg = parse_did_key(did_key)
+        .map_err(|_| JwtError::BadJwtSignature)?
+        .jwt_alg;
+    if key_alg != parsed.header.alg {
+        return Err(JwtError::BadJwtSignature);
+    }
+    // Tokens from older implementations may carry DER or high-S signatures
+    let opts = VerifyOptions {
+        allow_malleable_sig: Some(true),
+    };
+    match verify_signature(
+        did_key,
+        parsed.signing_input.as_bytes(),
+        &parsed.sig,
+        Some(opts),
+    ) {
+        Ok(true) => Ok(parsed.payload),
+        _ => Err(JwtError::BadJwtSignature),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::p256::keypair::P256Keypair;
+    use crate::secp256k1::keypair::Secp256k1Keypair;
+
+    const AUD: &str = "did:web:feed.example.com";
+    const LXM: &str = "app.bsky.feed.getFeedSkeleton";
+
+    fn params(exp: Option<u64>) -> ServiceJwtParams {
+        ServiceJwtParams {
+            iss: "did:plc:ewvi7nxzyoun6zhxrhs64oiz".to_string(),
+            aud: AUD.to_string(),
+            exp,
+            lxm: Some(LXM.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_create_and_verify_round_trip() {
+        let keypairs: Vec<Box<dyn Keypair>> = vec![
+            Box::new(Secp256k1Keypair::create(false)),
+            Box::new(P256Keypair::create(false)),
+        ];
+        for keypair in keypairs {
+            let jwt = create_service_jwt(params(None), keypair.as_ref()).unwrap();
+            let payload = verify_jwt(&jwt, Some(AUD), Some(LXM), &keypair.did().unwrap()).unwrap();
+
+            assert_eq!(payload.iss, "did:plc:ewvi7nxzyoun6zhxrhs64oiz");
+            assert_eq!(payload.jti.unwrap().len(), 32);
+            assert_eq!(parse_jwt(&jwt).unwrap().header.alg, keypair.jwt_alg());
+        }
+    }
+
+    #[test]
+    fn test_rejects_bad_claims() {
+        let keypair = Secp256k1Keypair::create(false);
+        let did_key = keypair.did().unwrap();
+        let jwt = create_service_jwt(params(None), &keypair).unwrap();
+        let expired = create_service_jwt(params(Some(now() - 1)), &keypair).unwrap();
+
+        assert_eq!(
+            verify_jwt(&expired, Some(AUD), None, &did_key),
+            Err(JwtError::JwtExpired)
+        );
+        assert_eq!(
+            verify_jwt(&jwt, Some("did:web:other.example.com"), None, &did_key),
+            Err(JwtError::BadJwtAudience)
+        );
+        assert_eq!(
+            verify_jwt(&jwt, Some(AUD), Some("app.bsky.feed.getPosts"), &did_key),
+            Err(JwtError::BadJwtLexiconMethod(Some(LXM.to_string())))
+        );
+    }
+
+    #[test]
+    fn test_rejects_wrong_signature() {
+        let keypair = Secp256k1Keypair::create(false);
+        let other = Secp256k1Keypair::create(false);
+        let jwt = create_service_jwt(params(None), &keypair).unwrap();
+
+        assert_eq!(
+            verify_jwt(&jwt, Some(AUD), None, &other.did().unwrap()),
+            Err(JwtError::BadJwtSignature)
+        );
+        // A P-256 key can't vouch for an ES256K token
+        assert_eq!(
+            verify_jwt(
+                &jwt,
+                Some(AUD),
+                None,
+                &P256Keypair::create(false).did().unwrap()
+            ),
+            Err(JwtError::BadJwtSignature)
+        );
+    }
+
+    #[test]
+    fn test_parse_malformed_jwt() {
+        assert!(matches!(parse_jwt("abc"), Err(JwtError::BadJwt(_))));
+        assert!(matches!(parse_jwt("a.b.c.d"), Err(JwtError::BadJwt(_))));
+        assert!(matches!(parse_jwt("!!.??.$$"), Err(JwtError::BadJwt(_))));
+        // Standard base64 padding is not base64url
+        assert!(matches!(
+            parse_jwt("eyJhbGciOiJFUzI1NksifQ==.e30.AA"),
+            Err(JwtError::BadJwt(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_base64url_payload() {
+        // {"iss":"did:plc:a","aud":"did:web:b","exp":1,"lxm":"?>?"} encodes with `-` and `_`
+        let payload =
+            base64url_encode(br#"{"iss":"did:plc:a","aud":"did:web:b","exp":1,"lxm":"?>?"}"#);
+        let jwt = format!("{}.{payload}.AA", base64url_encode(br#"{"alg":"ES256K"}"#));
+
+        assert!(payload.contains('_') || payload.contains('-'));
+        assert_eq!(parse_jwt(&jwt).unwrap().payload.lxm.as_deref(), Some("?>?"));
+    }
+}
@@ -1,8 +1,11 @@
 pub mod constants;
+pub mod dag_cbor;
 pub mod did;
+pub mod jwt;
 pub mod multibase;
 pub mod p256;
 pub mod secp256k1;
+pub mod signature;
 pub mod types;
 pub mod utils;
 pub mod verify;
@@ -1,19 +1,44 @@
 use anyhow::{bail, Result};
-use multibase::{encode, Base};
+use multibase::Base;
 
 pub fn multibase_to_bytes(mb: String) -> Result<Vec<u8>> {
     match mb.get(0..1) {
         None => bail!("empty multibase string"),
         Some(base) => match (base, mb.get(1..)) {
-            ("f", Some(key)) => Ok(encode(Base::Base16Lower, key).into_bytes()),
-            ("F", Some(key)) => Ok(encode(Base::Base16Upper, key).into_bytes()),
-            ("b", Some(key)) => Ok(encode(Base::Base32Lower, key).into_bytes()),
-            ("B", Some(key)) => Ok(encode(Base::Base32Upper, key).into_bytes()),
-            ("z", Some(key)) => Ok(encode(Base::Base58Btc, key).into_bytes()),
-            ("m", Some(key)) => Ok(encode(Base::Base64, key).into_bytes()),
-            ("u", Some(key)) => Ok(encode(Base::Base64Url, key).into_bytes()),
-            ("U", Some(key)) => Ok(encode(Base::Base64UrlPad, key).into_bytes()),
+            ("f", Some(key)) => Ok(Base::Base16Lower.decode(key)?),
+            ("F", Some(key)) => Ok(Base::Base16Upper.decode(key)?),
+            ("b", Some(key)) => Ok(Base::Base32Lower.decode(key)?),
+            ("B", Some(key)) => Ok(Base::Base32Upper.decode(key)?),
+            ("z", Some(key)) => Ok(Base::Base58Btc.decode(key)?),
+            ("m", Some(key)) => Ok(Base::Base64.decode(key)?),
+            ("u", Some(key)) => Ok(Base::Base64Url.decode(key)?),
+            ("U", Some(key)) => Ok(Base::Base64UrlPad.decode(key)?),
             (&_, _) => bail!("Unsupported multibase: {mb}"),
         },
     }
 }
+
+pub fn bytes_to_multibase(bytes: &[u8], base: Base) -> String {
+    multibase::encode(base, bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_multibase_to_bytes_decodes() {
+        let bytes = vec![0x00, 0x01, 0xfe, 0xff];
+
+        assert_eq!(multibase_to_bytes("f0001feff".to_string()).unwrap(), bytes);
+        assert_eq!(
+            multibase_to_bytes("zCn8eVZg".to_string()).unwrap(),
+            b"hello"
+        );
+        for base in [Base::Base32Lower, Base::Base58Btc, Base::Base64Url] {
+            let mb = bytes_to_multibase(&bytes, base);
+            assert_eq!(multibase_to_bytes(mb).unwrap(), bytes);
+        }
+        assert!(multibase_to_bytes("fzz".to_string()).is_err());
+    }
+}
@@ -0,0 +1,146 @@
+use crate::constants::P256_JWT_ALG;
+use crate::did::format_did_key;
+use crate::multibase::{bytes_to_multibase, multibase_to_bytes};
+use crate::p256::operations::sign;
+use crate::types::{Jwk, Keypair};
+use crate::utils::{base64url_decode, base64url_encode};
+use anyhow::{bail, Result};
+use multibase::Base;
+use p256::ecdsa::SigningKey;
+
+const JWK_CRV: &str = "P-256";
+
+pub struct P256Keypair {
+    signing_key: SigningKey,
+    exportable: bool,
+}
+
+impl P256Keypair {
+    pub fn create(exportable: bool) -> Self {
+        Self {
+            signing_key: SigningKey::random(&mut rand::thread_rng()),
+            exportable,
+        }
+    }
+
+    pub fn import(private_key: &[u8], exportable: bool) -> Result<Self> {
+        Ok(Self {
+            signing_key: SigningKey::from_slice(private_key)?,
+            exportable,
+        })
+    }
+
+    pub fn import_multibase(private_key: &str, exportable: bool) -> Result<Self> {
+        Self::import(&multibase_to_bytes(private_key.to_string())?, exportable)
+    }
+
+    pub fn import_jwk(jwk: &Jwk, exportable: bool) -> Result<Self> {
+        if jwk.kty != "EC" || jwk.crv != JWK_CRV {
+            bail!("Not a P-256 JWK: {} {}", jwk.kty, jwk.crv)
+        }
+        let Some(ref d) = jwk.d else {
+            bail!("JWK is missing its private key")
+        };
+        let keypair = Self::import(&base64url_decode(d)?, exportable)?;
+        let (x, y) = keypair.coordinates();
+        if x != jwk.x || y != jwk.y {
+            bail!("JWK public key does not match its private key")
+        }
+        Ok(keypair)
+    }
+
+    /// The public key's affine coordinates, base64url encoded for a JWK.
+    fn coordinates(&self) -> (String, String) {
+        let point = self.signing_key.verifying_key().to_encoded_point(false);
+        let (x, y) = (point.x().unwrap(), point.y().unwrap());
+        (base64url_encode(x), base64url_encode(y))
+    }
+
+    pub fn export(&self) -> Result<Vec<u8>> {
+        if !self.exportable {
+            bail!("Private key is not exportable")
+        }
+        Ok(self.signing_key.to_bytes().to_vec())
+    }
+
+    pub fn export_multibase(&self) -> Result<String> {
+        Ok(bytes_to_multibase(&self.export()?, Base::Base58Btc))
+    }
+
+    pub fn export_jwk(&self) -> Result<Jwk> {
+        let d = self.export()?;
+        let (x, y) = self.coordinates();
+        Ok(Jwk {
+            kty: "EC".to_string(),
+            crv: JWK_CRV.to_string(),
+            x,
+            y,
+            d: Some(base64url_encode(&d)),
+        })
+    }
+}
+
+impl Keypair for P256Keypair {
+    fn jwt_alg(&self) -> &'static str {
+        P256_JWT_ALG
+    }
+
+    fn public_key_bytes(&self) -> Vec<u8> {
+        self.signing_key
+            .verifying_key()
+            .to_encoded_point(true)
+            .as_bytes()
+            .to_vec()
+    }
+
+    fn did(&self) -> Result<String> {
+        format_did_key(P256_JWT_ALG.to_string(), self.public_key_bytes())
+    }
+
+    fn sign(&self, msg: &[u8]) -> Result<Vec<u8>> {
+        sign(&self.signing_key.to_bytes(), msg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::p256::operations::is_compact_format;
+    use crate::verify::verify_signature;
+
+    #[test]
+    fn test_sign_and_verify_round_trip() {
+        let keypair = P256Keypair::create(false);
+        let did = keypair.did().unwrap();
+        let msg = b"hello world";
+        let sig = keypair.sign(msg).unwrap();
+
+        assert!(did.starts_with("did:key:zDn"));
+        assert!(is_compact_format(&sig));
+        assert!(verify_signature(&did, msg, &sig, None).unwrap());
+        assert!(!verify_signature(&did, b"hello worle", &sig, None).unwrap());
+    }
+
+    #[test]
+    fn test_export_import_round_trip() {
+        let keypair = P256Keypair::create(true);
+        let did = keypair.did().unwrap();
+
+        let raw = P256Keypair::import(&keypair.export().unwrap(), true).unwrap();
+        let multibase =
+            P256Keypair::import_multibase(&keypair.export_multibase().unwrap(), true).unwrap();
+        let jwk = P256Keypair::import_jwk(&keypair.export_jwk().unwrap(), true).unwrap();
+
+        assert_eq!(raw.did().unwrap(), did);
+        assert_eq!(multibase.did().unwrap(), did);
+        assert_eq!(jwk.did().unwrap(), did);
+    }
+
+    #[test]
+    fn test_export_requires_exportable() {
+        let keypair = P256Keypair::create(false);
+
+        assert!(keypair.export().is_err());
+        assert!(keypair.export_jwk().is_err());
+    }
+}
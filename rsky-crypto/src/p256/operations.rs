@@ -2,7 +2,8 @@ use crate::constants::P256_DID_PREFIX;
 use crate::types::VerifyOptions;
 use crate::utils::{extract_multikey, extract_prefixed_bytes, has_prefix};
 use anyhow::{bail, Result};
-use p256::ecdsa::{signature::Verifier, Signature, VerifyingKey};
+use p256::ecdsa::signature::{Signer, Verifier};
+use p256::ecdsa::{Signature, SigningKey, VerifyingKey};
 
 pub fn verify_did_sig(
     did: &String,
@@ -28,22 +29,63 @@ pub fn verify_sig(
         Some(opts) if opts.allow_malleable_sig.is_some() => opts.allow_malleable_sig.unwrap(),
         _ => false,
     };
-    if !allow_malleable && !is_compact_format(sig) {
+    if !allow_malleable && (!is_compact_format(sig) || !is_low_s(sig)?) {
         return Ok(false);
     }
     let verifying_key = VerifyingKey::from_sec1_bytes(public_key)?;
-    let signature = Signature::try_from(sig)?;
+    let signature = parse_sig(sig)?;
+    let signature = signature.normalize_s().unwrap_or(signature);
     Ok(verifying_key.verify(data, &signature).is_ok())
 }
 
+/// Signs the sha256 digest of `data`, returning a 64-byte compact signature normalized to low-S.
+pub fn sign(private_key: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+    let signing_key = SigningKey::from_slice(private_key)?;
+    let sig: Signature = signing_key.sign(data);
+    Ok(sig.normalize_s().unwrap_or(sig).to_vec())
+}
+
 pub fn is_compact_format(sig: &[u8]) -> bool {
-    let mut parsed = match Signature::try_from(sig) {
-        Ok(res) => res,
-        Err(_) => return false,
-    };
-    parsed = match parsed.normalize_s() {
-        Some(res) => res,
-        None => return false,
-    };
-    parsed.to_vec() == *sig
+    match Signature::try_from(sig) {
+        Ok(parsed) => parsed.to_vec() == *sig,
+        Err(_) => false,
+    }
+}
+
+pub fn is_der_format(sig: &[u8]) -> bool {
+    match Signature::from_der(sig) {
+        Ok(parsed) => parsed.to_der().as_bytes() == sig,
+        Err(_) => false,
+    }
+}
+
+/// Parses a signature in either DER or compact format. DER is tried first, since a 64-byte DER
+/// signature would also pass for compact.
+fn parse_sig(sig: &[u8]) -> Result<Signature> {
+    match is_der_format(sig) {
+        true => Ok(Signature::from_der(sig)?),
+        false => Ok(Signature::try_from(sig)?),
+    }
+}
+
+pub fn der_to_compact(sig: &[u8]) -> Result<Vec<u8>> {
+    Ok(Signature::from_der(sig)?.to_vec())
+}
+
+pub fn compact_to_der(sig: &[u8]) -> Result<Vec<u8>> {
+    if !is_compact_format(sig) {
+        bail!("Not a compact P-256 signature")
+    }
+    Ok(Signature::try_from(sig)?.to_der().as_bytes().to_vec())
+}
+
+pub fn is_low_s(sig: &[u8]) -> Result<bool> {
+    // `normalize_s` only returns a signature when `s` was high
+    Ok(parse_sig(sig)?.normalize_s().is_none())
+}
+
+/// Converts a compact or DER signature to compact format with a low `s`.
+pub fn normalize_s(sig: &[u8]) -> Result<Vec<u8>> {
+    let parsed = parse_sig(sig)?;
+    Ok(parsed.normalize_s().unwrap_or(parsed).to_vec())
 }
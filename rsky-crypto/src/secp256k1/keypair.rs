@@ -0,0 +1,156 @@
+use crate::constants::SECP256K1_JWT_ALG;
+use crate::did::format_did_key;
+use crate::multibase::{bytes_to_multibase, multibase_to_bytes};
+use crate::secp256k1::operations::sign;
+use crate::types::{Jwk, Keypair};
+use crate::utils::{base64url_decode, base64url_encode};
+use anyhow::{bail, Result};
+use multibase::Base;
+use secp256k1::{PublicKey, Secp256k1, SecretKey};
+
+const JWK_CRV: &str = "secp256k1";
+
+pub struct Secp256k1Keypair {
+    secret_key: SecretKey,
+    public_key: PublicKey,
+    exportable: bool,
+}
+
+impl Secp256k1Keypair {
+    pub fn create(exportable: bool) -> Self {
+        let secret_key = SecretKey::new(&mut rand::thread_rng());
+        Self::from_secret_key(secret_key, exportable)
+    }
+
+    pub fn import(private_key: &[u8], exportable: bool) -> Result<Self> {
+        let secret_key = SecretKey::from_slice(private_key)?;
+        Ok(Self::from_secret_key(secret_key, exportable))
+    }
+
+    pub fn import_multibase(private_key: &str, exportable: bool) -> Result<Self> {
+        Self::import(&multibase_to_bytes(private_key.to_string())?, exportable)
+    }
+
+    pub fn import_jwk(jwk: &Jwk, exportable: bool) -> Result<Self> {
+        if jwk.kty != "EC" || jwk.crv != JWK_CRV {
+            bail!("Not a secp256k1 JWK: {} {}", jwk.kty, jwk.crv)
+        }
+        let Some(ref d) = jwk.d else {
+            bail!("JWK is missing its private key")
+        };
+        let keypair = Self::import(&base64url_decode(d)?, exportable)?;
+        let point = keypair.public_key.serialize_uncompressed();
+        if base64url_encode(&point[1..33]) != jwk.x || base64url_encode(&point[33..]) != jwk.y {
+            bail!("JWK public key does not match its private key")
+        }
+        Ok(keypair)
+    }
+
+    fn from_secret_key(secret_key: SecretKey, exportable: bool) -> Self {
+        let public_key = secret_key.public_key(&Secp256k1::signing_only());
+        Self {
+            secret_key,
+            public_key,
+            exportable,
+        }
+    }
+
+    pub fn export(&self) -> Result<Vec<u8>> {
+        if !self.exportable {
+            bail!("Private key is not exportable")
+        }
+        Ok(self.secret_key.secret_bytes().to_vec())
+    }
+
+    pub fn export_multibase(&self) -> Result<String> {
+        Ok(bytes_to_multibase(&self.export()?, Base::Base58Btc))
+    }
+
+    pub fn export_jwk(&self) -> Result<Jwk> {
+        let d = self.export()?;
+        let point = self.public_key.serialize_uncompressed();
+        Ok(Jwk {
+            kty: "EC".to_string(),
+            crv: JWK_CRV.to_string(),
+            x: base64url_encode(&point[1..33]),
+            y: base64url_encode(&point[33..]),
+            d: Some(base64url_encode(&d)),
+        })
+    }
+}
+
+impl Keypair for Secp256k1Keypair {
+    fn jwt_alg(&self) -> &'static str {
+        SECP256K1_JWT_ALG
+    }
+
+    fn public_key_bytes(&self) -> Vec<u8> {
+        self.public_key.serialize().to_vec()
+    }
+
+    fn did(&self) -> Result<String> {
+        format_did_key(SECP256K1_JWT_ALG.to_string(), self.public_key_bytes())
+    }
+
+    fn sign(&self, msg: &[u8]) -> Result<Vec<u8>> {
+        sign(&self.secret_key.secret_bytes(), msg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secp256k1::operations::is_compact_format;
+    use crate::verify::verify_signature;
+
+    #[test]
+    fn test_sign_and_verify_round_trip() {
+        let keypair = Secp256k1Keypair::create(false);
+        let did = keypair.did().unwrap();
+        let msg = b"hello world";
+        let sig = keypair.sign(msg).unwrap();
+
+        assert!(did.starts_with("did:key:zQ3s"));
+        assert!(is_compact_format(&sig));
+        assert!(verify_signature(&did, msg, &sig, None).unwrap());
+        assert!(!verify_signature(&did, b"hello worle", &sig, None).unwrap());
+    }
+
+    #[test]
+    fn test_known_did_key() {
+        let private_key = [
+            0x90, 0x85, 0xd2, 0xbe, 0xf6, 0x92, 0x86, 0xa6, 0xcb, 0xb5, 0x16, 0x23, 0xc8, 0xfa,
+            0x25, 0x86, 0x29, 0x94, 0x5c, 0xd5, 0x5c, 0xa7, 0x05, 0xcc, 0x4e, 0x66, 0x70, 0x03,
+            0x96, 0x89, 0x4e, 0x0c,
+        ];
+        let keypair = Secp256k1Keypair::import(&private_key, false).unwrap();
+
+        assert_eq!(
+            keypair.did().unwrap(),
+            "did:key:zQ3shokFTS3brHcDQrn82RUDfCZESWL1ZdCEJwekUDPQiYBme"
+        );
+    }
+
+    #[test]
+    fn test_export_import_round_trip() {
+        let keypair = Secp256k1Keypair::create(true);
+        let did = keypair.did().unwrap();
+
+        let raw = Secp256k1Keypair::import(&keypair.export().unwrap(), true).unwrap();
+        let multibase =
+            Secp256k1Keypair::import_multibase(&keypair.export_multibase().unwrap(), true).unwrap();
+        let jwk = Secp256k1Keypair::import_jwk(&keypair.export_jwk().unwrap(), true).unwrap();
+
+        assert_eq!(raw.did().unwrap(), did);
+        assert_eq!(multibase.did().unwrap(), did);
+        assert_eq!(jwk.did().unwrap(), did);
+    }
+
+    #[test]
+    fn test_export_requires_exportable() {
+        let keypair = Secp256k1Keypair::create(false);
+
+        assert!(keypair.export().is_err());
+        assert!(keypair.export_jwk().is_err());
+    }
+}
@@ -1,3 +1,4 @@
 pub mod encoding;
+pub mod keypair;
 pub mod operations;
 pub mod plugin;
@@ -2,7 +2,8 @@ use crate::constants::SECP256K1_DID_PREFIX;
 use crate::types::VerifyOptions;
 use crate::utils::{extract_multikey, extract_prefixed_bytes, has_prefix};
 use anyhow::{bail, Result};
-use secp256k1::{ecdsa, Message, PublicKey, Secp256k1};
+use secp256k1::hashes::sha256;
+use secp256k1::{ecdsa, Message, PublicKey, Secp256k1, SecretKey};
 
 pub fn verify_did_sig(
     did: &String,
@@ -28,23 +29,94 @@ pub fn verify_sig(
         Some(opts) if opts.allow_malleable_sig.is_some() => opts.allow_malleable_sig.unwrap(),
         _ => false,
     };
-    let is_compact = is_compact_format(sig);
-    if !allow_malleable && !is_compact {
+    if !allow_malleable && (!is_compact_format(sig) || !is_low_s(sig)?) {
         return Ok(false);
     }
     let secp = Secp256k1::verification_only();
     let public_key = PublicKey::from_slice(public_key)?;
-    let data = Message::from_digest_slice(data)?;
-    let sig = match is_compact {
-        true => ecdsa::Signature::from_compact(sig)?,
-        false => ecdsa::Signature::from_der(sig)?,
-    };
+    let data = Message::from_hashed_data::<sha256::Hash>(data);
+    // libsecp256k1 only accepts low-S signatures, so malleable ones are normalized first
+    let mut sig = parse_sig(sig)?;
+    sig.normalize_s();
     Ok(secp.verify_ecdsa(&data, &sig, &public_key).is_ok())
 }
 
+/// Signs the sha256 digest of `data`, returning a 64-byte compact signature normalized to low-S.
+pub fn sign(private_key: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+    let secp = Secp256k1::signing_only();
+    let secret_key = SecretKey::from_slice(private_key)?;
+    let data = Message::from_hashed_data::<sha256::Hash>(data);
+    let mut sig = secp.sign_ecdsa(&data, &secret_key);
+    sig.normalize_s();
+    Ok(sig.serialize_compact().to_vec())
+}
+
 pub fn is_compact_format(sig: &[u8]) -> bool {
     match ecdsa::Signature::from_compact(sig) {
         Ok(parsed) => parsed.serialize_compact() == sig,
         Err(_) => false,
     }
 }
+
+pub fn is_der_format(sig: &[u8]) -> bool {
+    match ecdsa::Signature::from_der(sig) {
+        Ok(parsed) => *parsed.serialize_der() == *sig,
+        Err(_) => false,
+    }
+}
+
+/// Parses a signature in either DER or compact format. DER is tried first, since a 64-byte DER
+/// signature would also pass for compact.
+fn parse_sig(sig: &[u8]) -> Result<ecdsa::Signature> {
+    match is_der_format(sig) {
+        true => Ok(ecdsa::Signature::from_der(sig)?),
+        false => Ok(ecdsa::Signature::from_compact(sig)?),
+    }
+}
+
+pub fn der_to_compact(sig: &[u8]) -> Result<Vec<u8>> {
+    Ok(ecdsa::Signature::from_der(sig)?
+        .serialize_compact()
+        .to_vec())
+}
+
+pub fn compact_to_der(sig: &[u8]) -> Result<Vec<u8>> {
+    if !is_compact_format(sig) {
+        bail!("Not a compact secp256k1 signature")
+    }
+    Ok(ecdsa::Signature::from_compact(sig)?
+        .serialize_der()
+        .to_vec())
+}
+
+pub fn is_low_s(sig: &[u8]) -> Result<bool> {
+    let parsed = parse_sig(sig)?;
+    let mut normalized = parsed;
+    normalized.normalize_s();
+    Ok(normalized == parsed)
+}
+
+/// Converts a compact or DER signature to compact format with a low `s`.
+pub fn normalize_s(sig: &[u8]) -> Result<Vec<u8>> {
+    let mut parsed = parse_sig(sig)?;
+    parsed.normalize_s();
+    Ok(parsed.serialize_compact().to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_sig_hashes_data() {
+        let secp = Secp256k1::new();
+        let secret_key = SecretKey::from_slice(&[0xcd; 32]).unwrap();
+        let public_key = PublicKey::from_secret_key(&secp, &secret_key).serialize();
+        let data = b"a message that isn't a 32-byte digest";
+        let digest = Message::from_hashed_data::<sha256::Hash>(data);
+        let sig = secp.sign_ecdsa(&digest, &secret_key).serialize_compact();
+
+        assert!(verify_sig(&public_key, data, &sig, None).unwrap());
+        assert!(!verify_sig(&public_key, b"another message", &sig, None).unwrap());
+    }
+}
@@ -0,0 +1,220 @@
+use crate::constants::{P256_JWT_ALG, SECP256K1_JWT_ALG};
+use crate::{p256, secp256k1};
+use anyhow::{bail, Result};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SignatureFormat {
+    /// 64 bytes: `r` then `s`, both big-endian. The only format atproto signs with.
+    Compact,
+    /// ASN.1 DER `SEQUENCE { r INTEGER, s INTEGER }`, as produced by many ECDSA tools.
+    Der,
+}
+
+struct SignatureOps {
+    is_compact_format: fn(&[u8]) -> bool,
+    is_der_format: fn(&[u8]) -> bool,
+    der_to_compact: fn(&[u8]) -> Result<Vec<u8>>,
+    compact_to_der: fn(&[u8]) -> Result<Vec<u8>>,
+    is_low_s: fn(&[u8]) -> Result<bool>,
+    normalize_s: fn(&[u8]) -> Result<Vec<u8>>,
+}
+
+const SECP256K1_OPS: SignatureOps = SignatureOps {
+    is_compact_format: secp256k1::operations::is_compact_format,
+    is_der_format: secp256k1::operations::is_der_format,
+    der_to_compact: secp256k1::operations::der_to_compact,
+    compact_to_der: secp256k1::operations::compact_to_der,
+    is_low_s: secp256k1::operations::is_low_s,
+    normalize_s: secp256k1::operations::normalize_s,
+};
+
+const P256_OPS: SignatureOps = SignatureOps {
+    is_compact_format: p256::operations::is_compact_format,
+    is_der_format: p256::operations::is_der_format,
+    der_to_compact: p256::operations::der_to_compact,
+    compact_to_der: p256::operations::compact_to_der,
+    is_low_s: p256::operations::is_low_s,
+    normalize_s: p256::operations::normalize_s,
+};
+
+fn ops(jwt_alg: &str) -> Result<SignatureOps> {
+    match jwt_alg {
+        SECP256K1_JWT_ALG => Ok(SECP256K1_OPS),
+        P256_JWT_ALG => Ok(P256_OPS),
+        _ => bail!("Unsupported signature alg: {jwt_alg}"),
+    }
+}
+
+/// A strict DER encoding can be exactly 64 bytes and read as compact too, so DER wins.
+pub fn detect_format(jwt_alg: &str, sig: &[u8]) -> Result<SignatureFormat> {
+    let ops = ops(jwt_alg)?;
+    if (ops.is_der_format)(sig) {
+        Ok(SignatureFormat::Der)
+    } else if (ops.is_compact_format)(sig) {
+        Ok(SignatureFormat::Compact)
+    } else {
+        bail!("Unrecognized {jwt_alg} signature format")
+    }
+}
+
+pub fn der_to_compact(jwt_alg: &str, sig: &[u8]) -> Result<Vec<u8>> {
+    (ops(jwt_alg)?.der_to_compact)(sig)
+}
+
+pub fn compact_to_der(jwt_alg: &str, sig: &[u8]) -> Result<Vec<u8>> {
+    (ops(jwt_alg)?.compact_to_der)(sig)
+}
+
+pub fn is_low_s(jwt_alg: &str, sig: &[u8]) -> Result<bool> {
+    (ops(jwt_alg)?.is_low_s)(sig)
+}
+
+/// Rewrites a compact or DER signature, high-S or not, into the compact low-S form that
+/// verification accepts without `allow_malleable_sig`.
+pub fn normalize_s(jwt_alg: &str, sig: &[u8]) -> Result<Vec<u8>> {
+    (ops(jwt_alg)?.normalize_s)(sig)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::VerifyOptions;
+    use crate::verify::verify_signature;
+
+    /// The same message signed on both curves, with each signature in all four forms.
+    struct SignatureVector {
+        jwt_alg: &'static str,
+        did: &'static str,
+        compact_low_s: &'static str,
+        compact_high_s: &'static str,
+        der_low_s: &'static str,
+        der_high_s: &'static str,
+    }
+
+    const MESSAGE: &[u8] = b"hello world";
+
+    const VECTORS: [SignatureVector; 2] = [
+        SignatureVector {
+            jwt_alg: SECP256K1_JWT_ALG,
+            did: "did:key:zQ3shokFTS3brHcDQrn82RUDfCZESWL1ZdCEJwekUDPQiYBme",
+            compact_low_s: "15259d1e4900a614f6b2813049e4b16ecea6b0b9efe9014dc2bb31358319ec525ee9d5d9be0c6456fb43c41a1526a4d5eac812d3721578a8cf2ae2b6ea9a8219",
+            compact_high_s: "15259d1e4900a614f6b2813049e4b16ecea6b0b9efe9014dc2bb31358319ec52a1162a2641f39ba904bc3be5ead95b28cfe6ca133d332792f0a77bd5e59bbf28",
+            der_low_s: "3044022015259d1e4900a614f6b2813049e4b16ecea6b0b9efe9014dc2bb31358319ec5202205ee9d5d9be0c6456fb43c41a1526a4d5eac812d3721578a8cf2ae2b6ea9a8219",
+            der_high_s: "3045022015259d1e4900a614f6b2813049e4b16ecea6b0b9efe9014dc2bb31358319ec52022100a1162a2641f39ba904bc3be5ead95b28cfe6ca133d332792f0a77bd5e59bbf28",
+        },
+        SignatureVector {
+            jwt_alg: P256_JWT_ALG,
+            did: "did:key:zDnaeQa8zprPhHA7Yuxcgc2Uh6XNQgaKjmFZE4EaA9fk5svmJ",
+            compact_low_s: "88aea3fc8b28cdfeb4a5f2a2572f31335f84f85f8de4ff1900c735f5d0c6181b30b5e2bcd707fcf6e2a4c595ebc5a8c4bbd9d8b124c629ae0944b4e13e8eb9bf",
+            compact_high_s: "88aea3fc8b28cdfeb4a5f2a2572f31335f84f85f8de4ff1900c735f5d0c6181bcf4a1d4228f8030a1d5b3a6a143a573b010d21fc825174d6ea7515e1bdd46b92",
+            der_low_s: "304502210088aea3fc8b28cdfeb4a5f2a2572f31335f84f85f8de4ff1900c735f5d0c6181b022030b5e2bcd707fcf6e2a4c595ebc5a8c4bbd9d8b124c629ae0944b4e13e8eb9bf",
+            der_high_s: "304602210088aea3fc8b28cdfeb4a5f2a2572f31335f84f85f8de4ff1900c735f5d0c6181b022100cf4a1d4228f8030a1d5b3a6a143a573b010d21fc825174d6ea7515e1bdd46b92",
+        },
+    ];
+
+    fn hex(encoded: &str) -> Vec<u8> {
+        (0..encoded.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&encoded[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn verify(vector: &SignatureVector, sig: &[u8], allow_malleable: bool) -> bool {
+        let opts = VerifyOptions {
+            allow_malleable_sig: Some(allow_malleable),
+        };
+        verify_signature(&vector.did.to_string(), MESSAGE, sig, Some(opts)).unwrap_or(false)
+    }
+
+    #[test]
+    fn test_detect_format() {
+        for vector in VECTORS {
+            let alg = vector.jwt_alg;
+            for compact in [vector.compact_low_s, vector.compact_high_s] {
+                assert_eq!(
+                    detect_format(alg, &hex(compact)).unwrap(),
+                    SignatureFormat::Compact
+                );
+            }
+            for der in [vector.der_low_s, vector.der_high_s] {
+                assert_eq!(detect_format(alg, &hex(der)).unwrap(), SignatureFormat::Der);
+            }
+            assert!(detect_format(alg, &[0x30, 0x01, 0x02]).is_err());
+        }
+        assert!(detect_format("HS256", &hex(VECTORS[0].compact_low_s)).is_err());
+    }
+
+    #[test]
+    fn test_64_byte_der_is_detected_as_der() {
+        // `r` and `s` of 29 bytes each make a DER signature exactly as long as a compact one
+        let (r, s) = ([0x01; 29], [0x02; 29]);
+        let der = [&[0x30, 0x3e, 0x02, 0x1d][..], &r, &[0x02, 0x1d], &s].concat();
+        let compact = [&[0; 3][..], &r, &[0; 3], &s].concat();
+        assert_eq!(der.len(), 64);
+        for alg in [SECP256K1_JWT_ALG, P256_JWT_ALG] {
+            assert!((ops(alg).unwrap().is_compact_format)(&der));
+            assert_eq!(detect_format(alg, &der).unwrap(), SignatureFormat::Der);
+            assert_eq!(der_to_compact(alg, &der).unwrap(), compact);
+            assert!(is_low_s(alg, &der).unwrap());
+            assert_eq!(normalize_s(alg, &der).unwrap(), compact);
+        }
+    }
+
+    #[test]
+    fn test_der_compact_conversion() {
+        for vector in VECTORS {
+            let alg = vector.jwt_alg;
+            for (compact, der) in [
+                (vector.compact_low_s, vector.der_low_s),
+                (vector.compact_high_s, vector.der_high_s),
+            ] {
+                assert_eq!(der_to_compact(alg, &hex(der)).unwrap(), hex(compact));
+                assert_eq!(compact_to_der(alg, &hex(compact)).unwrap(), hex(der));
+            }
+            assert!(der_to_compact(alg, &hex(vector.compact_low_s)).is_err());
+            assert!(compact_to_der(alg, &hex(vector.der_low_s)).is_err());
+        }
+    }
+
+    #[test]
+    fn test_normalize_s() {
+        for vector in VECTORS {
+            let alg = vector.jwt_alg;
+            let low_s = hex(vector.compact_low_s);
+
+            assert!(is_low_s(alg, &low_s).unwrap());
+            assert!(is_low_s(alg, &hex(vector.der_low_s)).unwrap());
+            assert!(!is_low_s(alg, &hex(vector.compact_high_s)).unwrap());
+            assert!(!is_low_s(alg, &hex(vector.der_high_s)).unwrap());
+            for sig in [
+                vector.compact_low_s,
+                vector.compact_high_s,
+                vector.der_low_s,
+                vector.der_high_s,
+            ] {
+                assert_eq!(normalize_s(alg, &hex(sig)).unwrap(), low_s);
+            }
+        }
+    }
+
+    #[test]
+    fn test_verify_accepts_only_compact_low_s_unless_malleable() {
+        for vector in VECTORS {
+            assert!(verify(&vector, &hex(vector.compact_low_s), false));
+            for sig in [vector.compact_high_s, vector.der_low_s, vector.der_high_s] {
+                assert!(!verify(&vector, &hex(sig), false));
+                assert!(verify(&vector, &hex(sig), true));
+                assert!(verify(
+                    &vector,
+                    &normalize_s(vector.jwt_alg, &hex(sig)).unwrap(),
+                    false
+                ));
+            }
+            let other = VECTORS
+                .iter()
+                .find(|other| other.jwt_alg != vector.jwt_alg)
+                .unwrap();
+            assert!(!verify(&vector, &hex(other.compact_low_s), true));
+        }
+    }
+}
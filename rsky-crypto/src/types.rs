@@ -1,4 +1,5 @@
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
 
 pub struct DidKeyPlugin<'p> {
     pub prefix: [u8; 2],
@@ -11,3 +12,24 @@ pub struct DidKeyPlugin<'p> {
 pub struct VerifyOptions {
     pub allow_malleable_sig: Option<bool>,
 }
+
+/// A private key that can sign on behalf of the did:key it derives.
+pub trait Keypair {
+    fn jwt_alg(&self) -> &'static str;
+    /// The compressed public key.
+    fn public_key_bytes(&self) -> Vec<u8>;
+    fn did(&self) -> Result<String>;
+    /// Signs the sha256 digest of `msg`, returning a 64-byte compact low-S signature.
+    fn sign(&self, msg: &[u8]) -> Result<Vec<u8>>;
+}
+
+/// An EC key as a JSON Web Key. Coordinates and the private scalar are unpadded base64url.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Jwk {
+    pub kty: String,
+    pub crv: String,
+    pub x: String,
+    pub y: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub d: Option<String>,
+}
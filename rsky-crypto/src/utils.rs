@@ -1,6 +1,8 @@
 use crate::constants::{BASE58_MULTIBASE_PREFIX, DID_KEY_PREFIX};
 use anyhow::{bail, Result};
-use multibase::{encode, Base};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine as _;
+use multibase::Base;
 
 pub fn extract_multikey(did: &String) -> Result<String> {
     if !did.starts_with(DID_KEY_PREFIX) {
@@ -13,9 +15,34 @@ pub fn extract_prefixed_bytes(multikey: String) -> Result<Vec<u8>> {
     if !multikey.starts_with(BASE58_MULTIBASE_PREFIX) {
         bail!("Incorrect prefix for multikey: {multikey}")
     }
-    Ok(encode(Base::Base58Btc, &multikey[BASE58_MULTIBASE_PREFIX.len()..]).into_bytes())
+    Ok(Base::Base58Btc.decode(&multikey[BASE58_MULTIBASE_PREFIX.len()..])?)
 }
 
 pub fn has_prefix(bytes: &[u8], prefix: &Vec<u8>) -> bool {
     *prefix == bytes[0..prefix.len()]
 }
+
+pub fn base64url_encode(bytes: &[u8]) -> String {
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+pub fn base64url_decode(encoded: &str) -> Result<Vec<u8>> {
+    Ok(URL_SAFE_NO_PAD.decode(encoded)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::SECP256K1_DID_PREFIX;
+
+    #[test]
+    fn test_extract_prefixed_bytes_decodes() {
+        let did = "did:key:zQ3shokFTS3brHcDQrn82RUDfCZESWL1ZdCEJwekUDPQiYBme".to_string();
+        let prefixed_bytes = extract_prefixed_bytes(extract_multikey(&did).unwrap()).unwrap();
+
+        assert!(has_prefix(&prefixed_bytes, &SECP256K1_DID_PREFIX.to_vec()));
+        // The prefix followed by a 33-byte compressed public key
+        assert_eq!(prefixed_bytes.len(), 35);
+        assert!(extract_prefixed_bytes("zQ3sh0".to_string()).is_err());
+    }
+}
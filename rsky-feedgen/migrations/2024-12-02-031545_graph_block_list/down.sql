@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE public.listitem;
+DROP TABLE public.list;
+DROP TABLE public.block;
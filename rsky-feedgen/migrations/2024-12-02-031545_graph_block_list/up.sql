@@ -0,0 +1,55 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS public.block
+(
+    uri         character varying NOT NULL,
+    cid         character varying NOT NULL,
+    author      character varying NOT NULL,
+    subject     character varying NOT NULL,
+    "createdAt" character varying NOT NULL,
+    "indexedAt" character varying NOT NULL,
+    prev        character varying,
+    sequence    bigint
+);
+
+ALTER TABLE ONLY public.block
+    ADD CONSTRAINT block_pkey PRIMARY KEY (uri);
+
+CREATE INDEX IF NOT EXISTS block_author_idx ON public.block USING btree (author);
+CREATE INDEX IF NOT EXISTS block_subject_idx ON public.block USING btree (subject);
+
+CREATE TABLE IF NOT EXISTS public.list
+(
+    uri           character varying NOT NULL,
+    cid           character varying NOT NULL,
+    author        character varying NOT NULL,
+    name          character varying NOT NULL,
+    purpose       character varying NOT NULL,
+    description   character varying,
+    "createdAt"   character varying NOT NULL,
+    "indexedAt"   character varying NOT NULL,
+    prev          character varying,
+    sequence      bigint
+);
+
+ALTER TABLE ONLY public.list
+    ADD CONSTRAINT list_pkey PRIMARY KEY (uri);
+
+CREATE INDEX IF NOT EXISTS list_author_idx ON public.list USING btree (author);
+
+CREATE TABLE IF NOT EXISTS public.listitem
+(
+    uri         character varying NOT NULL,
+    cid         character varying NOT NULL,
+    author      character varying NOT NULL,
+    subject     character varying NOT NULL,
+    list        character varying NOT NULL,
+    "createdAt" character varying NOT NULL,
+    "indexedAt" character varying NOT NULL,
+    prev        character varying,
+    sequence    bigint
+);
+
+ALTER TABLE ONLY public.listitem
+    ADD CONSTRAINT listitem_pkey PRIMARY KEY (uri);
+
+CREATE INDEX IF NOT EXISTS listitem_list_idx ON public.listitem USING btree (list);
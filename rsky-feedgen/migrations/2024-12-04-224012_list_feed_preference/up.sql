@@ -0,0 +1,9 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS public.list_feed_preference
+(
+    did      character varying NOT NULL,
+    list_uri character varying NOT NULL
+);
+
+ALTER TABLE ONLY public.list_feed_preference
+    ADD CONSTRAINT list_feed_preference_pkey PRIMARY KEY (did);
@@ -0,0 +1,32 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS public.threadgate
+(
+    uri             character varying NOT NULL,
+    cid             character varying NOT NULL,
+    author          character varying NOT NULL,
+    post            character varying NOT NULL,
+    "hiddenReplies" text[]            NOT NULL DEFAULT '{}',
+    "createdAt"     character varying NOT NULL,
+    "indexedAt"     character varying NOT NULL
+);
+
+ALTER TABLE ONLY public.threadgate
+    ADD CONSTRAINT threadgate_pkey PRIMARY KEY (uri);
+
+CREATE INDEX IF NOT EXISTS threadgate_post_idx ON public.threadgate USING btree (post);
+
+CREATE TABLE IF NOT EXISTS public.postgate
+(
+    uri                     character varying NOT NULL,
+    cid                     character varying NOT NULL,
+    author                  character varying NOT NULL,
+    post                    character varying NOT NULL,
+    "detachedEmbeddingUris" text[]            NOT NULL DEFAULT '{}',
+    "createdAt"             character varying NOT NULL,
+    "indexedAt"             character varying NOT NULL
+);
+
+ALTER TABLE ONLY public.postgate
+    ADD CONSTRAINT postgate_pkey PRIMARY KEY (uri);
+
+CREATE INDEX IF NOT EXISTS postgate_post_idx ON public.postgate USING btree (post);
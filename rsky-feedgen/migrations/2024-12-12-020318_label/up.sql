@@ -0,0 +1,29 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS public.label
+(
+    src         character varying NOT NULL,
+    uri         character varying NOT NULL,
+    cid         character varying,
+    val         character varying NOT NULL,
+    cts         character varying NOT NULL,
+    exp         character varying,
+    "indexedAt" character varying NOT NULL
+);
+
+ALTER TABLE ONLY public.label
+    ADD CONSTRAINT label_pkey PRIMARY KEY (src, uri, val);
+
+CREATE INDEX IF NOT EXISTS label_uri_idx ON public.label USING btree (uri);
+
+-- An empty labeler_did applies the preference to every labeler, like an undefined
+-- labelerDid on app.bsky.actor.defs#contentLabelPref
+CREATE TABLE IF NOT EXISTS public.label_preference
+(
+    did         character varying NOT NULL,
+    labeler_did character varying NOT NULL DEFAULT '',
+    label       character varying NOT NULL,
+    visibility  character varying NOT NULL
+);
+
+ALTER TABLE ONLY public.label_preference
+    ADD CONSTRAINT label_preference_pkey PRIMARY KEY (did, labeler_did, label);
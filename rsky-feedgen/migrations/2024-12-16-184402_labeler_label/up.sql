@@ -0,0 +1,17 @@
+-- Your SQL goes here
+-- Labels published by this service's own labeler. seq orders the
+-- com.atproto.label.subscribeLabels stream; negations are kept as rows of their own
+CREATE TABLE IF NOT EXISTS public.labeler_label
+(
+    seq bigserial PRIMARY KEY,
+    src character varying NOT NULL,
+    uri character varying NOT NULL,
+    cid character varying,
+    val character varying NOT NULL,
+    neg boolean NOT NULL DEFAULT false,
+    cts character varying NOT NULL,
+    exp character varying,
+    sig bytea NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS labeler_label_uri_val_idx ON public.labeler_label USING btree (uri, val);
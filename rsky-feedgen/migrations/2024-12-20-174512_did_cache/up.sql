@@ -0,0 +1,11 @@
+-- Your SQL goes here
+-- Resolved DID documents, shared by every feedgen worker. Timestamps are
+-- microseconds since the epoch; the oldest are evicted first
+CREATE TABLE IF NOT EXISTS public.did_cache
+(
+    did character varying PRIMARY KEY,
+    doc character varying NOT NULL,
+    updated_at bigint NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS did_cache_updated_at_idx ON public.did_cache USING btree (updated_at);
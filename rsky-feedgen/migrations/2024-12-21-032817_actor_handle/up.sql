@@ -0,0 +1,12 @@
+-- Your SQL goes here
+-- Latest handle seen for each DID on Jetstream identity events. handle is null
+-- when the DID's handle no longer verifies; seq orders out-of-order updates
+CREATE TABLE IF NOT EXISTS public.actor_handle
+(
+    did character varying PRIMARY KEY,
+    handle character varying,
+    seq bigint NOT NULL,
+    "indexedAt" character varying NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS actor_handle_handle_idx ON public.actor_handle USING btree (handle);
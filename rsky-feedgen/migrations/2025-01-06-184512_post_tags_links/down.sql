@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS public.post_tags_idx;
+ALTER TABLE public.post
+    DROP COLUMN IF EXISTS links,
+    DROP COLUMN IF EXISTS tags;
@@ -0,0 +1,7 @@
+-- Your SQL goes here
+-- Hashtags (without the '#') and link URIs from each post's facets and tags
+ALTER TABLE public.post
+    ADD COLUMN IF NOT EXISTS tags text[] NOT NULL DEFAULT '{}',
+    ADD COLUMN IF NOT EXISTS links text[] NOT NULL DEFAULT '{}';
+
+CREATE INDEX IF NOT EXISTS post_tags_idx ON public.post USING gin (tags);
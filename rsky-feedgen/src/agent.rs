@@ -1,4 +1,4 @@
-use crate::models::Follow;
+use crate::models::{Follow, ListItem};
 use bsky_sdk::api::com::atproto::repo::list_records::Record;
 use bsky_sdk::api::types::string::{AtIdentifier, Nsid};
 use bsky_sdk::api::types::Unknown;
@@ -7,47 +7,12 @@ use ipld_core::ipld::Ipld;
 use std::str::FromStr;
 
 #[tracing::instrument(skip(agent))]
-pub async fn get_follows(agent: &BskyAgent, did: &str) -> Vec<Follow> {
+async fn list_all_records(agent: &BskyAgent, did: &str, collection: &str) -> Vec<Record> {
     use bsky_sdk::api::com::atproto::repo::list_records::{Parameters, ParametersData};
     let mut records: Vec<Record> = Vec::new();
-    let mut follows = Vec::new();
     let mut cursor: Option<String> = None;
 
-    match agent
-        .api
-        .com
-        .atproto
-        .repo
-        .list_records(Parameters {
-            data: ParametersData {
-                collection: Nsid::new(String::from("app.bsky.graph.follow")).unwrap(),
-                cursor,
-                limit: None,
-                repo: AtIdentifier::from_str(did).unwrap(),
-                reverse: None,
-                rkey_end: None,
-                rkey_start: None,
-            },
-            extra_data: Ipld::Null,
-        })
-        .await
-    {
-        Ok(res) => {
-            cursor = res.cursor.clone();
-            records = res.records.clone();
-        }
-        Err(e) => {
-            tracing::error!(
-                "{}",
-                format!(
-                    "Error calling get following records: {x}",
-                    x = e.to_string()
-                )
-            );
-            cursor = None;
-        }
-    }
-    while cursor.is_some() {
+    loop {
         match agent
             .api
             .com
@@ -55,7 +20,7 @@ pub async fn get_follows(agent: &BskyAgent, did: &str) -> Vec<Follow> {
             .repo
             .list_records(Parameters {
                 data: ParametersData {
-                    collection: Nsid::new(String::from("app.bsky.graph.follow")).unwrap(),
+                    collection: Nsid::new(String::from(collection)).unwrap(),
                     cursor,
                     limit: None,
                     repo: AtIdentifier::from_str(did).unwrap(),
@@ -75,14 +40,25 @@ pub async fn get_follows(agent: &BskyAgent, did: &str) -> Vec<Follow> {
                 tracing::error!(
                     "{}",
                     format!(
-                        "Error calling get following records: {x}",
+                        "Error calling get {collection} records: {x}",
                         x = e.to_string()
                     )
                 );
                 cursor = None;
             }
         }
+        if cursor.is_none() {
+            break;
+        }
     }
+    records
+}
+
+#[tracing::instrument(skip(agent))]
+pub async fn get_follows(agent: &BskyAgent, did: &str) -> Vec<Follow> {
+    let mut records = list_all_records(agent, did, "app.bsky.graph.follow").await;
+    let mut follows = Vec::new();
+
     for record in records.iter_mut() {
         match record.value.clone() {
             Unknown::Object(obj) => {
@@ -136,6 +112,43 @@ pub async fn get_follows(agent: &BskyAgent, did: &str) -> Vec<Follow> {
     follows
 }
 
+#[tracing::instrument(skip(agent))]
+pub async fn get_list_items(agent: &BskyAgent, did: &str, list_uri: &str) -> Vec<ListItem> {
+    let records = list_all_records(agent, did, "app.bsky.graph.listitem").await;
+    let mut list_items = Vec::new();
+
+    for record in records.iter() {
+        if let Unknown::Object(obj) = record.value.clone() {
+            let field = |name: &str| -> Option<String> {
+                obj.get(name)
+                    .and_then(|x| <Ipld as Clone>::clone(x).try_into().ok())
+            };
+            if field("$type").as_deref() != Some("app.bsky.graph.listitem") {
+                continue;
+            }
+            if let (Some(subject), Some(list), Some(created_at)) =
+                (field("subject"), field("list"), field("createdAt"))
+            {
+                if list != list_uri {
+                    continue;
+                }
+                list_items.push(ListItem {
+                    uri: record.uri.clone(),
+                    cid: record.cid.as_ref().to_string(),
+                    author: did.to_string(),
+                    subject,
+                    list,
+                    created_at: created_at.clone(),
+                    indexed_at: created_at,
+                    prev: None,
+                    sequence: None,
+                });
+            }
+        }
+    }
+    list_items
+}
+
 pub async fn get_agent() -> Result<BskyAgent, bool> {
     let agent: BskyAgent = BskyAgent::builder().build().await.unwrap();
     Ok(agent)
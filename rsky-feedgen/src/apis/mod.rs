@@ -1,18 +1,32 @@
-use crate::agent::{get_agent, get_follows};
+use crate::agent::{get_agent, get_follows, get_list_items};
+use crate::auth::ID_RESOLVER;
 use crate::db::*;
-use crate::models::post_result::PostResultReason;
+use crate::labeler::{
+    self, LABELER, NO_ALT_TEXT, QUERY_LABELS_DEFAULT_LIMIT, QUERY_LABELS_MAX_LIMIT,
+};
 use crate::models::*;
 use crate::schema::follow::dsl as FollowSchema;
 use crate::schema::user_feed_preference::dsl as UserFeedSchema;
 use crate::schema::user_feed_preference::dsl::user_feed_preference;
 use crate::{ReadReplicaConn, WriteDbConn};
 use chrono::offset::Utc as UtcOffset;
-use chrono::{DateTime, NaiveDateTime, Utc};
+use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
 use diesel::prelude::*;
 use diesel::sql_query;
+use lazy_static::lazy_static;
+use rsky_identity::IdResolver;
 use rsky_lexicon::app::bsky::embed::Embeds;
-use std::collections::HashSet;
+use rsky_lexicon::app::bsky::feed::get_feed_skeleton::GetFeedSkeletonOutput;
+use rsky_lexicon::app::bsky::feed::{SkeletonFeedPost, SkeletonReason, SkeletonReasonRepost};
+use rsky_lexicon::app::bsky::richtext::rich_text::RichText;
+use rsky_lexicon::com::atproto::label::{Label, QueryLabelsOutput, SubscribeLabelsLabels};
+use rsky_lexicon::validation::{Format, ValidationError, ValidationErrorKind, ValidationErrors};
+use rsky_syntax::datetime::{format_datetime, normalize_datetime};
+use rsky_syntax::language::is_valid_language;
+use rsky_syntax::Tid;
+use std::collections::{BTreeMap, HashSet};
 use std::fmt::Write;
+use std::sync::{Mutex, MutexGuard};
 use std::time::SystemTime;
 
 const SHOW_REPLIES_FOR_FOLLOWING_ONLY: &str =
@@ -28,6 +42,10 @@ const HIDE_SEEN_POSTS: &str =
     "at://did:plc:cimwguwdlh2i2mebdqczgcyl/app.bsky.feed.post/3l7edu2ufdp2u";
 const HIDE_NOT_ALT_TEXT_POSTS: &str =
     "at://did:plc:cimwguwdlh2i2mebdqczgcyl/app.bsky.feed.post/3lbsxswsgus2f";
+/// How far a post's `createdAt` may precede the TID in its rkey before it counts as backdated
+const MAX_CREATED_AT_BACKDATE_MINUTES: i64 = 15;
+const STATS_DEFAULT_VISITORS: i64 = 25;
+const STATS_MAX_VISITORS: i64 = 250;
 const USER_PREF_OPTIONS: [&str; 6] = [
     RESET_PREF,
     DONT_SHOW_QUOTEPOSTS,
@@ -37,6 +55,11 @@ const USER_PREF_OPTIONS: [&str; 6] = [
     HIDE_NOT_ALT_TEXT_POSTS,
 ];
 
+lazy_static! {
+    /// Records skipped by `valid_records` since this worker started, by collection NSID.
+    static ref INVALID_RECORDS: Mutex<BTreeMap<String, u64>> = Mutex::new(BTreeMap::new());
+}
+
 fn update_seen_posts(did: &str, conn: &mut PgConnection) {
     println!("Checking if seen posts should be updated");
     let fetched_posts = get_fetched_posts(did, conn);
@@ -50,7 +73,24 @@ fn update_seen_posts(did: &str, conn: &mut PgConnection) {
     invalidate_fetched_posts(did, uri_list, conn);
 }
 
-fn post_media_query_str(following: &str) -> String {
+/// Condition excluding rows where any of `subjects` (record URIs or author DIDs) carries an
+/// unexpired label that the viewer has set to `hide`.
+fn hidden_label_filter_str(did: &str, subjects: &[&str]) -> String {
+    format!(
+        "not exists (select 1
+                   from label l
+                            join label_preference lp
+                                 on lp.label = l.val and (lp.labeler_did = '' or lp.labeler_did = l.src)
+                   where lp.did = '{did}'
+                     and lp.visibility = 'hide'
+                     and l.uri in ({subjects})
+                     and (l.exp is null or l.exp::timestamptz > now()))",
+        did = did,
+        subjects = subjects.join(", "),
+    )
+}
+
+fn post_media_query_str(following: &str, did: &str) -> String {
     format!(
         "select uri,
        \"indexedAt\",
@@ -91,9 +131,13 @@ p1.\"alt\"
       from post p1
       where p1.author in ({authors})
         and (p1.media is true)
+        and not exists (select 1 from threadgate t where t.post = p1.\"replyRoot\" and p1.uri = any(t.\"hiddenReplies\"))
+        and not exists (select 1 from postgate g where g.post = p1.\"quoteUri\" and p1.uri = any(g.\"detachedEmbeddingUris\"))
+        and {hidden_labels}
       group by p1.uri, p1.cid, p1.author) as x
 where true=true",
         authors = following,
+        hidden_labels = hidden_label_filter_str(did, &["p1.uri", "p1.author"]),
     )
 }
 
@@ -153,6 +197,11 @@ p1.\"alt\"
         and ({replies_included} or p1.\"replyParent\" is null)
         and s1.id is null
         and ({all_replies} or p2.author is null or (p2.author in ({authors})))
+        and p1.author not in (select b.subject from block b where b.author = '{did}')
+        and p1.author not in (select b.author from block b where b.subject = '{did}')
+        and not exists (select 1 from threadgate t where t.post = p1.\"replyRoot\" and p1.uri = any(t.\"hiddenReplies\"))
+        and not exists (select 1 from postgate g where g.post = p1.\"quoteUri\" and p1.uri = any(g.\"detachedEmbeddingUris\"))
+        and {hidden_labels}
       group by p1.uri, p1.cid, p1.author) as x
 where (\"replyParent\" is null or likeCount >= {like_threshold})",
             authors = following,
@@ -160,6 +209,7 @@ where (\"replyParent\" is null or likeCount >= {like_threshold})",
             replies_included = user_config.show_replies,
             all_replies = !user_config.reply_filter_followed_only,
             like_threshold = user_config.reply_filter_likes,
+            hidden_labels = hidden_label_filter_str(did, &["p1.uri", "p1.author"]),
             did = did
         )
     } else {
@@ -209,13 +259,20 @@ p1.\"alt\"
         and ({hide_no_alt_text}=false or p1.\"media\" is false or p1.\"alt\" is not null)
         and ({replies_included} or p1.\"replyParent\" is null)
         and ({all_replies} or p2.author is null or (p2.author in ({authors})))
+        and p1.author not in (select b.subject from block b where b.author = '{did}')
+        and p1.author not in (select b.author from block b where b.subject = '{did}')
+        and not exists (select 1 from threadgate t where t.post = p1.\"replyRoot\" and p1.uri = any(t.\"hiddenReplies\"))
+        and not exists (select 1 from postgate g where g.post = p1.\"quoteUri\" and p1.uri = any(g.\"detachedEmbeddingUris\"))
+        and {hidden_labels}
       group by p1.uri, p1.cid, p1.author) as x
 where (\"replyParent\" is null or likeCount >= {like_threshold})",
             authors = following,
             quotes_included = user_config.show_quote_posts,
             replies_included = user_config.show_replies,
             all_replies = !user_config.reply_filter_followed_only,
-            like_threshold = user_config.reply_filter_likes
+            like_threshold = user_config.reply_filter_likes,
+            hidden_labels = hidden_label_filter_str(did, &["p1.uri", "p1.author"]),
+            did = did
         )
     }
 }
@@ -251,8 +308,21 @@ from (select r1.uri as uri,
              r1.\"sequence\"
       from repost r1
           LEFT OUTER JOIN seen_post s1 ON s1.did = '{did}' and s1.uri = r1.uri
-      where r1.author in ({authors}) and s1.id is null) as x",
+      where r1.author in ({authors}) and s1.id is null
+        and r1.author not in (select b.subject from block b where b.author = '{did}')
+        and r1.author not in (select b.author from block b where b.subject = '{did}')
+        and split_part(r1.\"subjectUri\", '/', 3) not in (select b.subject from block b where b.author = '{did}')
+        and split_part(r1.\"subjectUri\", '/', 3) not in (select b.author from block b where b.subject = '{did}')
+        and {hidden_labels}) as x",
             authors = following_reposts_string,
+            hidden_labels = hidden_label_filter_str(
+                did,
+                &[
+                    "r1.\"subjectUri\"",
+                    "split_part(r1.\"subjectUri\", '/', 3)",
+                    "r1.author",
+                ],
+            ),
             did = did
         )
     } else {
@@ -285,8 +355,22 @@ from (select r1.uri as uri,
              r1.\"sequence\"
       from repost r1
       where r1.author in ({authors})
+        and r1.author not in (select b.subject from block b where b.author = '{did}')
+        and r1.author not in (select b.author from block b where b.subject = '{did}')
+        and split_part(r1.\"subjectUri\", '/', 3) not in (select b.subject from block b where b.author = '{did}')
+        and split_part(r1.\"subjectUri\", '/', 3) not in (select b.author from block b where b.subject = '{did}')
+        and {hidden_labels}
       ) as x",
-            authors = following_reposts_string
+            authors = following_reposts_string,
+            hidden_labels = hidden_label_filter_str(
+                did,
+                &[
+                    "r1.\"subjectUri\"",
+                    "split_part(r1.\"subjectUri\", '/', 3)",
+                    "r1.author",
+                ],
+            ),
+            did = did
         )
     }
 }
@@ -297,14 +381,7 @@ pub async fn get_posts_by_user_feed(
     _limit: Option<i64>,
     params_cursor: Option<&str>,
     connection: ReadReplicaConn,
-) -> Result<AlgoResponse, ValidationErrorMessageResponse> {
-    let limit: i64 = _limit.unwrap_or(30);
-    let params_cursor = match params_cursor {
-        None => None,
-        Some(params_cursor) => Some(params_cursor.to_string()),
-    };
-    let mut following = String::from("");
-
+) -> Result<GetFeedSkeletonOutput, ValidationErrorMessageResponse> {
     let mut follow_dids = get_saved_follows(did.clone(), &connection).await;
     if follow_dids.is_empty() {
         tracing::info!("Creating followers for {}", did);
@@ -318,14 +395,64 @@ pub async fn get_posts_by_user_feed(
         follow_dids = get_saved_follows(did.clone(), &connection).await;
     }
 
-    if follow_dids.is_empty() {
-        return Ok(AlgoResponse {
+    get_posts_by_authors(did, follow_dids, _limit, params_cursor, connection).await
+}
+
+#[tracing::instrument(skip(connection))]
+pub async fn get_posts_by_list_feed(
+    did: String,
+    _limit: Option<i64>,
+    params_cursor: Option<&str>,
+    connection: ReadReplicaConn,
+) -> Result<GetFeedSkeletonOutput, ValidationErrorMessageResponse> {
+    let list_uri = match get_list_feed_preference(did.clone(), &connection).await {
+        Some(preference) => preference.list_uri,
+        None => {
+            return Ok(GetFeedSkeletonOutput {
+                cursor: None,
+                feed: Vec::new(),
+                req_id: None,
+            })
+        }
+    };
+
+    let mut member_dids = get_saved_list_members(list_uri.clone(), &connection).await;
+    if member_dids.is_empty() {
+        tracing::info!("Creating list items for {}", list_uri);
+        let agent = get_agent().await.unwrap();
+        let list_items = get_list_items(&agent, did.as_str(), list_uri.as_str()).await;
+        connection
+            .run(move |conn| {
+                insert_list_items(list_items, conn);
+            })
+            .await;
+        member_dids = get_saved_list_members(list_uri, &connection).await;
+    }
+
+    get_posts_by_authors(did, member_dids, _limit, params_cursor, connection).await
+}
+
+/// Chronological feed over `author_dids`, applying the viewer's `UserFeedPreference` filters.
+async fn get_posts_by_authors(
+    did: String,
+    author_dids: Vec<String>,
+    _limit: Option<i64>,
+    params_cursor: Option<&str>,
+    connection: ReadReplicaConn,
+) -> Result<GetFeedSkeletonOutput, ValidationErrorMessageResponse> {
+    let limit: i64 = _limit.unwrap_or(30);
+    let params_cursor = params_cursor.map(str::to_string);
+    let mut following = String::from("");
+
+    if author_dids.is_empty() {
+        return Ok(GetFeedSkeletonOutput {
             cursor: None,
             feed: Vec::new(),
+            req_id: None,
         });
     }
 
-    for follow_did in follow_dids.iter() {
+    for follow_did in author_dids.iter() {
         following += ("\'".to_string() + follow_did.as_str() + "\',").as_str();
     }
     following.pop();
@@ -358,7 +485,7 @@ pub async fn get_posts_by_user_feed(
             }
 
             let following_preferences = get_following_preferences2(did.clone(), conn);
-            let mut following_reposts: HashSet<String> = follow_dids.iter().cloned().collect();
+            let mut following_reposts: HashSet<String> = author_dids.iter().cloned().collect();
             let mut following_reposts_string = String::from("");
             for following_preference in following_preferences {
                 if !following_preference.show_reposts {
@@ -392,7 +519,7 @@ pub async fn get_posts_by_user_feed(
                     .map(String::from)
                     .collect::<Vec<_>>();
                 if let [indexed_at_c, _cid_c] = &v[..] {
-                    if let Ok(timestamp) = indexed_at_c.parse::<i64>() {
+                    if let Some(timestamp) = cursor_timestamp(indexed_at_c) {
                         let nanoseconds = 230 * 1000000;
                         let datetime = DateTime::<Utc>::from_utc(
                             NaiveDateTime::from_timestamp(timestamp / 1000, nanoseconds),
@@ -437,10 +564,14 @@ pub async fn get_posts_by_user_feed(
                     let fmt = "%+";
                     let a_date = NaiveDateTime::parse_from_str(a.indexed_at.as_str(), fmt).unwrap();
                     let b_date = NaiveDateTime::parse_from_str(b.indexed_at.as_str(), fmt).unwrap();
+                    // Posts and reposts indexed in the same second fall back to rkey order, so
+                    // paging doesn't shuffle them
                     b_date
                         .and_utc()
                         .timestamp()
                         .cmp(&a_date.and_utc().timestamp())
+                        .then_with(|| rkey_tid(&b.uri).cmp(&rkey_tid(&a.uri)))
+                        .then_with(|| b.uri.cmp(&a.uri))
                 });
             }
 
@@ -472,22 +603,20 @@ pub async fn get_posts_by_user_feed(
                 .clone()
                 .into_iter()
                 .map(|result| {
-                    let post_result;
-                    if result.quote_uri.is_some() {
-                        let reason = PostResultReason {
-                            reason_type: "app.bsky.feed.defs#skeletonReasonRepost".to_string(),
-                            repost_uri: result.uri,
-                        };
-                        post_result = PostResult {
-                            post: result.quote_uri.unwrap(),
-                            reason: Some(reason),
-                        };
-                    } else {
-                        post_result = PostResult {
+                    let post_result = match result.quote_uri {
+                        Some(quote_uri) => SkeletonFeedPost {
+                            post: quote_uri,
+                            reason: Some(SkeletonReason::SkeletonReasonRepost(
+                                SkeletonReasonRepost { repost: result.uri },
+                            )),
+                            feed_context: None,
+                        },
+                        None => SkeletonFeedPost {
                             post: result.uri,
                             reason: None,
-                        };
-                    }
+                            feed_context: None,
+                        },
+                    };
                     post_results.push(post_result);
                 })
                 .for_each(drop);
@@ -504,9 +633,10 @@ pub async fn get_posts_by_user_feed(
                 insert_fetched_posts(fetched_posts, conn);
             }
 
-            let new_response = AlgoResponse {
+            let new_response = GetFeedSkeletonOutput {
                 cursor,
                 feed: post_results,
+                req_id: None,
             };
             Ok(new_response)
         })
@@ -514,13 +644,68 @@ pub async fn get_posts_by_user_feed(
     result
 }
 
+/// The TID a record is keyed by, if any
+fn rkey_tid(uri: &str) -> Option<Tid> {
+    uri.rsplit('/').next()?.parse().ok()
+}
+
+/// The millisecond timestamp in a feed cursor. An rkey TID works too, to page from a given post.
+fn cursor_timestamp(cursor: &str) -> Option<i64> {
+    cursor
+        .parse::<i64>()
+        .ok()
+        .or_else(|| Some((cursor.parse::<Tid>().ok()?.timestamp() / 1000) as i64))
+}
+
+/// A post's `createdAt`, unless it claims to be much older than the TID in its rkey, in which
+/// case the rkey's time is used
+fn post_created_at(uri: &str, created_at: DateTime<Utc>) -> DateTime<Utc> {
+    match rkey_tid(uri).map(|tid| tid.created_at()) {
+        Some(rkey_created_at)
+            if created_at
+                < rkey_created_at - chrono::Duration::minutes(MAX_CREATED_AT_BACKDATE_MINUTES) =>
+        {
+            tracing::warn!(
+                "@LOG: Backdated post {uri:?}: createdAt {created_at} but rkey from {rkey_created_at}"
+            );
+            rkey_created_at
+        }
+        _ => created_at,
+    }
+}
+
+/// A record's `createdAt`, normalized to the atproto datetime profile. Anything else is flagged
+/// and replaced by when the record was indexed, so it can't skew time-based sorting.
+fn normalize_created_at(uri: &str, created_at: &str, indexed_at: &DateTime<Utc>) -> String {
+    match normalize_datetime(created_at) {
+        Ok(created_at) => created_at,
+        Err(error) => {
+            tracing::warn!("@LOG: Invalid createdAt on {uri:?}: {error}");
+            format_datetime(indexed_at)
+        }
+    }
+}
+
+/// A post's languages as stored, keeping only well-formed BCP-47 tags
+fn post_lang(uri: &str, langs: Option<Vec<String>>) -> Option<String> {
+    let (valid, invalid): (Vec<String>, Vec<String>) =
+        langs?.into_iter().partition(|lang| is_valid_language(lang));
+    if !invalid.is_empty() {
+        tracing::warn!("@LOG: Dropping invalid langs on {uri:?}: {invalid:?}");
+    }
+    match valid.is_empty() {
+        true => None,
+        false => Some(valid.join(",")),
+    }
+}
+
 #[tracing::instrument(skip(connection))]
 pub async fn get_posts_by_following_media(
     did: String,
     _limit: Option<i64>,
     params_cursor: Option<&str>,
     connection: ReadReplicaConn,
-) -> Result<AlgoResponse, ValidationErrorMessageResponse> {
+) -> Result<GetFeedSkeletonOutput, ValidationErrorMessageResponse> {
     let limit: i64 = _limit.unwrap_or(30);
     let params_cursor = match params_cursor {
         None => None,
@@ -542,9 +727,10 @@ pub async fn get_posts_by_following_media(
     }
 
     if follow_dids.len() == 0 {
-        return Ok(AlgoResponse {
+        return Ok(GetFeedSkeletonOutput {
             cursor: None,
             feed: Vec::new(),
+            req_id: None,
         });
     }
 
@@ -555,7 +741,7 @@ pub async fn get_posts_by_following_media(
 
     let result = connection
         .run(move |conn| {
-            let mut query_str: String = post_media_query_str(following.as_str());
+            let mut query_str: String = post_media_query_str(following.as_str(), did.as_str());
 
             if params_cursor.is_some() {
                 let cursor_str = params_cursor.unwrap();
@@ -565,7 +751,7 @@ pub async fn get_posts_by_following_media(
                     .map(String::from)
                     .collect::<Vec<_>>();
                 if let [indexed_at_c, _cid_c] = &v[..] {
-                    if let Ok(timestamp) = indexed_at_c.parse::<i64>() {
+                    if let Some(timestamp) = cursor_timestamp(indexed_at_c) {
                         let nanoseconds = 230 * 1000000;
                         let datetime = DateTime::<Utc>::from_utc(
                             NaiveDateTime::from_timestamp(timestamp / 1000, nanoseconds),
@@ -614,29 +800,28 @@ pub async fn get_posts_by_following_media(
                 .clone()
                 .into_iter()
                 .map(|result| {
-                    let post_result;
-                    if result.quote_uri.is_some() {
-                        let reason = PostResultReason {
-                            reason_type: "app.bsky.feed.defs#skeletonReasonRepost".to_string(),
-                            repost_uri: result.uri,
-                        };
-                        post_result = PostResult {
-                            post: result.quote_uri.unwrap(),
-                            reason: Some(reason),
-                        };
-                    } else {
-                        post_result = PostResult {
+                    let post_result = match result.quote_uri {
+                        Some(quote_uri) => SkeletonFeedPost {
+                            post: quote_uri,
+                            reason: Some(SkeletonReason::SkeletonReasonRepost(
+                                SkeletonReasonRepost { repost: result.uri },
+                            )),
+                            feed_context: None,
+                        },
+                        None => SkeletonFeedPost {
                             post: result.uri,
                             reason: None,
-                        };
-                    }
+                            feed_context: None,
+                        },
+                    };
                     post_results.push(post_result);
                 })
                 .for_each(drop);
 
-            let new_response = AlgoResponse {
+            let new_response = GetFeedSkeletonOutput {
                 cursor,
                 feed: post_results,
+                req_id: None,
             };
             Ok(new_response)
         })
@@ -644,11 +829,68 @@ pub async fn get_posts_by_following_media(
     result
 }
 
+/// Whether an embed carries media, and the alt text given for it. For several images this is
+/// the last non-empty alt.
+fn embed_media_alt(embed: &Embeds) -> (bool, Option<String>) {
+    match embed {
+        Embeds::Images(e) => (
+            true,
+            e.images
+                .iter()
+                .rev()
+                .map(|image| image.alt.clone())
+                .find(|alt| !alt.is_empty()),
+        ),
+        Embeds::Video(e) => (true, e.alt.clone()),
+        _ => (false, None),
+    }
+}
+
+/// Hashtags and links to index for a post, each once: those in its facets, or detected in its
+/// text for clients that don't write facets, plus any extra `tags` on the record.
+fn post_tags_links(post: &rsky_lexicon::app::bsky::feed::Post) -> (Vec<String>, Vec<String>) {
+    let mut rich_text = RichText {
+        text: post.text.clone(),
+        facets: post.facets.clone().unwrap_or_default(),
+    };
+    if rich_text.facets.is_empty() {
+        rich_text.detect_facets_without_resolution();
+    }
+    let extra_tags = post.tags.iter().flatten().map(String::as_str);
+    (
+        unique(rich_text.tags().chain(extra_tags)),
+        unique(rich_text.links()),
+    )
+}
+
+fn unique<'a>(values: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    values
+        .filter(|value| seen.insert(*value))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Publishes or negates `no-alt-text` for posts whose media or alt text was just indexed.
+fn sync_alt_text_labels(alt_text_checks: Vec<(String, String, bool)>, conn: &mut PgConnection) {
+    if let Some(labeler) = LABELER.as_ref() {
+        for (uri, cid, missing_alt) in alt_text_checks {
+            labeler.sync_label(&uri, &cid, NO_ALT_TEXT, missing_alt, conn);
+        }
+    }
+}
+
 fn queue_post_creation(body: Vec<CreateRequest>, conn: &mut PgConnection) {
+    use crate::schema::label::dsl as LabelSchema;
     use crate::schema::post::dsl as PostSchema;
     use crate::schema::user_feed_preference::dsl as UserFeedSchema;
+    use crate::schema::video::dsl as VideoSchema;
+    use rsky_lexicon::app::bsky::feed::PostLabels;
 
     let mut new_posts = Vec::new();
+    let mut new_labels = Vec::new();
+    let mut new_videos = Vec::new();
+    let mut alt_text_checks = Vec::new();
 
     body.into_iter()
         .map(|req| {
@@ -676,30 +918,53 @@ fn queue_post_creation(body: Vec<CreateRequest>, conn: &mut PgConnection) {
                 quote_uri: None,
                 media: false,
                 alt: None,
+                tags: Vec::new(),
+                links: Vec::new(),
             };
 
             if let Lexicon::AppBskyFeedPost(post_record) = req.record {
                 post_text_original = post_record.text.clone();
+                (new_post.tags, new_post.links) = post_tags_links(&post_record);
+                let created_at = post_created_at(&new_post.uri, post_record.created_at);
+                let created_at = format_datetime(&created_at);
+                let mut self_labels = Vec::new();
+                if let Some(PostLabels::SelfLabels(labels)) = post_record.labels {
+                    for self_label in labels.values {
+                        // Self-labels are stored like any other label, with the author as source
+                        new_labels.push((
+                            LabelSchema::src.eq(new_post.author.clone()),
+                            LabelSchema::uri.eq(new_post.uri.clone()),
+                            LabelSchema::cid.eq(Some(new_post.cid.clone())),
+                            LabelSchema::val.eq(self_label.val.clone()),
+                            LabelSchema::cts.eq(created_at.clone()),
+                            LabelSchema::exp.eq(None::<String>),
+                            LabelSchema::indexedAt.eq(new_post.indexed_at.clone()),
+                        ));
+                        self_labels.push(Some(self_label.val));
+                    }
+                }
                 if let Some(reply) = post_record.reply {
                     new_post.reply_parent = Some(reply.parent.uri);
                     new_post.reply_root = Some(reply.root.uri);
                 }
-                if let Some(langs) = post_record.langs {
-                    new_post.lang = Some(langs.join(","));
-                }
+                new_post.lang = post_lang(&new_post.uri, post_record.langs);
                 if let Some(embed) = post_record.embed {
+                    (post_media_original, post_alt_original) = embed_media_alt(&embed);
                     match embed {
-                        Embeds::Images(e) => {
-                            post_media_original = true;
-                            for image in e.images {
-                                if image.alt != "" {
-                                    post_alt_original = Some(image.alt);
-                                }
-                            }
-                        }
+                        // Unknown embed types don't map onto any post column
+                        Embeds::Images(_) | Embeds::Unknown { .. } => {}
                         Embeds::Video(e) => {
-                            post_media_original = true;
-                            post_alt_original = e.alt;
+                            if let Some(video_ref) = e.video.r#ref {
+                                new_videos.push((
+                                    VideoSchema::cid.eq(video_ref.link),
+                                    VideoSchema::alt.eq(e.alt.clone()),
+                                    VideoSchema::postCid.eq(new_post.cid.clone()),
+                                    VideoSchema::postUri.eq(new_post.uri.clone()),
+                                    VideoSchema::createdAt.eq(created_at.clone()),
+                                    VideoSchema::indexedAt.eq(new_post.indexed_at.clone()),
+                                    VideoSchema::labels.eq(Some(self_labels.clone())),
+                                ));
+                            }
                         }
                         Embeds::RecordWithMedia(e) => {}
                         Embeds::External(e) => {
@@ -723,6 +988,13 @@ fn queue_post_creation(body: Vec<CreateRequest>, conn: &mut PgConnection) {
             new_post.text = Some(post_text_original);
             new_post.media = post_media_original;
             new_post.alt = post_alt_original;
+            if new_post.media {
+                alt_text_checks.push((
+                    new_post.uri.clone(),
+                    new_post.cid.clone(),
+                    new_post.alt.is_none(),
+                ));
+            }
 
             match new_post.reply_parent {
                 None => {}
@@ -794,6 +1066,8 @@ fn queue_post_creation(body: Vec<CreateRequest>, conn: &mut PgConnection) {
                 PostSchema::quoteUri.eq(new_post.quote_uri),
                 PostSchema::media.eq(new_post.media),
                 PostSchema::alt.eq(new_post.alt),
+                PostSchema::tags.eq(new_post.tags),
+                PostSchema::links.eq(new_post.links),
             );
             new_posts.push(new_post);
         })
@@ -805,6 +1079,62 @@ fn queue_post_creation(body: Vec<CreateRequest>, conn: &mut PgConnection) {
         .do_nothing()
         .execute(conn)
         .expect("Error inserting post records");
+
+    if !new_labels.is_empty() {
+        diesel::insert_into(LabelSchema::label)
+            .values(&new_labels)
+            .on_conflict((LabelSchema::src, LabelSchema::uri, LabelSchema::val))
+            .do_nothing()
+            .execute(conn)
+            .expect("Error inserting label records");
+    }
+
+    if !new_videos.is_empty() {
+        diesel::insert_into(VideoSchema::video)
+            .values(&new_videos)
+            .on_conflict(VideoSchema::cid)
+            .do_nothing()
+            .execute(conn)
+            .expect("Error inserting video records");
+    }
+
+    sync_alt_text_labels(alt_text_checks, conn);
+}
+
+/// Re-indexes edited posts. Only the record's content changes; replies and quotes stay as
+/// they were created.
+fn queue_post_update(body: Vec<CreateRequest>, conn: &mut PgConnection) {
+    use crate::schema::post::dsl as PostSchema;
+
+    let mut alt_text_checks = Vec::new();
+    for req in body {
+        if let Lexicon::AppBskyFeedPost(post_record) = req.record {
+            let (media, alt) = match post_record.embed {
+                Some(ref embed) => embed_media_alt(embed),
+                None => (false, None),
+            };
+            let (tags, links) = post_tags_links(&post_record);
+            let updated = diesel::update(PostSchema::post)
+                .filter(PostSchema::uri.eq(&req.uri))
+                .set((
+                    PostSchema::cid.eq(&req.cid),
+                    PostSchema::text.eq(Some(post_record.text)),
+                    PostSchema::lang.eq(post_lang(&req.uri, post_record.langs)),
+                    PostSchema::media.eq(media),
+                    PostSchema::alt.eq(alt.clone()),
+                    PostSchema::tags.eq(tags),
+                    PostSchema::links.eq(links),
+                ))
+                .execute(conn)
+                .expect("Error updating post records");
+            // Edits to posts that were never indexed have nothing to relabel
+            if updated > 0 {
+                alt_text_checks.push((req.uri, req.cid, media && alt.is_none()));
+            }
+        }
+    }
+
+    sync_alt_text_labels(alt_text_checks, conn);
 }
 
 fn queue_repost_creation(body: Vec<CreateRequest>, conn: &mut PgConnection) {
@@ -817,14 +1147,16 @@ fn queue_repost_creation(body: Vec<CreateRequest>, conn: &mut PgConnection) {
             if let Lexicon::AppBskyFeedRepost(repost_record) = req.record {
                 let system_time = SystemTime::now();
                 let dt: DateTime<UtcOffset> = system_time.into();
+                let indexed_at = format!("{}", dt.format("%+"));
+                let created_at = normalize_created_at(&req.uri, &repost_record.created_at, &dt);
                 let new_like = (
                     RepostSchema::uri.eq(req.uri),
                     RepostSchema::cid.eq(req.cid),
                     RepostSchema::author.eq(req.author),
                     RepostSchema::subjectCid.eq(repost_record.subject.cid),
                     RepostSchema::subjectUri.eq(repost_record.subject.uri),
-                    RepostSchema::createdAt.eq(repost_record.created_at),
-                    RepostSchema::indexedAt.eq(format!("{}", dt.format("%+"))),
+                    RepostSchema::createdAt.eq(created_at),
+                    RepostSchema::indexedAt.eq(indexed_at),
                     RepostSchema::prev.eq(req.prev),
                     RepostSchema::sequence.eq(req.sequence),
                 );
@@ -917,14 +1249,16 @@ fn queue_like_creation(body: Vec<CreateRequest>, conn: &mut PgConnection) {
 
                 let system_time = SystemTime::now();
                 let dt: DateTime<UtcOffset> = system_time.into();
+                let indexed_at = format!("{}", dt.format("%+"));
+                let created_at = normalize_created_at(&req.uri, &like_record.created_at, &dt);
                 let new_like = (
                     LikeSchema::uri.eq(req.uri),
                     LikeSchema::cid.eq(req.cid),
                     LikeSchema::author.eq(req.author),
                     LikeSchema::subjectCid.eq(like_record.subject.cid),
                     LikeSchema::subjectUri.eq(like_record.subject.uri),
-                    LikeSchema::createdAt.eq(like_record.created_at),
-                    LikeSchema::indexedAt.eq(format!("{}", dt.format("%+"))),
+                    LikeSchema::createdAt.eq(created_at),
+                    LikeSchema::indexedAt.eq(indexed_at),
                     LikeSchema::prev.eq(req.prev),
                     LikeSchema::sequence.eq(req.sequence),
                 );
@@ -950,13 +1284,15 @@ fn queue_follow_creation(body: Vec<CreateRequest>, conn: &mut PgConnection) {
                 if let Lexicon::AppBskyFeedFollow(follow_record) = req.record {
                     let system_time = SystemTime::now();
                     let dt: DateTime<UtcOffset> = system_time.into();
+                    let indexed_at = format!("{}", dt.format("%+"));
+                    let created_at = normalize_created_at(&req.uri, &follow_record.created_at, &dt);
                     let new_follow = (
                         FollowSchema::uri.eq(req.uri),
                         FollowSchema::cid.eq(req.cid),
                         FollowSchema::author.eq(req.author),
                         FollowSchema::subject.eq(follow_record.subject),
-                        FollowSchema::createdAt.eq(follow_record.created_at),
-                        FollowSchema::indexedAt.eq(format!("{}", dt.format("%+"))),
+                        FollowSchema::createdAt.eq(created_at),
+                        FollowSchema::indexedAt.eq(indexed_at),
                         FollowSchema::prev.eq(req.prev),
                         FollowSchema::sequence.eq(req.sequence),
                     );
@@ -976,11 +1312,343 @@ fn queue_follow_creation(body: Vec<CreateRequest>, conn: &mut PgConnection) {
     }
 }
 
+fn queue_block_creation(body: Vec<CreateRequest>, conn: &mut PgConnection) {
+    use crate::schema::block::dsl as BlockSchema;
+
+    let mut new_blocks = Vec::new();
+
+    body.into_iter()
+        .map(|req| {
+            if let Lexicon::AppBskyGraphBlock(block_record) = req.record {
+                // Only blocks touching a feed user matter, in either direction
+                if user_follows_indexed(req.author.as_str(), conn)
+                    || user_follows_indexed(block_record.subject.as_str(), conn)
+                {
+                    let system_time = SystemTime::now();
+                    let dt: DateTime<UtcOffset> = system_time.into();
+                    let indexed_at = format!("{}", dt.format("%+"));
+                    let created_at = normalize_created_at(&req.uri, &block_record.created_at, &dt);
+                    let new_block = (
+                        BlockSchema::uri.eq(req.uri),
+                        BlockSchema::cid.eq(req.cid),
+                        BlockSchema::author.eq(req.author),
+                        BlockSchema::subject.eq(block_record.subject),
+                        BlockSchema::createdAt.eq(created_at),
+                        BlockSchema::indexedAt.eq(indexed_at),
+                        BlockSchema::prev.eq(req.prev),
+                        BlockSchema::sequence.eq(req.sequence),
+                    );
+                    new_blocks.push(new_block);
+                }
+            }
+        })
+        .for_each(drop);
+
+    if !new_blocks.is_empty() {
+        diesel::insert_into(BlockSchema::block)
+            .values(&new_blocks)
+            .on_conflict(BlockSchema::uri)
+            .do_nothing()
+            .execute(conn)
+            .expect("Error inserting block records");
+    }
+}
+
+fn queue_list_creation(body: Vec<CreateRequest>, conn: &mut PgConnection) {
+    use crate::schema::list::dsl as ListSchema;
+    use rsky_lexicon::app::bsky::graph::ListPurpose;
+
+    let mut new_lists = Vec::new();
+
+    body.into_iter()
+        .map(|req| {
+            if user_follows_indexed(req.author.as_str(), conn) {
+                if let Lexicon::AppBskyGraphList(list_record) = req.record {
+                    let purpose = match list_record.purpose {
+                        ListPurpose::ModList => "app.bsky.graph.defs#modlist",
+                        ListPurpose::CurateList => "app.bsky.graph.defs#curatelist",
+                        ListPurpose::ReferenceList => "app.bsky.graph.defs#referencelist",
+                    };
+                    let system_time = SystemTime::now();
+                    let dt: DateTime<UtcOffset> = system_time.into();
+                    let indexed_at = format!("{}", dt.format("%+"));
+                    let created_at = normalize_created_at(&req.uri, &list_record.created_at, &dt);
+                    let new_list = (
+                        ListSchema::uri.eq(req.uri),
+                        ListSchema::cid.eq(req.cid),
+                        ListSchema::author.eq(req.author),
+                        ListSchema::name.eq(list_record.name),
+                        ListSchema::purpose.eq(purpose.to_string()),
+                        ListSchema::description.eq(list_record.description),
+                        ListSchema::createdAt.eq(created_at),
+                        ListSchema::indexedAt.eq(indexed_at),
+                        ListSchema::prev.eq(req.prev),
+                        ListSchema::sequence.eq(req.sequence),
+                    );
+                    new_lists.push(new_list);
+                }
+            }
+        })
+        .for_each(drop);
+
+    if !new_lists.is_empty() {
+        diesel::insert_into(ListSchema::list)
+            .values(&new_lists)
+            .on_conflict(ListSchema::uri)
+            .do_nothing()
+            .execute(conn)
+            .expect("Error inserting list records");
+    }
+}
+
+fn queue_listitem_creation(body: Vec<CreateRequest>, conn: &mut PgConnection) {
+    use crate::schema::listitem::dsl as ListItemSchema;
+
+    let mut new_listitems = Vec::new();
+
+    body.into_iter()
+        .map(|req| {
+            if user_follows_indexed(req.author.as_str(), conn)
+                || user_list_feed_indexed(req.author.as_str(), conn)
+            {
+                if let Lexicon::AppBskyGraphListItem(listitem_record) = req.record {
+                    let system_time = SystemTime::now();
+                    let dt: DateTime<UtcOffset> = system_time.into();
+                    let indexed_at = format!("{}", dt.format("%+"));
+                    let created_at =
+                        normalize_created_at(&req.uri, &listitem_record.created_at, &dt);
+                    let new_listitem = (
+                        ListItemSchema::uri.eq(req.uri),
+                        ListItemSchema::cid.eq(req.cid),
+                        ListItemSchema::author.eq(req.author),
+                        ListItemSchema::subject.eq(listitem_record.subject),
+                        ListItemSchema::list.eq(listitem_record.list),
+                        ListItemSchema::createdAt.eq(created_at),
+                        ListItemSchema::indexedAt.eq(indexed_at),
+                        ListItemSchema::prev.eq(req.prev),
+                        ListItemSchema::sequence.eq(req.sequence),
+                    );
+                    new_listitems.push(new_listitem);
+                }
+            }
+        })
+        .for_each(drop);
+
+    if !new_listitems.is_empty() {
+        diesel::insert_into(ListItemSchema::listitem)
+            .values(&new_listitems)
+            .on_conflict(ListItemSchema::uri)
+            .do_nothing()
+            .execute(conn)
+            .expect("Error inserting listitem records");
+    }
+}
+
+fn queue_threadgate_creation(body: Vec<CreateRequest>, conn: &mut PgConnection) {
+    use crate::schema::threadgate::dsl as ThreadgateSchema;
+    use diesel::upsert::excluded;
+
+    let mut new_threadgates = Vec::new();
+
+    body.into_iter()
+        .map(|req| {
+            if let Lexicon::AppBskyFeedThreadgate(threadgate_record) = req.record {
+                // Only gates on a feed user's own posts are kept
+                if user_follows_indexed(req.author.as_str(), conn) {
+                    let system_time = SystemTime::now();
+                    let dt: DateTime<UtcOffset> = system_time.into();
+                    let indexed_at = format!("{}", dt.format("%+"));
+                    let created_at =
+                        normalize_created_at(&req.uri, &threadgate_record.created_at, &dt);
+                    let new_threadgate = (
+                        ThreadgateSchema::uri.eq(req.uri),
+                        ThreadgateSchema::cid.eq(req.cid),
+                        ThreadgateSchema::author.eq(req.author),
+                        ThreadgateSchema::post.eq(threadgate_record.post),
+                        ThreadgateSchema::hiddenReplies
+                            .eq(threadgate_record.hidden_replies.unwrap_or_default()),
+                        ThreadgateSchema::createdAt.eq(created_at),
+                        ThreadgateSchema::indexedAt.eq(indexed_at),
+                    );
+                    new_threadgates.push(new_threadgate);
+                }
+            }
+        })
+        .for_each(drop);
+
+    // Threadgates are edited in place, so the latest version replaces the stored one
+    if !new_threadgates.is_empty() {
+        diesel::insert_into(ThreadgateSchema::threadgate)
+            .values(&new_threadgates)
+            .on_conflict(ThreadgateSchema::uri)
+            .do_update()
+            .set((
+                ThreadgateSchema::cid.eq(excluded(ThreadgateSchema::cid)),
+                ThreadgateSchema::hiddenReplies.eq(excluded(ThreadgateSchema::hiddenReplies)),
+                ThreadgateSchema::indexedAt.eq(excluded(ThreadgateSchema::indexedAt)),
+            ))
+            .execute(conn)
+            .expect("Error inserting threadgate records");
+    }
+}
+
+fn queue_postgate_creation(body: Vec<CreateRequest>, conn: &mut PgConnection) {
+    use crate::schema::postgate::dsl as PostgateSchema;
+    use diesel::upsert::excluded;
+
+    let mut new_postgates = Vec::new();
+
+    body.into_iter()
+        .map(|req| {
+            if let Lexicon::AppBskyFeedPostgate(postgate_record) = req.record {
+                // Only gates on a feed user's own posts are kept
+                if user_follows_indexed(req.author.as_str(), conn) {
+                    let system_time = SystemTime::now();
+                    let dt: DateTime<UtcOffset> = system_time.into();
+                    let indexed_at = format!("{}", dt.format("%+"));
+                    let created_at =
+                        normalize_created_at(&req.uri, &postgate_record.created_at, &dt);
+                    let new_postgate = (
+                        PostgateSchema::uri.eq(req.uri),
+                        PostgateSchema::cid.eq(req.cid),
+                        PostgateSchema::author.eq(req.author),
+                        PostgateSchema::post.eq(postgate_record.post),
+                        PostgateSchema::detachedEmbeddingUris
+                            .eq(postgate_record.detached_embedding_uris.unwrap_or_default()),
+                        PostgateSchema::createdAt.eq(created_at),
+                        PostgateSchema::indexedAt.eq(indexed_at),
+                    );
+                    new_postgates.push(new_postgate);
+                }
+            }
+        })
+        .for_each(drop);
+
+    // Postgates are edited in place, so the latest version replaces the stored one
+    if !new_postgates.is_empty() {
+        diesel::insert_into(PostgateSchema::postgate)
+            .values(&new_postgates)
+            .on_conflict(PostgateSchema::uri)
+            .do_update()
+            .set((
+                PostgateSchema::cid.eq(excluded(PostgateSchema::cid)),
+                PostgateSchema::detachedEmbeddingUris
+                    .eq(excluded(PostgateSchema::detachedEmbeddingUris)),
+                PostgateSchema::indexedAt.eq(excluded(PostgateSchema::indexedAt)),
+            ))
+            .execute(conn)
+            .expect("Error inserting postgate records");
+    }
+}
+
+fn queue_label_creation(labels: Vec<Label>, conn: &mut PgConnection) {
+    use crate::schema::label::dsl as LabelSchema;
+    use diesel::upsert::excluded;
+
+    let system_time = SystemTime::now();
+    let dt: DateTime<UtcOffset> = system_time.into();
+
+    // Applied one at a time and in order, since a batch may label and then negate the same value
+    for label in labels {
+        let expired = matches!(label.exp, Some(exp) if exp <= dt);
+        if label.neg.unwrap_or(false) || expired {
+            // A negation (or an already expired label) retracts whatever the labeler said before
+            diesel::delete(
+                LabelSchema::label
+                    .filter(LabelSchema::src.eq(&label.src))
+                    .filter(LabelSchema::uri.eq(&label.uri))
+                    .filter(LabelSchema::val.eq(&label.val)),
+            )
+            .execute(conn)
+            .expect("Error deleting label records");
+        } else {
+            let new_label = (
+                LabelSchema::src.eq(label.src),
+                LabelSchema::uri.eq(label.uri),
+                LabelSchema::cid.eq(label.cid),
+                LabelSchema::val.eq(label.val),
+                LabelSchema::cts.eq(label.cts.to_rfc3339_opts(SecondsFormat::Millis, true)),
+                LabelSchema::exp.eq(label
+                    .exp
+                    .map(|exp| exp.to_rfc3339_opts(SecondsFormat::Millis, true))),
+                LabelSchema::indexedAt.eq(format!("{}", dt.format("%+"))),
+            );
+            diesel::insert_into(LabelSchema::label)
+                .values(&new_label)
+                .on_conflict((LabelSchema::src, LabelSchema::uri, LabelSchema::val))
+                .do_update()
+                .set((
+                    LabelSchema::cid.eq(excluded(LabelSchema::cid)),
+                    LabelSchema::cts.eq(excluded(LabelSchema::cts)),
+                    LabelSchema::exp.eq(excluded(LabelSchema::exp)),
+                    LabelSchema::indexedAt.eq(excluded(LabelSchema::indexedAt)),
+                ))
+                .execute(conn)
+                .expect("Error inserting label records");
+        }
+    }
+}
+
+pub async fn queue_labels(labels: Vec<Label>, connection: WriteDbConn) -> Result<(), String> {
+    let mut resolver = ID_RESOLVER.clone();
+    let labels = labeler::verified_labels(labels, &mut resolver).await;
+    connection
+        .run(move |conn| {
+            queue_label_creation(labels, conn);
+            Ok(())
+        })
+        .await
+}
+
+/// Whether indexing repairs `error` itself: bad languages are dropped by `post_lang` and bad
+/// datetimes replaced by `normalize_created_at`.
+fn is_repaired_on_index(error: &ValidationError) -> bool {
+    matches!(
+        error.kind,
+        ValidationErrorKind::InvalidFormat(Format::Language | Format::Datetime)
+    ) || error.path == "langs"
+}
+
+fn invalid_records() -> MutexGuard<'static, BTreeMap<String, u64>> {
+    // Only counters, which a panic elsewhere can't leave inconsistent
+    INVALID_RECORDS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Drops records that break their lexicon beyond what indexing repairs, so a malformed record
+/// from the firehose can't end up indexed or fail the batch it arrived in.
+fn valid_records(body: Vec<CreateRequest>) -> Vec<CreateRequest> {
+    body.into_iter()
+        .filter(|req| {
+            let Err(ValidationErrors(errors)) = req.record.validate() else {
+                return true;
+            };
+            let errors: Vec<ValidationError> = errors
+                .into_iter()
+                .filter(|error| !is_repaired_on_index(error))
+                .collect();
+            if errors.is_empty() {
+                return true;
+            }
+            let nsid = req.record.nsid();
+            tracing::warn!(
+                "@LOG: Skipping invalid {nsid} record {:?}: {}",
+                req.uri,
+                ValidationErrors(errors)
+            );
+            *invalid_records().entry(nsid.to_string()).or_default() += 1;
+            false
+        })
+        .collect()
+}
+
 pub async fn queue_creation(
     lex: String,
     body: Vec<CreateRequest>,
     connection: WriteDbConn,
 ) -> Result<(), String> {
+    let body = valid_records(body);
     let result = connection
         .run(move |conn| {
             if lex == "posts" {
@@ -995,6 +1663,21 @@ pub async fn queue_creation(
             } else if lex == "follows" {
                 queue_follow_creation(body, conn);
                 Ok(())
+            } else if lex == "blocks" {
+                queue_block_creation(body, conn);
+                Ok(())
+            } else if lex == "lists" {
+                queue_list_creation(body, conn);
+                Ok(())
+            } else if lex == "listitems" {
+                queue_listitem_creation(body, conn);
+                Ok(())
+            } else if lex == "threadgates" {
+                queue_threadgate_creation(body, conn);
+                Ok(())
+            } else if lex == "postgates" {
+                queue_postgate_creation(body, conn);
+                Ok(())
             } else {
                 Err(format!("Unknown lexicon received {lex:?}"))
             }
@@ -1003,6 +1686,24 @@ pub async fn queue_creation(
     result
 }
 
+pub async fn queue_update(
+    lex: String,
+    body: Vec<CreateRequest>,
+    connection: WriteDbConn,
+) -> Result<(), String> {
+    let body = valid_records(body);
+    connection
+        .run(move |conn| {
+            if lex == "posts" {
+                queue_post_update(body, conn);
+                Ok(())
+            } else {
+                Err(format!("Unknown lexicon received {lex:?}"))
+            }
+        })
+        .await
+}
+
 #[tracing::instrument(skip(connection))]
 pub async fn queue_deletion(
     lex: String,
@@ -1025,6 +1726,16 @@ pub async fn queue_deletion(
                 delete_likes_by_uri(delete_rows, conn);
             } else if lex == "follows" {
                 delete_follows_by_uri(delete_rows, conn);
+            } else if lex == "blocks" {
+                delete_blocks_by_uri(delete_rows, conn);
+            } else if lex == "lists" {
+                delete_lists_by_uri(delete_rows, conn);
+            } else if lex == "listitems" {
+                delete_listitems_by_uri(delete_rows, conn);
+            } else if lex == "threadgates" {
+                delete_threadgates_by_uri(delete_rows, conn);
+            } else if lex == "postgates" {
+                delete_postgates_by_uri(delete_rows, conn);
             } else {
                 tracing::error!("Unknown lexicon received {lex:?}");
             }
@@ -1034,6 +1745,58 @@ pub async fn queue_deletion(
     result
 }
 
+pub async fn query_labels(
+    uri_patterns: Vec<String>,
+    sources: Vec<String>,
+    limit: Option<i64>,
+    cursor: Option<String>,
+    connection: ReadReplicaConn,
+) -> Result<QueryLabelsOutput, ValidationErrorMessageResponse> {
+    let (limit, cursor) = check_query_labels_params(&uri_patterns, limit, cursor)?;
+    Ok(connection
+        .run(move |conn| labeler::query_labels(uri_patterns, sources, limit, cursor, conn))
+        .await)
+}
+
+/// The limit and sequence number cursor a `queryLabels` request asks for, if it's valid.
+fn check_query_labels_params(
+    uri_patterns: &[String],
+    limit: Option<i64>,
+    cursor: Option<String>,
+) -> Result<(i64, Option<i64>), ValidationErrorMessageResponse> {
+    let limit = limit.unwrap_or(QUERY_LABELS_DEFAULT_LIMIT);
+    if uri_patterns.is_empty() || !(1..=QUERY_LABELS_MAX_LIMIT).contains(&limit) {
+        return Err(ValidationErrorMessageResponse {
+            code: Some(ErrorCode::ValidationError),
+            message: Some(format!(
+                "uriPatterns is required and limit must be between 1 and {QUERY_LABELS_MAX_LIMIT}."
+            )),
+        });
+    }
+    match cursor.map(|cursor| cursor.parse::<i64>()) {
+        None => Ok((limit, None)),
+        Some(Ok(cursor)) => Ok((limit, Some(cursor))),
+        Some(Err(_)) => Err(ValidationErrorMessageResponse {
+            code: Some(ErrorCode::ValidationError),
+            message: Some("Malformed cursor.".to_string()),
+        }),
+    }
+}
+
+pub async fn get_labeler_labels(
+    cursor: i64,
+    limit: i64,
+    connection: &ReadReplicaConn,
+) -> Vec<SubscribeLabelsLabels> {
+    connection
+        .run(move |conn| labeler::labels_since(cursor, limit, conn))
+        .await
+}
+
+pub async fn get_labeler_seq(connection: &ReadReplicaConn) -> i64 {
+    connection.run(labeler::latest_seq).await
+}
+
 pub async fn update_cursor(
     service: String,
     sequence: i64,
@@ -1109,3 +1872,600 @@ pub async fn get_cursor(
 
     result
 }
+
+/// Records the handles from Jetstream identity events and invalidates what was cached for each
+/// DID. An identity event doesn't say whether the keys or the handle changed, so the cached DID
+/// document goes along with both the old and the new handle.
+#[tracing::instrument(skip(connection))]
+pub async fn queue_identity_update(
+    body: Vec<IdentityUpdateRequest>,
+    connection: WriteDbConn,
+) -> Result<(), String> {
+    let dids: Vec<String> = body.iter().map(|req| req.did.clone()).collect();
+    let mut stale_handles: Vec<String> = body.iter().filter_map(|req| req.handle.clone()).collect();
+
+    let previous_handles = connection
+        .run(move |conn| {
+            use crate::schema::actor_handle::dsl::*;
+
+            let system_time = SystemTime::now();
+            let dt: DateTime<UtcOffset> = system_time.into();
+            let now = format!("{}", dt.format("%+"));
+
+            let mut previous_handles = Vec::new();
+            for req in body {
+                conn.transaction::<_, diesel::result::Error, _>(|conn| {
+                    let existing = actor_handle
+                        .filter(did.eq(&req.did))
+                        .select(ActorHandle::as_select())
+                        .for_update()
+                        .first(conn)
+                        .optional()?;
+                    // Events can be replayed after a cursor rewind, so never go backwards
+                    if let Some(existing) = existing {
+                        if existing.seq >= req.seq {
+                            return Ok(());
+                        }
+                        previous_handles.extend(existing.handle);
+                    }
+                    let new_handle = ActorHandle {
+                        did: req.did,
+                        handle: req.handle,
+                        seq: req.seq,
+                        indexed_at: now.clone(),
+                    };
+                    diesel::insert_into(actor_handle)
+                        .values(&new_handle)
+                        .on_conflict(did)
+                        .do_update()
+                        .set(&new_handle)
+                        .execute(conn)?;
+                    Ok(())
+                })
+                .map_err(|error| error.to_string())?;
+            }
+            Ok::<_, String>(previous_handles)
+        })
+        .await?;
+    stale_handles.extend(previous_handles);
+
+    invalidate_identities(&ID_RESOLVER, dids, stale_handles).await;
+    Ok(())
+}
+
+/// Drops the cached DID documents and handles of changed identities. DID documents are cached in
+/// Postgres, so every worker sees this; handles are cached per worker, and other workers keep
+/// them until [`IDENTITY_CACHE_TTL`](crate::auth::IDENTITY_CACHE_TTL) passes.
+pub async fn invalidate_identities(resolver: &IdResolver, dids: Vec<String>, handles: Vec<String>) {
+    if let Some(did_cache) = &resolver.did.cache {
+        for _did in dids {
+            if let Err(error) = did_cache.clear_entry(_did).await {
+                tracing::error!("@LOG: Failed to invalidate cached DID document: {error:?}");
+            }
+        }
+    }
+    for handle in handles {
+        resolver.handle.clear_cache_entry(&handle);
+    }
+}
+
+pub async fn get_actor_handle(
+    _did: String,
+    connection: ReadReplicaConn,
+) -> Result<ActorHandle, PathUnknownErrorMessageResponse> {
+    use crate::schema::actor_handle::dsl::*;
+
+    connection
+        .run(move |conn| {
+            actor_handle
+                .filter(did.eq(_did))
+                .select(ActorHandle::as_select())
+                .first(conn)
+                .optional()
+                .expect("Error loading handle records")
+                .ok_or_else(|| crate::models::PathUnknownErrorMessageResponse {
+                    code: Some(crate::models::NotFoundErrorCode::NotFoundError),
+                    message: Some("Not found.".into()),
+                })
+        })
+        .await
+}
+
+pub async fn get_stats(
+    visitors_limit: Option<i64>,
+    connection: ReadReplicaConn,
+) -> Result<FeedgenStats, String> {
+    use crate::schema::actor_handle::dsl as ActorHandleSchema;
+    use crate::schema::visitor::dsl as VisitorSchema;
+    use diesel::dsl::{count, count_star};
+
+    let visitors_limit = visitors_limit
+        .unwrap_or(STATS_DEFAULT_VISITORS)
+        .clamp(1, STATS_MAX_VISITORS);
+    let did_cache = ID_RESOLVER
+        .did
+        .cache
+        .as_ref()
+        .map(|did_cache| did_cache.stats())
+        .unwrap_or_default();
+    let invalid_records = invalid_records().clone();
+
+    connection
+        .run(move |conn| {
+            let indexed_handles = ActorHandleSchema::actor_handle
+                .select(count_star())
+                .first::<i64>(conn)?;
+            let visits: Vec<(String, i64)> = VisitorSchema::visitor
+                .group_by(VisitorSchema::did)
+                .select((VisitorSchema::did, count(VisitorSchema::id)))
+                .order(count(VisitorSchema::id).desc())
+                .limit(visitors_limit)
+                .load(conn)?;
+            let visitor_dids: Vec<&String> = visits.iter().map(|(did, _)| did).collect();
+            let handles: Vec<(String, Option<String>)> = ActorHandleSchema::actor_handle
+                .filter(ActorHandleSchema::did.eq_any(visitor_dids))
+                .select((ActorHandleSchema::did, ActorHandleSchema::handle))
+                .load(conn)?;
+
+            let top_visitors = visits
+                .into_iter()
+                .map(|(did, visits)| VisitorStats {
+                    handle: handles
+                        .iter()
+                        .find(|(handle_did, _)| *handle_did == did)
+                        .and_then(|(_, handle)| handle.clone()),
+                    did,
+                    visits,
+                })
+                .collect();
+            Ok::<_, diesel::result::Error>(FeedgenStats {
+                did_cache,
+                indexed_handles,
+                invalid_records,
+                top_visitors,
+            })
+        })
+        .await
+        .map_err(|error| error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::did_cache::PgDidCache;
+    use crate::test_utils::{create_request, insert_feed_user, test_connection, test_pool};
+    use rsky_identity::types::{DidCache, DidDocument, IdentityResolverOpts};
+    use rsky_syntax::datetime::is_valid_datetime;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    const ALICE: &str = "did:plc:alice";
+    const BOB: &str = "did:plc:bob";
+    const CAROL: &str = "did:plc:carol";
+    const DAVE: &str = "did:plc:dave";
+    const MALLORY: &str = "did:plc:mallory";
+    const CREATED_AT: &str = "2024-11-13T23:19:36.449Z";
+
+    fn block_request(rkey: &str, author: &str, subject: &str) -> CreateRequest {
+        create_request(
+            &format!("at://{author}/app.bsky.graph.block/{rkey}"),
+            author,
+            json!({"$type": "app.bsky.graph.block", "subject": subject, "createdAt": CREATED_AT}),
+        )
+    }
+
+    fn list_request(uri: &str) -> CreateRequest {
+        create_request(
+            uri,
+            ALICE,
+            json!({
+                "$type": "app.bsky.graph.list",
+                "purpose": "app.bsky.graph.defs#curatelist",
+                "name": "Friends",
+                "createdAt": CREATED_AT,
+            }),
+        )
+    }
+
+    fn listitem_request(rkey: &str, list: &str, subject: &str) -> CreateRequest {
+        create_request(
+            &format!("at://{ALICE}/app.bsky.graph.listitem/{rkey}"),
+            ALICE,
+            json!({
+                "$type": "app.bsky.graph.listitem",
+                "subject": subject,
+                "list": list,
+                "createdAt": CREATED_AT,
+            }),
+        )
+    }
+
+    fn repost_request(rkey: &str, author: &str, subject_author: &str) -> CreateRequest {
+        create_request(
+            &format!("at://{author}/app.bsky.feed.repost/{rkey}"),
+            author,
+            json!({
+                "$type": "app.bsky.feed.repost",
+                "subject": {
+                    "uri": format!("at://{subject_author}/app.bsky.feed.post/3latjcehsho2n"),
+                    "cid": "bafyreigw5ufnkavdzcczl2dusa3bcnkckhi4tscp6qsrsmg76s3ckseney",
+                },
+                "createdAt": CREATED_AT,
+            }),
+        )
+    }
+
+    fn gate_request(collection: &str, author: &str, field: &str) -> CreateRequest {
+        let post = format!("at://{author}/app.bsky.feed.post/3latjcehsho2n");
+        create_request(
+            &format!("at://{author}/{collection}/3latjcehsho2n"),
+            author,
+            json!({
+                "$type": collection,
+                "post": post,
+                field: [format!("at://{CAROL}/app.bsky.feed.post/3lauicnw5op2a")],
+                "createdAt": CREATED_AT,
+            }),
+        )
+    }
+
+    fn blocks(conn: &mut PgConnection) -> Vec<(String, String)> {
+        use crate::schema::block::dsl as BlockSchema;
+
+        BlockSchema::block
+            .filter(BlockSchema::author.eq_any([ALICE, BOB, DAVE, MALLORY]))
+            .order(BlockSchema::uri)
+            .select((BlockSchema::author, BlockSchema::subject))
+            .load(conn)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_valid_records_keeps_repairable_records() {
+        let uri = |rkey: &str| format!("at://{ALICE}/app.bsky.feed.like/{rkey}");
+        let like = |rkey: &str, subject_uri: &str, created_at: &str| {
+            create_request(
+                &uri(rkey),
+                ALICE,
+                json!({
+                    "$type": "app.bsky.feed.like",
+                    "subject": {
+                        "uri": subject_uri,
+                        "cid": "bafyreigw5ufnkavdzcczl2dusa3bcnkckhi4tscp6qsrsmg76s3ckseney",
+                    },
+                    "createdAt": created_at,
+                }),
+            )
+        };
+        let subject = format!("at://{BOB}/app.bsky.feed.post/3latjcehsho2n");
+        let post = create_request(
+            &format!("at://{ALICE}/app.bsky.feed.post/3lauicnw5op2f"),
+            ALICE,
+            json!({
+                "$type": "app.bsky.feed.post",
+                "text": "hello",
+                "langs": ["en", "not a language", "de", "fr"],
+                "createdAt": CREATED_AT,
+            }),
+        );
+
+        let kept = valid_records(vec![
+            like("3lauicnw5op2a", &subject, CREATED_AT),
+            like("3lauicnw5op2b", &subject, "yesterday"),
+            like("3lauicnw5op2c", "not an at-uri", CREATED_AT),
+            post,
+        ]);
+        assert_eq!(
+            kept.iter().map(|req| req.uri.as_str()).collect::<Vec<_>>(),
+            vec![
+                uri("3lauicnw5op2a").as_str(),
+                uri("3lauicnw5op2b").as_str(),
+                &format!("at://{ALICE}/app.bsky.feed.post/3lauicnw5op2f"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_normalize_created_at_falls_back_to_a_valid_datetime() {
+        let indexed_at = DateTime::parse_from_rfc3339("2024-11-13T23:19:36.449123+00:00")
+            .unwrap()
+            .with_timezone(&Utc);
+        let uri = format!("at://{ALICE}/app.bsky.feed.like/3lauicnw5op2f");
+        assert_eq!(
+            normalize_created_at(&uri, "2024-11-13T18:19:36-05:00", &indexed_at),
+            "2024-11-13T23:19:36.000Z"
+        );
+        let fallback = normalize_created_at(&uri, "yesterday", &indexed_at);
+        assert_eq!(fallback, "2024-11-13T23:19:36.449Z");
+        assert!(is_valid_datetime(&fallback));
+    }
+
+    #[test]
+    fn test_check_query_labels_params() {
+        let patterns = vec![format!("at://{ALICE}/*")];
+        assert_eq!(
+            check_query_labels_params(&patterns, None, None).unwrap(),
+            (QUERY_LABELS_DEFAULT_LIMIT, None)
+        );
+        assert_eq!(
+            check_query_labels_params(&patterns, Some(250), Some("42".to_string())).unwrap(),
+            (250, Some(42))
+        );
+        assert!(check_query_labels_params(&[], None, None).is_err());
+        assert!(check_query_labels_params(&patterns, Some(0), None).is_err());
+        assert!(check_query_labels_params(&patterns, Some(251), None).is_err());
+        assert_eq!(
+            check_query_labels_params(&patterns, None, Some("3latjcehsho2n".to_string()))
+                .unwrap_err()
+                .message
+                .as_deref(),
+            Some("Malformed cursor.")
+        );
+    }
+
+    #[test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    fn test_block_creation_only_indexes_feed_users() {
+        let conn = &mut test_connection();
+        insert_feed_user(ALICE, conn);
+
+        queue_block_creation(
+            vec![
+                block_request("3lauicnw5op2a", ALICE, MALLORY),
+                block_request("3lauicnw5op2b", MALLORY, ALICE),
+                block_request("3lauicnw5op2c", BOB, CAROL),
+            ],
+            conn,
+        );
+
+        assert_eq!(
+            blocks(conn),
+            vec![
+                (ALICE.to_string(), MALLORY.to_string()),
+                (MALLORY.to_string(), ALICE.to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    fn test_gate_creation_only_indexes_feed_users() {
+        use crate::schema::postgate::dsl as PostgateSchema;
+        use crate::schema::threadgate::dsl as ThreadgateSchema;
+
+        let conn = &mut test_connection();
+        insert_feed_user(ALICE, conn);
+
+        queue_threadgate_creation(
+            vec![
+                gate_request("app.bsky.feed.threadgate", ALICE, "hiddenReplies"),
+                gate_request("app.bsky.feed.threadgate", BOB, "hiddenReplies"),
+            ],
+            conn,
+        );
+        queue_postgate_creation(
+            vec![
+                gate_request("app.bsky.feed.postgate", ALICE, "detachedEmbeddingUris"),
+                gate_request("app.bsky.feed.postgate", BOB, "detachedEmbeddingUris"),
+            ],
+            conn,
+        );
+
+        let threadgate_authors: Vec<String> = ThreadgateSchema::threadgate
+            .filter(ThreadgateSchema::author.eq_any([ALICE, BOB]))
+            .select(ThreadgateSchema::author)
+            .load(conn)
+            .unwrap();
+        assert_eq!(threadgate_authors, vec![ALICE.to_string()]);
+        let postgate_authors: Vec<String> = PostgateSchema::postgate
+            .filter(PostgateSchema::author.eq_any([ALICE, BOB]))
+            .select(PostgateSchema::author)
+            .load(conn)
+            .unwrap();
+        assert_eq!(postgate_authors, vec![ALICE.to_string()]);
+    }
+
+    #[test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    fn test_delete_blocks_and_list_items_by_uri() {
+        use crate::schema::listitem::dsl as ListItemSchema;
+
+        let conn = &mut test_connection();
+        insert_feed_user(ALICE, conn);
+        let list = format!("at://{ALICE}/app.bsky.graph.list/3lauicnw5op2f");
+        queue_block_creation(
+            vec![
+                block_request("3lauicnw5op2a", ALICE, MALLORY),
+                block_request("3lauicnw5op2b", ALICE, DAVE),
+            ],
+            conn,
+        );
+        queue_listitem_creation(
+            vec![
+                listitem_request("3lauicnw5op2c", &list, BOB),
+                listitem_request("3lauicnw5op2d", &list, CAROL),
+            ],
+            conn,
+        );
+
+        delete_blocks_by_uri(
+            vec![format!("at://{ALICE}/app.bsky.graph.block/3lauicnw5op2a")],
+            conn,
+        );
+        delete_listitems_by_uri(
+            vec![format!(
+                "at://{ALICE}/app.bsky.graph.listitem/3lauicnw5op2c"
+            )],
+            conn,
+        );
+
+        assert_eq!(blocks(conn), vec![(ALICE.to_string(), DAVE.to_string())]);
+        let members: Vec<String> = ListItemSchema::listitem
+            .filter(ListItemSchema::list.eq(&list))
+            .select(ListItemSchema::subject)
+            .load(conn)
+            .unwrap();
+        assert_eq!(members, vec![CAROL.to_string()]);
+    }
+
+    #[test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    fn test_delete_list_deletes_its_items() {
+        use crate::schema::list::dsl as ListSchema;
+        use crate::schema::listitem::dsl as ListItemSchema;
+
+        let conn = &mut test_connection();
+        insert_feed_user(ALICE, conn);
+        let list = format!("at://{ALICE}/app.bsky.graph.list/3lauicnw5op2f");
+        let other_list = format!("at://{ALICE}/app.bsky.graph.list/3lauicnw5op2g");
+        queue_list_creation(vec![list_request(&list), list_request(&other_list)], conn);
+        queue_listitem_creation(
+            vec![
+                listitem_request("3lauicnw5op2c", &list, BOB),
+                listitem_request("3lauicnw5op2d", &other_list, BOB),
+            ],
+            conn,
+        );
+
+        delete_lists_by_uri(vec![list.clone()], conn);
+
+        let lists: Vec<String> = ListSchema::list
+            .filter(ListSchema::uri.eq_any([&list, &other_list]))
+            .select(ListSchema::uri)
+            .load(conn)
+            .unwrap();
+        assert_eq!(lists, vec![other_list.clone()]);
+        let item_lists: Vec<String> = ListItemSchema::listitem
+            .filter(ListItemSchema::list.eq_any([&list, &other_list]))
+            .select(ListItemSchema::list)
+            .load(conn)
+            .unwrap();
+        assert_eq!(item_lists, vec![other_list]);
+    }
+
+    #[test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    fn test_reposts_exclude_blocked_accounts() {
+        let conn = &mut test_connection();
+        insert_feed_user(ALICE, conn);
+        queue_block_creation(
+            vec![
+                block_request("3lauicnw5op2a", ALICE, MALLORY),
+                block_request("3lauicnw5op2b", DAVE, ALICE),
+            ],
+            conn,
+        );
+        queue_repost_creation(
+            vec![
+                repost_request("3lauicnw5op2c", BOB, CAROL),
+                repost_request("3lauicnw5op2d", MALLORY, CAROL),
+                repost_request("3lauicnw5op2e", BOB, MALLORY),
+                repost_request("3lauicnw5op2f", BOB, DAVE),
+            ],
+            conn,
+        );
+
+        let following = format!("'{BOB}', '{MALLORY}'");
+        let reposts = sql_query(repost_query_str(false, &following, ALICE))
+            .load::<crate::models::Post>(conn)
+            .unwrap();
+        let uris: Vec<String> = reposts.into_iter().map(|repost| repost.uri).collect();
+        assert_eq!(
+            uris,
+            vec![format!("at://{BOB}/app.bsky.feed.repost/3lauicnw5op2c")]
+        );
+    }
+
+    #[test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    fn test_hidden_labels_compare_expiry_as_time() {
+        use crate::schema::label::dsl as LabelSchema;
+        use crate::schema::label_preference::dsl as LabelPrefSchema;
+        use diesel::dsl::sql;
+        use diesel::sql_types::Bool;
+
+        let conn = &mut test_connection();
+        diesel::insert_into(LabelPrefSchema::label_preference)
+            .values((
+                LabelPrefSchema::did.eq(ALICE),
+                LabelPrefSchema::labeler_did.eq(""),
+                LabelPrefSchema::label.eq("spam"),
+                LabelPrefSchema::visibility.eq("hide"),
+            ))
+            .execute(conn)
+            .unwrap();
+        // An hour ago, but written with an offset that sorts after the current UTC time as text
+        let an_hour_ago = (Utc::now() - chrono::Duration::hours(1))
+            .with_timezone(&chrono::FixedOffset::east_opt(5 * 3600).unwrap())
+            .to_rfc3339();
+        let in_an_hour = (Utc::now() + chrono::Duration::hours(1)).to_rfc3339();
+        for (author, exp) in [(BOB, an_hour_ago), (CAROL, in_an_hour)] {
+            diesel::insert_into(LabelSchema::label)
+                .values((
+                    LabelSchema::src.eq("did:plc:labeler"),
+                    LabelSchema::uri.eq(author),
+                    LabelSchema::val.eq("spam"),
+                    LabelSchema::cts.eq(CREATED_AT),
+                    LabelSchema::exp.eq(Some(exp)),
+                    LabelSchema::indexedAt.eq(CREATED_AT),
+                ))
+                .execute(conn)
+                .unwrap();
+        }
+
+        let shown = |author: &str, conn: &mut PgConnection| {
+            diesel::select(sql::<Bool>(&format!(
+                "(select {} from (values ('at://{author}/app.bsky.feed.post/3k', '{author}')) \
+                 as p1(uri, author))",
+                hidden_label_filter_str(ALICE, &["p1.uri", "p1.author"])
+            )))
+            .get_result::<bool>(conn)
+            .unwrap()
+        };
+        assert!(shown(BOB, conn));
+        assert!(!shown(CAROL, conn));
+    }
+
+    #[rocket::async_test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    async fn test_invalidating_identities_reaches_other_workers() {
+        let pool = test_pool();
+        let worker = || {
+            IdResolver::new(IdentityResolverOpts {
+                timeout: None,
+                plc_url: None,
+                did_cache: Some(DidCache::with_store(
+                    Arc::new(PgDidCache::with_pool(pool.clone(), None)),
+                    None,
+                    None,
+                )),
+                backup_nameservers: None,
+                backup_nameserver_port: None,
+                handle_cache_ttl: None,
+                transport: None,
+            })
+        };
+        let (first, second) = (worker(), worker());
+        let first_cache = first.did.cache.as_ref().unwrap();
+        let doc = DidDocument {
+            context: None,
+            id: ALICE.to_string(),
+            also_known_as: Some(vec!["at://alice.test".to_string()]),
+            verification_method: None,
+            service: None,
+        };
+        first_cache.cache_did(ALICE.to_string(), doc).await.unwrap();
+        assert!(first_cache
+            .check_cache(ALICE.to_string())
+            .await
+            .unwrap()
+            .is_some());
+
+        invalidate_identities(&second, vec![ALICE.to_string()], vec![]).await;
+        assert!(first_cache
+            .check_cache(ALICE.to_string())
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
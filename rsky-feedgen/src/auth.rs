@@ -1,39 +1,70 @@
+use crate::did_cache::PgDidCache;
 use crate::models::JwtParts;
-use base64::{engine::general_purpose, Engine as _};
-use std::time::{SystemTime, UNIX_EPOCH};
+use lazy_static::lazy_static;
+use rsky_crypto::jwt::{parse_jwt, verify_claims, verify_jwt as verify_signed_jwt};
+use rsky_identity::types::{DidCache, IdentityResolverOpts};
+use rsky_identity::IdResolver;
+use std::env;
+use std::sync::Arc;
+use std::time::Duration;
 
-pub fn verify_jwt(jwtstr: &str, service_did: &String) -> Result<String, String> {
-    let parts = jwtstr.split(".").map(String::from).collect::<Vec<_>>();
+/// How long a worker trusts resolved handles, which it can't be told have changed. Identity
+/// events only reach one worker's memory.
+pub const IDENTITY_CACHE_TTL: Duration = Duration::from_secs(5 * 60);
 
-    if parts.len() != 3 {
-        return Err("poorly formatted jwt".into());
-    }
+lazy_static! {
+    /// Shared by every request. Clones share the DID cache, which lives in Postgres so that all
+    /// workers see the same resolved keys.
+    pub static ref ID_RESOLVER: IdResolver = IdResolver::new(IdentityResolverOpts {
+        timeout: None,
+        plc_url: env::var("PLC_URL").ok(),
+        did_cache: Some(did_cache()),
+        backup_nameservers: None,
+        backup_nameserver_port: None,
+        handle_cache_ttl: Some(IDENTITY_CACHE_TTL),
+        transport: None,
+    });
+}
 
-    let bytes = general_purpose::STANDARD_NO_PAD.decode(&parts[1]).unwrap();
+fn did_cache() -> DidCache {
+    let max_entries = env::var("DID_CACHE_MAX_ENTRIES")
+        .ok()
+        .and_then(|max_entries| max_entries.parse().ok());
+    DidCache::with_store(Arc::new(PgDidCache::new(max_entries)), None, None)
+}
 
-    if let Ok(payload) = std::str::from_utf8(&bytes) {
-        if let Ok(payload) = serde_json::from_str::<JwtParts>(payload) {
-            let start = SystemTime::now();
-            let since_the_epoch = start
-                .duration_since(UNIX_EPOCH)
-                .expect("Time went backwards");
+pub async fn verify_jwt(jwtstr: &str, service_did: &str) -> Result<String, String> {
+    let parsed = parse_jwt(jwtstr).map_err(|error| error.to_string())?;
+    verify_claims(&parsed.payload, Some(service_did), None).map_err(|error| error.to_string())?;
 
-            if since_the_epoch.as_millis() / 1000 > payload.exp {
-                return Err("jwt expired".into());
-            }
-            if service_did != &payload.aud {
-                return Err("jwt audience does not match service did".into());
-            }
-            // TO DO: Verify cryptographic signature
-            if let Ok(jwtstr) = serde_json::to_string(&payload) {
-                Ok(jwtstr)
-            } else {
-                Err("error parsing payload".into())
-            }
-        } else {
-            Err("error parsing payload".into())
+    // `iss` may name a service on the DID, e.g. `did:plc:...#atproto_labeler`
+    let iss = parsed.payload.iss.split('#').next().unwrap_or_default();
+    let mut resolver = ID_RESOLVER.clone();
+    let did_key = resolver
+        .did
+        .resolve_atproto_key(&iss.to_string(), None)
+        .await
+        .map_err(|error| error.to_string())?;
+    let mut verified = verify_signed_jwt(jwtstr, Some(service_did), None, &did_key)
+        .map_err(|error| error.to_string());
+    if verified.is_err() {
+        // The key may have rotated since it was cached, so check again only if it has
+        let fresh_key = resolver
+            .did
+            .resolve_atproto_key(&iss.to_string(), Some(true))
+            .await
+            .map_err(|error| error.to_string())?;
+        if fresh_key != did_key {
+            verified = verify_signed_jwt(jwtstr, Some(service_did), None, &fresh_key)
+                .map_err(|error| error.to_string());
         }
-    } else {
-        Err("error parsing payload".into())
     }
+    let verified = verified?;
+
+    let payload = JwtParts {
+        iss: verified.iss,
+        aud: verified.aud,
+        exp: verified.exp.into(),
+    };
+    serde_json::to_string(&payload).map_err(|_| "error parsing payload".to_string())
 }
@@ -1,9 +1,14 @@
-use crate::models::{FetchedPost, Follow, FollowingPreference, UserFeedPreference};
+use crate::auth::ID_RESOLVER;
+use crate::models::{
+    FetchedPost, Follow, FollowingPreference, LabelPreference, ListFeedPreference, ListItem,
+    UserFeedPreference,
+};
 use crate::{ReadReplicaConn, WriteDbConn};
 use diesel::dsl::count;
 use diesel::pg::PgConnection;
 use diesel::prelude::*;
 use dotenvy::dotenv;
+use rsky_syntax::aturi::{AtUri, BSKY_APP_URL};
 use std::env;
 
 use crate::schema::following_preference::dsl::following_preference;
@@ -185,6 +190,174 @@ pub fn user_follows_indexed(did: &str, conn: &mut PgConnection) -> bool {
     !follows.is_empty()
 }
 
+pub async fn get_saved_list_members(list_uri: String, connection: &ReadReplicaConn) -> Vec<String> {
+    use crate::schema::listitem::dsl::*;
+
+    connection
+        .run(move |conn| {
+            listitem
+                .filter(list.eq(list_uri))
+                .select(subject)
+                .load(conn)
+                .expect("Error querying list items")
+        })
+        .await
+}
+
+pub fn insert_list_items(list_items: Vec<ListItem>, conn: &mut PgConnection) {
+    use crate::schema::listitem::dsl as ListItemSchema;
+    let mut list_items_to_insert = Vec::new();
+    for list_item in list_items.iter() {
+        let new_list_item = (
+            ListItemSchema::uri.eq(list_item.uri.clone()),
+            ListItemSchema::cid.eq(list_item.cid.clone()),
+            ListItemSchema::author.eq(list_item.author.clone()),
+            ListItemSchema::subject.eq(list_item.subject.clone()),
+            ListItemSchema::list.eq(list_item.list.clone()),
+            ListItemSchema::createdAt.eq(list_item.created_at.clone()),
+            ListItemSchema::indexedAt.eq(list_item.indexed_at.clone()),
+            ListItemSchema::prev.eq(list_item.prev.clone()),
+            ListItemSchema::sequence.eq(list_item.sequence),
+        );
+        list_items_to_insert.push(new_list_item);
+    }
+
+    diesel::insert_into(crate::schema::listitem::dsl::listitem)
+        .values(&list_items_to_insert)
+        .on_conflict(ListItemSchema::uri)
+        .do_nothing()
+        .execute(conn)
+        .expect("Error inserting listitem records");
+}
+
+pub fn user_list_feed_indexed(_did: &str, conn: &mut PgConnection) -> bool {
+    use crate::schema::list_feed_preference::dsl::*;
+
+    let preferences: Vec<ListFeedPreference> = list_feed_preference
+        .filter(did.eq(_did))
+        .limit(1)
+        .select(ListFeedPreference::as_select())
+        .load(conn)
+        .expect("Error querying list feed preferences");
+
+    !preferences.is_empty()
+}
+
+pub async fn get_list_feed_preference(
+    _did: String,
+    connection: &ReadReplicaConn,
+) -> Option<ListFeedPreference> {
+    use crate::schema::list_feed_preference::dsl::*;
+
+    connection
+        .run(move |conn| {
+            list_feed_preference
+                .filter(did.eq(_did))
+                .select(ListFeedPreference::as_select())
+                .first(conn)
+                .optional()
+                .expect("Error querying list feed preferences")
+        })
+        .await
+}
+
+pub async fn list_feed_pref_fetch(
+    _did: String,
+    connection: WriteDbConn,
+) -> Vec<ListFeedPreference> {
+    use crate::schema::list_feed_preference::dsl::did;
+    use crate::schema::list_feed_preference::dsl::list_feed_preference as ListFeedPrefSchema;
+
+    let result = connection
+        .run(move |conn| {
+            ListFeedPrefSchema
+                .filter(did.eq(_did))
+                .select(ListFeedPreference::as_select())
+                .load(conn)
+                .unwrap()
+        })
+        .await;
+    result
+}
+
+pub async fn list_feed_pref_update(
+    mut _list_feed_preference: ListFeedPreference,
+    connection: WriteDbConn,
+) -> Result<(), String> {
+    use crate::schema::list_feed_preference::did;
+    use crate::schema::list_feed_preference::dsl::list_feed_preference;
+
+    // Lists can also be given as their bsky.app page
+    let list_uri = if _list_feed_preference.list_uri.starts_with(BSKY_APP_URL) {
+        AtUri::from_bsky_app_url(&_list_feed_preference.list_uri, &ID_RESOLVER.handle).await
+    } else {
+        _list_feed_preference.list_uri.parse::<AtUri>()
+    }
+    .map_err(|error| format!("Invalid list {}: {error}", _list_feed_preference.list_uri))?;
+    if list_uri.authority().as_str() != _list_feed_preference.did
+        || list_uri.collection().map(|nsid| nsid.as_str()) != Some("app.bsky.graph.list")
+        || list_uri.rkey().is_none()
+    {
+        return Err(format!(
+            "List {} is not owned by {}",
+            _list_feed_preference.list_uri, _list_feed_preference.did
+        ));
+    }
+    _list_feed_preference.list_uri = list_uri.to_string();
+
+    let result = connection
+        .run(move |conn| {
+            diesel::insert_into(list_feed_preference)
+                .values(&_list_feed_preference)
+                .on_conflict(did)
+                .do_update()
+                .set(&_list_feed_preference)
+                .execute(conn)
+                .expect("Error update list feed preference records");
+        })
+        .await;
+    Ok(result)
+}
+
+pub async fn label_pref_fetch(_did: String, connection: WriteDbConn) -> Vec<LabelPreference> {
+    use crate::schema::label_preference::dsl::did;
+    use crate::schema::label_preference::dsl::label_preference as LabelPrefSchema;
+
+    let result = connection
+        .run(move |conn| {
+            LabelPrefSchema
+                .filter(did.eq(_did))
+                .select(LabelPreference::as_select())
+                .load(conn)
+                .unwrap()
+        })
+        .await;
+    result
+}
+
+/// Replaces every label preference of `_did` with `_label_preferences`.
+pub async fn label_pref_update(
+    _did: String,
+    _label_preferences: Vec<LabelPreference>,
+    connection: WriteDbConn,
+) -> Result<(), String> {
+    use crate::schema::label_preference::dsl::did;
+    use crate::schema::label_preference::dsl::label_preference as LabelPrefSchema;
+
+    connection
+        .run(move |conn| {
+            conn.transaction::<_, diesel::result::Error, _>(|conn| {
+                diesel::delete(LabelPrefSchema.filter(did.eq(&_did))).execute(conn)?;
+                diesel::insert_into(LabelPrefSchema)
+                    .values(&_label_preferences)
+                    .execute(conn)?;
+                Ok(())
+            })
+            .map_err(|error| error.to_string())
+        })
+        .await
+}
+
 pub async fn user_config_creation(
     config: UserFeedPreference,
     connection: WriteDbConn,
@@ -352,6 +525,56 @@ pub fn delete_follows_by_uri(delete_rows: Vec<String>, conn: &mut PgConnection)
     .expect("Error deleting follow records");
 }
 
+pub fn delete_blocks_by_uri(delete_rows: Vec<String>, conn: &mut PgConnection) {
+    diesel::delete(
+        crate::schema::block::dsl::block.filter(crate::schema::block::dsl::uri.eq_any(delete_rows)),
+    )
+    .execute(conn)
+    .expect("Error deleting block records");
+}
+
+pub fn delete_lists_by_uri(delete_rows: Vec<String>, conn: &mut PgConnection) {
+    // Items of a deleted list are orphaned, so they go with it
+    diesel::delete(
+        crate::schema::listitem::dsl::listitem
+            .filter(crate::schema::listitem::dsl::list.eq_any(delete_rows.clone())),
+    )
+    .execute(conn)
+    .expect("Error deleting listitem records");
+    diesel::delete(
+        crate::schema::list::dsl::list.filter(crate::schema::list::dsl::uri.eq_any(delete_rows)),
+    )
+    .execute(conn)
+    .expect("Error deleting list records");
+}
+
+pub fn delete_listitems_by_uri(delete_rows: Vec<String>, conn: &mut PgConnection) {
+    diesel::delete(
+        crate::schema::listitem::dsl::listitem
+            .filter(crate::schema::listitem::dsl::uri.eq_any(delete_rows)),
+    )
+    .execute(conn)
+    .expect("Error deleting listitem records");
+}
+
+pub fn delete_threadgates_by_uri(delete_rows: Vec<String>, conn: &mut PgConnection) {
+    diesel::delete(
+        crate::schema::threadgate::dsl::threadgate
+            .filter(crate::schema::threadgate::dsl::uri.eq_any(delete_rows)),
+    )
+    .execute(conn)
+    .expect("Error deleting threadgate records");
+}
+
+pub fn delete_postgates_by_uri(delete_rows: Vec<String>, conn: &mut PgConnection) {
+    diesel::delete(
+        crate::schema::postgate::dsl::postgate
+            .filter(crate::schema::postgate::dsl::uri.eq_any(delete_rows)),
+    )
+    .execute(conn)
+    .expect("Error deleting postgate records");
+}
+
 pub fn delete_likes_by_uri(delete_rows: Vec<String>, conn: &mut PgConnection) {
     diesel::delete(
         crate::schema::like::dsl::like.filter(crate::schema::like::dsl::uri.eq_any(delete_rows)),
@@ -0,0 +1,217 @@
+use crate::models::DidCacheEntry;
+use anyhow::Result;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::r2d2::{ConnectionManager, Pool};
+use dotenvy::dotenv;
+use rocket::tokio::task;
+use rsky_identity::types::{CacheVal, DidCacheStore};
+use std::env;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Puts between evictions, so a busy cache isn't trimmed on every write
+const EVICT_EVERY: u64 = 100;
+
+/// Connections kept for cache reads and writes, apart from the feed's own pool
+const POOL_SIZE: u32 = 2;
+
+/// DID documents cached in Postgres, so every worker shares one warm cache across restarts.
+/// Reads never write. Once more than `max_entries` are stored, the least recently resolved are
+/// evicted, checked every [`EVICT_EVERY`] puts.
+pub struct PgDidCache {
+    pool: Pool<ConnectionManager<PgConnection>>,
+    max_entries: Option<i64>,
+    puts: AtomicU64,
+}
+
+impl fmt::Debug for PgDidCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PgDidCache")
+            .field("max_entries", &self.max_entries)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PgDidCache {
+    /// Connects lazily, so building the cache never blocks waiting on Postgres.
+    pub fn new(max_entries: Option<i64>) -> Self {
+        dotenv().ok();
+        let database_url = env::var("DATABASE_URL").unwrap_or_default();
+        let pool = Pool::builder()
+            .max_size(POOL_SIZE)
+            .build_unchecked(ConnectionManager::new(database_url));
+        Self::with_pool(pool, max_entries)
+    }
+
+    pub fn with_pool(
+        pool: Pool<ConnectionManager<PgConnection>>,
+        max_entries: Option<i64>,
+    ) -> Self {
+        Self {
+            pool,
+            max_entries,
+            puts: AtomicU64::new(0),
+        }
+    }
+
+    /// Runs `query` on a pooled connection off the async runtime.
+    async fn run<T, F>(&self, query: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut PgConnection) -> Result<T> + Send + 'static,
+    {
+        let pool = self.pool.clone();
+        task::spawn_blocking(move || query(&mut *pool.get()?)).await?
+    }
+}
+
+fn evict(max_entries: i64, conn: &mut PgConnection) -> Result<()> {
+    use crate::schema::did_cache::dsl::*;
+
+    let cutoff: Option<i64> = did_cache
+        .select(updated_at)
+        .order(updated_at.desc())
+        .offset(max_entries)
+        .first(conn)
+        .optional()?;
+    if let Some(cutoff) = cutoff {
+        diesel::delete(did_cache.filter(updated_at.le(cutoff))).execute(conn)?;
+    }
+    Ok(())
+}
+
+#[rocket::async_trait]
+impl DidCacheStore for PgDidCache {
+    async fn get(&self, _did: &str) -> Result<Option<CacheVal>> {
+        use crate::schema::did_cache::dsl::*;
+
+        let _did = _did.to_string();
+        let entry = self
+            .run(move |conn| {
+                Ok(did_cache
+                    .filter(did.eq(_did))
+                    .select(DidCacheEntry::as_select())
+                    .first(conn)
+                    .optional()?)
+            })
+            .await?;
+        match entry {
+            None => Ok(None),
+            Some(entry) => Ok(Some(CacheVal {
+                doc: serde_json::from_str(&entry.doc)?,
+                updated_at: entry.updated_at as u128,
+            })),
+        }
+    }
+
+    async fn put(&self, _did: String, val: CacheVal) -> Result<()> {
+        use crate::schema::did_cache::dsl::*;
+
+        let new_entry = (
+            did.eq(_did),
+            doc.eq(serde_json::to_string(&val.doc)?),
+            updated_at.eq(val.updated_at as i64),
+        );
+        let evict_over = self.max_entries.filter(|_| {
+            self.puts
+                .fetch_add(1, Ordering::Relaxed)
+                .is_multiple_of(EVICT_EVERY)
+        });
+        self.run(move |conn| {
+            diesel::insert_into(did_cache)
+                .values(&new_entry)
+                .on_conflict(did)
+                .do_update()
+                .set(new_entry.clone())
+                .execute(conn)?;
+            match evict_over {
+                Some(max_entries) => evict(max_entries, conn),
+                None => Ok(()),
+            }
+        })
+        .await
+    }
+
+    async fn remove(&self, _did: &str) -> Result<()> {
+        use crate::schema::did_cache::dsl::*;
+
+        let _did = _did.to_string();
+        self.run(move |conn| {
+            diesel::delete(did_cache.filter(did.eq(_did))).execute(conn)?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn clear(&self) -> Result<()> {
+        use crate::schema::did_cache::dsl::*;
+
+        self.run(|conn| {
+            diesel::delete(did_cache).execute(conn)?;
+            Ok(())
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rsky_identity::types::DidDocument;
+
+    fn val(_did: &str, updated_at: u128) -> CacheVal {
+        CacheVal {
+            doc: DidDocument {
+                context: None,
+                id: _did.to_string(),
+                also_known_as: None,
+                verification_method: None,
+                service: None,
+            },
+            updated_at,
+        }
+    }
+
+    // One test, since eviction trims the whole table
+    #[rocket::async_test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    async fn test_pg_did_cache() {
+        let cache = PgDidCache::new(Some(2));
+        cache.clear().await.unwrap();
+
+        cache
+            .put("did:plc:a".to_string(), val("did:plc:a", 1))
+            .await
+            .unwrap();
+        cache
+            .put("did:plc:b".to_string(), val("did:plc:b", 2))
+            .await
+            .unwrap();
+        cache
+            .put("did:plc:a".to_string(), val("did:plc:a", 3))
+            .await
+            .unwrap();
+        // Reads leave entries untouched
+        let a = cache.get("did:plc:a").await.unwrap().unwrap();
+        assert_eq!((a.doc.id.as_str(), a.updated_at), ("did:plc:a", 3));
+        cache.remove("did:plc:b").await.unwrap();
+        assert!(cache.get("did:plc:b").await.unwrap().is_none());
+
+        // Evicts down to the two most recently resolved, on every EVICT_EVERY-th put
+        cache.puts.store(EVICT_EVERY - 1, Ordering::Relaxed);
+        cache
+            .put("did:plc:c".to_string(), val("did:plc:c", 4))
+            .await
+            .unwrap();
+        cache
+            .put("did:plc:d".to_string(), val("did:plc:d", 5))
+            .await
+            .unwrap();
+        assert!(cache.get("did:plc:a").await.unwrap().is_none());
+        assert!(cache.get("did:plc:c").await.unwrap().is_some());
+        assert!(cache.get("did:plc:d").await.unwrap().is_some());
+
+        cache.clear().await.unwrap();
+    }
+}
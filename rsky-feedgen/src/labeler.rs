@@ -0,0 +1,766 @@
+use crate::models::LabelerLabel;
+use chrono::{DateTime, SecondsFormat, SubsecRound, Utc};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::sql_types::BigInt;
+use lazy_static::lazy_static;
+use rocket::futures::{Sink, SinkExt, Stream, StreamExt};
+use rocket_ws::Message as WsMessage;
+use rsky_crypto::dag_cbor;
+use rsky_crypto::secp256k1::keypair::Secp256k1Keypair;
+use rsky_crypto::types::Keypair;
+use rsky_crypto::verify::verify_signature;
+use rsky_identity::did::atproto_data::{get_did_key_from_multibase, get_verification_material};
+use rsky_identity::IdResolver;
+use rsky_lexicon::com::atproto::label::{Label, QueryLabelsOutput, SubscribeLabelsLabels};
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::future::Future;
+use std::time::Duration;
+
+/// Applied to posts with images or video but no alt text.
+pub const NO_ALT_TEXT: &str = "no-alt-text";
+
+pub const QUERY_LABELS_DEFAULT_LIMIT: i64 = 50;
+pub const QUERY_LABELS_MAX_LIMIT: i64 = 250;
+
+/// How often subscribers are checked for newly emitted labels.
+pub const LABEL_POLL_INTERVAL: Duration = Duration::from_secs(1);
+pub const LABEL_BATCH_SIZE: i64 = 500;
+
+/// Advisory lock held while a label's sequence number is allocated and committed.
+const LABEL_SEQ_LOCK: i64 = 0x6c61_6265_6c5f_7365;
+
+lazy_static! {
+    /// Set when both `LABELER_DID` and `LABELER_SIGNING_KEY` are configured. Without them no
+    /// labels are emitted, but anything published before is still served.
+    pub static ref LABELER: Option<Labeler> = Labeler::from_env();
+}
+
+/// This service's labeler: the DID it publishes as and the secp256k1 key it signs labels with.
+pub struct Labeler {
+    pub did: String,
+    keypair: Secp256k1Keypair,
+}
+
+/// A label as it gets signed: `sig` left out and the remaining fields encoded as DAG-CBOR.
+#[derive(Serialize)]
+struct UnsignedLabel<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cid: Option<&'a str>,
+    cts: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exp: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    neg: Option<bool>,
+    src: &'a str,
+    uri: &'a str,
+    val: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ver: Option<u8>,
+}
+
+#[derive(Serialize)]
+struct FrameHeader<'a> {
+    op: i8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    t: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct ErrorFrame<'a> {
+    error: &'a str,
+    message: &'a str,
+}
+
+impl Labeler {
+    fn from_env() -> Option<Self> {
+        let did = env::var("LABELER_DID").ok()?;
+        // Hex encoded private key
+        let signing_key = env::var("LABELER_SIGNING_KEY").ok()?;
+        match hex::decode(signing_key)
+            .map_err(|error| error.to_string())
+            .and_then(|signing_key| {
+                Secp256k1Keypair::import(&signing_key, false).map_err(|error| error.to_string())
+            }) {
+            Ok(keypair) => Some(Self { did, keypair }),
+            Err(error) => {
+                tracing::error!("Invalid LABELER_SIGNING_KEY: {error}");
+                None
+            }
+        }
+    }
+
+    /// Publishes or negates `val` on `uri` so that it ends up applied exactly when `apply` is
+    /// set. Nothing is emitted if the label is already in that state.
+    pub fn sync_label(
+        &self,
+        uri: &str,
+        cid: &str,
+        val: &str,
+        apply: bool,
+        conn: &mut PgConnection,
+    ) {
+        use crate::schema::labeler_label::dsl as LabelerLabelSchema;
+
+        // Without the lock a subscriber could read seq N + 1 and move its cursor past N before N
+        // commits, never seeing it. Holding it until commit makes labels commit in seq order.
+        conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            diesel::sql_query("SELECT pg_advisory_xact_lock($1)")
+                .bind::<BigInt, _>(LABEL_SEQ_LOCK)
+                .execute(conn)?;
+
+            let applied = LabelerLabelSchema::labeler_label
+                .filter(LabelerLabelSchema::src.eq(&self.did))
+                .filter(LabelerLabelSchema::uri.eq(uri))
+                .filter(LabelerLabelSchema::val.eq(val))
+                .order(LabelerLabelSchema::seq.desc())
+                .select(LabelerLabelSchema::neg)
+                .first::<bool>(conn)
+                .optional()?
+                .is_some_and(|neg| !neg);
+            if applied == apply {
+                return Ok(());
+            }
+
+            // Truncated to milliseconds so the signed string survives a round trip through
+            // `Label`
+            let cts = Utc::now()
+                .trunc_subsecs(3)
+                .to_rfc3339_opts(SecondsFormat::AutoSi, true);
+            let unsigned = UnsignedLabel {
+                cid: Some(cid),
+                cts: cts.as_str(),
+                exp: None,
+                neg: (!apply).then_some(true),
+                src: self.did.as_str(),
+                uri,
+                val,
+                ver: Some(1),
+            };
+            let sig = match dag_cbor::encode(&unsigned)
+                .map_err(|error| error.to_string())
+                .and_then(|bytes| self.keypair.sign(&bytes).map_err(|error| error.to_string()))
+            {
+                Ok(sig) => sig,
+                Err(error) => {
+                    tracing::error!("@LOG: Failed to sign label {val:?} for {uri:?}: {error}");
+                    return Ok(());
+                }
+            };
+
+            let new_label = (
+                LabelerLabelSchema::src.eq(&self.did),
+                LabelerLabelSchema::uri.eq(uri),
+                LabelerLabelSchema::cid.eq(Some(cid)),
+                LabelerLabelSchema::val.eq(val),
+                LabelerLabelSchema::neg.eq(!apply),
+                LabelerLabelSchema::cts.eq(&cts),
+                LabelerLabelSchema::exp.eq(None::<String>),
+                LabelerLabelSchema::sig.eq(sig),
+            );
+            diesel::insert_into(LabelerLabelSchema::labeler_label)
+                .values(&new_label)
+                .execute(conn)?;
+            Ok(())
+        })
+        .expect("Error syncing labeler label records");
+    }
+}
+
+impl From<LabelerLabel> for Label {
+    fn from(row: LabelerLabel) -> Self {
+        let parse = |timestamp: &str| {
+            timestamp
+                .parse::<DateTime<Utc>>()
+                .expect("Invalid labeler label timestamp")
+        };
+        Label {
+            ver: Some(1),
+            src: row.src,
+            uri: row.uri,
+            cid: row.cid,
+            val: row.val,
+            neg: if row.neg { Some(true) } else { None },
+            cts: parse(&row.cts),
+            exp: row.exp.as_deref().map(parse),
+            sig: Some(row.sig),
+        }
+    }
+}
+
+/// The DAG-CBOR encodings `label` may have been signed as. Its timestamps were parsed on the way
+/// in, so they're written back both the way JavaScript labelers write them (always with
+/// milliseconds) and the way this one does (fractional seconds only when there are any).
+fn signed_bytes(label: &Label) -> Vec<Vec<u8>> {
+    let mut encodings: Vec<Vec<u8>> = [SecondsFormat::Millis, SecondsFormat::AutoSi]
+        .into_iter()
+        .filter_map(|format| {
+            let cts = label.cts.to_rfc3339_opts(format, true);
+            let exp = label.exp.map(|exp| exp.to_rfc3339_opts(format, true));
+            dag_cbor::encode(&UnsignedLabel {
+                cid: label.cid.as_deref(),
+                cts: &cts,
+                exp: exp.as_deref(),
+                neg: label.neg,
+                src: &label.src,
+                uri: &label.uri,
+                val: &label.val,
+                ver: label.ver,
+            })
+            .ok()
+        })
+        .collect();
+    encodings.dedup();
+    encodings
+}
+
+/// Whether `label` carries a signature by `did_key`.
+pub fn verify_label(label: &Label, did_key: &str) -> bool {
+    let Some(sig) = &label.sig else {
+        return false;
+    };
+    let did_key = did_key.to_string();
+    signed_bytes(label)
+        .iter()
+        .any(|bytes| verify_signature(&did_key, bytes, sig, None).unwrap_or(false))
+}
+
+/// The `did:key` that `did` signs labels with: its `#atproto_label` verification method.
+async fn label_key(resolver: &mut IdResolver, did: &str, force_refresh: bool) -> Option<String> {
+    let doc = match resolver
+        .did
+        .resolve(did.to_string(), Some(force_refresh))
+        .await
+    {
+        Ok(doc) => doc?,
+        Err(error) => {
+            tracing::error!("@LOG: Failed to resolve labeler {did:?}: {error:?}");
+            return None;
+        }
+    };
+    let key = get_verification_material(&doc, "atproto_label")?;
+    get_did_key_from_multibase(key).ok().flatten()
+}
+
+/// The `labels` signed with their labeler's `#atproto_label` key. The rest are logged and
+/// dropped.
+pub async fn verified_labels(labels: Vec<Label>, resolver: &mut IdResolver) -> Vec<Label> {
+    let mut keys: HashMap<String, Option<String>> = HashMap::new();
+    let mut refreshed = HashSet::new();
+    let mut verified = Vec::with_capacity(labels.len());
+    for label in labels {
+        let key = match keys.get(&label.src) {
+            Some(key) => key.clone(),
+            None => {
+                let key = label_key(resolver, &label.src, false).await;
+                keys.insert(label.src.clone(), key.clone());
+                key
+            }
+        };
+        let mut valid = key.as_deref().is_some_and(|key| verify_label(&label, key));
+        // The key may have rotated since it was cached, so check again, once, if it has
+        if !valid && refreshed.insert(label.src.clone()) {
+            let fresh_key = label_key(resolver, &label.src, true).await;
+            if fresh_key != key {
+                valid = fresh_key
+                    .as_deref()
+                    .is_some_and(|key| verify_label(&label, key));
+                keys.insert(label.src.clone(), fresh_key);
+            }
+        }
+        if valid {
+            verified.push(label);
+        } else {
+            tracing::warn!(
+                "@LOG: Dropping label {:?} on {:?} without a valid signature from {:?}",
+                label.val,
+                label.uri,
+                label.src
+            );
+        }
+    }
+    verified
+}
+
+/// Escapes `%`, `_` and `\` so a URI prefix can be used in a LIKE pattern.
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// Labels matching `com.atproto.label.queryLabels`: `uri_patterns` are exact URIs or prefixes
+/// ending in `*`, and an empty `sources` matches every labeler.
+pub fn query_labels(
+    uri_patterns: Vec<String>,
+    sources: Vec<String>,
+    limit: i64,
+    cursor: Option<i64>,
+    conn: &mut PgConnection,
+) -> QueryLabelsOutput {
+    use crate::schema::labeler_label::dsl as LabelerLabelSchema;
+
+    let mut query = LabelerLabelSchema::labeler_label.into_boxed();
+    for pattern in uri_patterns {
+        query = match pattern.strip_suffix('*') {
+            Some(prefix) => {
+                query.or_filter(LabelerLabelSchema::uri.like(format!("{}%", escape_like(prefix))))
+            }
+            None => query.or_filter(LabelerLabelSchema::uri.eq(pattern)),
+        };
+    }
+    if !sources.is_empty() {
+        query = query.filter(LabelerLabelSchema::src.eq_any(sources));
+    }
+    if let Some(cursor) = cursor {
+        query = query.filter(LabelerLabelSchema::seq.gt(cursor));
+    }
+    let rows = query
+        .order(LabelerLabelSchema::seq.asc())
+        .limit(limit)
+        .select(LabelerLabel::as_select())
+        .load(conn)
+        .expect("Error loading labeler label records");
+
+    QueryLabelsOutput {
+        cursor: rows.last().map(|row| row.seq.to_string()),
+        labels: rows.into_iter().map(Label::from).collect(),
+    }
+}
+
+/// Labels emitted after `cursor`, one `#labels` message per sequence number.
+pub fn labels_since(
+    cursor: i64,
+    limit: i64,
+    conn: &mut PgConnection,
+) -> Vec<SubscribeLabelsLabels> {
+    use crate::schema::labeler_label::dsl as LabelerLabelSchema;
+
+    LabelerLabelSchema::labeler_label
+        .filter(LabelerLabelSchema::seq.gt(cursor))
+        .order(LabelerLabelSchema::seq.asc())
+        .limit(limit)
+        .select(LabelerLabel::as_select())
+        .load(conn)
+        .expect("Error loading labeler label records")
+        .into_iter()
+        .map(|row| SubscribeLabelsLabels {
+            seq: row.seq,
+            labels: vec![row.into()],
+        })
+        .collect()
+}
+
+/// Sequence number of the most recent label, or 0 before any were emitted.
+pub fn latest_seq(conn: &mut PgConnection) -> i64 {
+    use crate::schema::labeler_label::dsl as LabelerLabelSchema;
+
+    LabelerLabelSchema::labeler_label
+        .select(diesel::dsl::max(LabelerLabelSchema::seq))
+        .first::<Option<i64>>(conn)
+        .expect("Error loading labeler sequence")
+        .unwrap_or(0)
+}
+
+/// Serves a `com.atproto.label.subscribeLabels` stream until the client goes away: labels after
+/// `cursor`, or after `latest` without one, then each label as it's emitted. `labels_since`
+/// loads the next batch of up to [`LABEL_BATCH_SIZE`] after a sequence number.
+pub async fn subscribe_labels<S, E, F, Fut>(
+    mut stream: S,
+    cursor: Option<i64>,
+    latest: i64,
+    mut labels_since: F,
+) -> Result<(), E>
+where
+    S: Sink<WsMessage, Error = E> + Stream<Item = Result<WsMessage, E>> + Unpin,
+    F: FnMut(i64) -> Fut,
+    Fut: Future<Output = Vec<SubscribeLabelsLabels>>,
+{
+    let mut seq = match cursor {
+        Some(cursor) if cursor > latest => {
+            if let Ok(frame) = error_frame("FutureCursor", "Cursor in the future.") {
+                stream.send(WsMessage::Binary(frame)).await?;
+            }
+            return stream.send(WsMessage::Close(None)).await;
+        }
+        Some(cursor) => cursor,
+        // Without a cursor only labels emitted from now on are sent
+        None => latest,
+    };
+
+    loop {
+        let batch = labels_since(seq).await;
+        for labels in &batch {
+            match labels_frame(labels) {
+                Ok(frame) => stream.send(WsMessage::Binary(frame)).await?,
+                Err(error) => tracing::error!("@LOG: Failed to encode label frame: {error:?}"),
+            }
+            seq = labels.seq;
+        }
+        if batch.len() as i64 == LABEL_BATCH_SIZE {
+            continue;
+        }
+        rocket::tokio::select! {
+            message = stream.next() => match message {
+                Some(Ok(WsMessage::Close(_))) | Some(Err(_)) | None => return Ok(()),
+                Some(Ok(_)) => {}
+            },
+            _ = rocket::tokio::time::sleep(LABEL_POLL_INTERVAL) => {}
+        }
+    }
+}
+
+/// Encodes a `com.atproto.label.subscribeLabels#labels` frame.
+pub fn labels_frame(labels: &SubscribeLabelsLabels) -> Result<Vec<u8>, serde_cbor::Error> {
+    let mut frame = serde_cbor::to_vec(&FrameHeader {
+        op: 1,
+        t: Some("#labels"),
+    })?;
+    frame.extend(serde_cbor::to_vec(labels)?);
+    Ok(frame)
+}
+
+/// Encodes an error frame, after which the stream is closed.
+pub fn error_frame(error: &str, message: &str) -> Result<Vec<u8>, serde_cbor::Error> {
+    let mut frame = serde_cbor::to_vec(&FrameHeader { op: -1, t: None })?;
+    frame.extend(serde_cbor::to_vec(&ErrorFrame { error, message })?);
+    Ok(frame)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::test_connection;
+    use rsky_identity::transport::MockTransport;
+    use rsky_identity::types::IdentityResolverOpts;
+    use serde_json::json;
+    use std::collections::VecDeque;
+    use std::convert::Infallible;
+    use std::pin::Pin;
+    use std::sync::Arc;
+    use std::task::{Context, Poll};
+
+    const LABELER_DID: &str = "did:plc:ar7c4by46qjdydhdevvrndac";
+    const POST: &str = "at://did:plc:6wthaiuqiys3y7eztkpsdam2/app.bsky.feed.post/3latjcehsho2n";
+    const CID: &str = "bafyreigw5ufnkavdzcczl2dusa3bcnkckhi4tscp6qsrsmg76s3ckseney";
+
+    /// A websocket whose client sends `incoming` and then disconnects.
+    #[derive(Default)]
+    struct TestSocket {
+        incoming: VecDeque<WsMessage>,
+        sent: Vec<WsMessage>,
+    }
+
+    impl Stream for TestSocket {
+        type Item = Result<WsMessage, Infallible>;
+
+        fn poll_next(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+            Poll::Ready(self.incoming.pop_front().map(Ok))
+        }
+    }
+
+    impl Sink<WsMessage> for TestSocket {
+        type Error = Infallible;
+
+        fn poll_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn start_send(mut self: Pin<&mut Self>, message: WsMessage) -> Result<(), Infallible> {
+            self.sent.push(message);
+            Ok(())
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    fn labels(seq: i64) -> SubscribeLabelsLabels {
+        SubscribeLabelsLabels {
+            seq,
+            labels: vec![Label {
+                ver: Some(1),
+                src: LABELER_DID.to_string(),
+                uri: POST.to_string(),
+                cid: None,
+                val: NO_ALT_TEXT.to_string(),
+                neg: None,
+                cts: "2024-11-14T09:02:11.512Z".parse().unwrap(),
+                exp: None,
+                sig: Some(vec![seq as u8; 64]),
+            }],
+        }
+    }
+
+    fn frames(seqs: impl Iterator<Item = i64>) -> Vec<WsMessage> {
+        seqs.map(|seq| WsMessage::Binary(labels_frame(&labels(seq)).unwrap()))
+            .collect()
+    }
+
+    #[rocket::async_test]
+    async fn test_subscribe_labels_rejects_future_cursors() {
+        let mut socket = TestSocket::default();
+        subscribe_labels(&mut socket, Some(4), 3, |_| async {
+            unreachable!("nothing is loaded for a future cursor")
+        })
+        .await
+        .unwrap();
+        assert_eq!(
+            socket.sent,
+            [
+                WsMessage::Binary(error_frame("FutureCursor", "Cursor in the future.").unwrap()),
+                WsMessage::Close(None),
+            ]
+        );
+    }
+
+    #[rocket::async_test]
+    async fn test_subscribe_labels_backfills_in_batches() {
+        let emitted = LABEL_BATCH_SIZE + 1;
+        let mut socket = TestSocket::default();
+        let mut loaded_after = Vec::new();
+        subscribe_labels(&mut socket, Some(0), emitted, |seq| {
+            loaded_after.push(seq);
+            let end = emitted.min(seq + LABEL_BATCH_SIZE);
+            std::future::ready((seq + 1..=end).map(labels).collect())
+        })
+        .await
+        .unwrap();
+        assert_eq!(loaded_after, [0, LABEL_BATCH_SIZE]);
+        assert_eq!(socket.sent, frames(1..=emitted));
+    }
+
+    #[rocket::async_test]
+    async fn test_subscribe_labels_without_cursor_sends_new_labels() {
+        let mut socket = TestSocket {
+            incoming: VecDeque::from([WsMessage::Ping(Vec::new())]),
+            ..Default::default()
+        };
+        // Label 3 is emitted between the first poll and the second
+        let mut batches = VecDeque::from([vec![], vec![labels(3)]]);
+        let mut loaded_after = Vec::new();
+        subscribe_labels(&mut socket, None, 2, |seq| {
+            loaded_after.push(seq);
+            std::future::ready(batches.pop_front().unwrap_or_default())
+        })
+        .await
+        .unwrap();
+        assert_eq!(loaded_after, [2, 2]);
+        assert_eq!(socket.sent, frames(3..=3));
+    }
+
+    /// `label` signed by `keypair`, with its timestamps written the way `format` writes them.
+    fn signed(label: Label, keypair: &Secp256k1Keypair, format: SecondsFormat) -> Label {
+        let cts = label.cts.to_rfc3339_opts(format, true);
+        let unsigned = UnsignedLabel {
+            cid: label.cid.as_deref(),
+            cts: &cts,
+            exp: None,
+            neg: label.neg,
+            src: &label.src,
+            uri: &label.uri,
+            val: &label.val,
+            ver: label.ver,
+        };
+        let sig = keypair.sign(&dag_cbor::encode(&unsigned).unwrap()).unwrap();
+        Label {
+            sig: Some(sig),
+            ..label
+        }
+    }
+
+    #[test]
+    fn test_verify_label() {
+        let keypair = Secp256k1Keypair::create(false);
+        let did_key = keypair.did().unwrap();
+        let label = labels(1).labels.remove(0);
+
+        let js_signed = signed(label.clone(), &keypair, SecondsFormat::Millis);
+        assert!(verify_label(&js_signed, &did_key));
+        let whole_seconds = Label {
+            cts: "2024-11-14T09:02:11Z".parse().unwrap(),
+            ..label.clone()
+        };
+        assert!(verify_label(
+            &signed(whole_seconds.clone(), &keypair, SecondsFormat::Millis),
+            &did_key
+        ));
+        assert!(verify_label(
+            &signed(whole_seconds, &keypair, SecondsFormat::AutoSi),
+            &did_key
+        ));
+
+        let tampered = Label {
+            val: "porn".to_string(),
+            ..js_signed.clone()
+        };
+        assert!(!verify_label(&tampered, &did_key));
+        let unsigned = Label { sig: None, ..label };
+        assert!(!verify_label(&unsigned, &did_key));
+        let other_key = Secp256k1Keypair::create(false).did().unwrap();
+        assert!(!verify_label(&js_signed, &other_key));
+    }
+
+    #[rocket::async_test]
+    async fn test_verified_labels_checks_the_labelers_key() {
+        let labeler = "did:web:labeler.test";
+        let keypair = Secp256k1Keypair::create(false);
+        let did_key = keypair.did().unwrap();
+        let transport = MockTransport::new();
+        transport.insert_json(
+            "https://labeler.test/.well-known/did.json",
+            &json!({
+                "id": labeler,
+                "verificationMethod": [{
+                    "id": "#atproto_label",
+                    "type": "Multikey",
+                    "controller": labeler,
+                    "publicKeyMultibase": did_key.strip_prefix("did:key:").unwrap(),
+                }],
+            }),
+        );
+        let transport = Arc::new(transport);
+        let mut resolver = IdResolver::new(IdentityResolverOpts {
+            timeout: None,
+            plc_url: None,
+            did_cache: None,
+            backup_nameservers: None,
+            backup_nameserver_port: None,
+            handle_cache_ttl: None,
+            transport: Some(transport.clone()),
+        });
+
+        let label = |src: &str, val: &str| Label {
+            src: src.to_string(),
+            val: val.to_string(),
+            ..labels(1).labels.remove(0)
+        };
+        let valid = signed(label(labeler, "spam"), &keypair, SecondsFormat::Millis);
+        let tampered = Label {
+            val: "porn".to_string(),
+            ..valid.clone()
+        };
+        let unresolvable = signed(
+            label("did:web:nobody.test", "spam"),
+            &keypair,
+            SecondsFormat::Millis,
+        );
+        let verified =
+            verified_labels(vec![valid.clone(), tampered, unresolvable], &mut resolver).await;
+        assert_eq!(verified, [valid]);
+        // Resolved once, then once more to rule out a rotated key
+        let labeler_requests = transport
+            .requests()
+            .into_iter()
+            .filter(|url| url == "https://labeler.test/.well-known/did.json")
+            .count();
+        assert_eq!(labeler_requests, 2);
+    }
+
+    #[test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    fn test_sync_label_publishes_and_negates_once() {
+        use crate::schema::labeler_label::dsl as LabelerLabelSchema;
+
+        let conn = &mut test_connection();
+        let labeler = Labeler {
+            did: LABELER_DID.to_string(),
+            keypair: Secp256k1Keypair::create(false),
+        };
+        let rows = |conn: &mut PgConnection| {
+            LabelerLabelSchema::labeler_label
+                .filter(LabelerLabelSchema::uri.eq(POST))
+                .order(LabelerLabelSchema::seq.asc())
+                .select(LabelerLabel::as_select())
+                .load(conn)
+                .unwrap()
+        };
+
+        labeler.sync_label(POST, CID, NO_ALT_TEXT, false, conn);
+        assert!(rows(conn).is_empty());
+        labeler.sync_label(POST, CID, NO_ALT_TEXT, true, conn);
+        labeler.sync_label(POST, CID, NO_ALT_TEXT, true, conn);
+        labeler.sync_label(POST, CID, NO_ALT_TEXT, false, conn);
+        labeler.sync_label(POST, CID, NO_ALT_TEXT, false, conn);
+        let rows = rows(conn);
+        assert_eq!(
+            rows.iter().map(|row| row.neg).collect::<Vec<_>>(),
+            [false, true]
+        );
+
+        let did_key = labeler.keypair.did().unwrap();
+        assert!(rows
+            .into_iter()
+            .all(|row| verify_label(&Label::from(row), &did_key)));
+    }
+
+    #[test]
+    #[ignore = "needs a migrated database in DATABASE_URL"]
+    fn test_query_and_stream_labels() {
+        use crate::schema::labeler_label::dsl as LabelerLabelSchema;
+
+        let conn = &mut test_connection();
+        let mut insert = |src: &str, uri: &str| -> i64 {
+            diesel::insert_into(LabelerLabelSchema::labeler_label)
+                .values((
+                    LabelerLabelSchema::src.eq(src),
+                    LabelerLabelSchema::uri.eq(uri),
+                    LabelerLabelSchema::val.eq(NO_ALT_TEXT),
+                    LabelerLabelSchema::cts.eq("2024-11-14T09:02:11.512Z"),
+                    LabelerLabelSchema::sig.eq(vec![0u8; 64]),
+                ))
+                .returning(LabelerLabelSchema::seq)
+                .get_result(conn)
+                .unwrap()
+        };
+        let alice_post = "at://did:plc:alice/app.bsky.feed.post/3latjcehsho2n";
+        let first = insert(LABELER_DID, alice_post);
+        let second = insert(
+            LABELER_DID,
+            "at://did:plc:alice/app.bsky.feed.post/3latjd3mzpk2w",
+        );
+        let other_source = insert("did:plc:labeler", alice_post);
+        let lookalike = insert(
+            LABELER_DID,
+            "at://did:plc:a_ice/app.bsky.feed.post/3latjcehsho2n",
+        );
+
+        let uris = |output: QueryLabelsOutput| -> Vec<String> {
+            output
+                .labels
+                .iter()
+                .map(|label| label.uri.clone())
+                .collect()
+        };
+        let prefix = vec!["at://did:plc:a_ice/*".to_string()];
+        let matched = query_labels(prefix, vec![], 10, None, conn);
+        assert_eq!(matched.cursor, Some(lookalike.to_string()));
+        assert_eq!(uris(matched).len(), 1);
+
+        let prefix = vec!["at://did:plc:alice/*".to_string()];
+        let sources = vec![LABELER_DID.to_string()];
+        let page = query_labels(prefix.clone(), sources.clone(), 1, None, conn);
+        assert_eq!(page.cursor, Some(first.to_string()));
+        let page = query_labels(prefix.clone(), sources, 10, Some(first), conn);
+        assert_eq!(page.cursor, Some(second.to_string()));
+        assert_eq!(page.labels.len(), 1);
+        let exact = query_labels(vec![alice_post.to_string()], vec![], 10, None, conn);
+        assert_eq!(exact.cursor, Some(other_source.to_string()));
+        assert_eq!(uris(exact), [alice_post, alice_post]);
+
+        assert_eq!(latest_seq(conn), lookalike);
+        let streamed = labels_since(first, 2, conn);
+        assert_eq!(
+            streamed.iter().map(|labels| labels.seq).collect::<Vec<_>>(),
+            [second, other_source]
+        );
+        assert!(streamed.iter().all(|labels| labels.labels.len() == 1));
+    }
+}
@@ -20,5 +20,9 @@ pub mod agent;
 pub mod apis;
 pub mod auth;
 pub mod db;
+pub mod did_cache;
+pub mod labeler;
 pub mod models;
 pub mod schema;
+#[cfg(test)]
+mod test_utils;
@@ -10,11 +10,19 @@ use rocket::http::Header;
 use rocket::http::Status;
 use rocket::response::status;
 use rocket::serde::json::Json;
-use rocket::{Request, Response};
+use rocket::{Either, Request, Response};
+use rocket_ws::WebSocket;
 use rsky_feedgen::models::{
-    AlgoResponse, FollowingPreference, JwtParts, PostResult, UserFeedPreference,
+    FollowingPreference, JwtParts, LabelPreference, ListFeedPreference, UserFeedPreference,
 };
 use rsky_feedgen::{ReadReplicaConn, WriteDbConn};
+use rsky_lexicon::app::bsky::actor::{ContentLabelPref, ContentLabelVisibility};
+use rsky_lexicon::app::bsky::feed::get_feed_skeleton::{
+    GetFeedSkeletonOutput, GetFeedSkeletonParams,
+};
+use rsky_lexicon::app::bsky::feed::SkeletonFeedPost;
+use rsky_lexicon::com::atproto::label::{Label, QueryLabelsOutput};
+use rsky_lexicon::validation::Validate;
 use std::env;
 
 pub struct CORS;
@@ -80,7 +88,7 @@ impl<'r> FromRequest<'r> for AccessToken {
                 let service_did = env::var("FEEDGEN_SERVICE_DID").unwrap_or("".into());
                 let jwt = token.split(" ").map(String::from).collect::<Vec<_>>();
                 if let Some(jwtstr) = jwt.last() {
-                    match rsky_feedgen::auth::verify_jwt(jwtstr, &service_did) {
+                    match rsky_feedgen::auth::verify_jwt(jwtstr, &service_did).await {
                         Ok(jwt_object) => Outcome::Success(AccessToken(jwt_object)),
                         Err(error) => {
                             tracing::error!("Error decoding jwt. {error:?}");
@@ -100,6 +108,11 @@ const FOLLOWING_TRAD: &str =
 const FOLLOWING_CLASSIC: &str =
     "at://did:plc:cimwguwdlh2i2mebdqczgcyl/app.bsky.feed.generator/follow-orig";
 const MEDIA: &str = "at://did:plc:nffcjkyymm3pzutbxobso2pa/app.bsky.feed.generator/media";
+const FOLLOWING_LIST: &str =
+    "at://did:plc:cimwguwdlh2i2mebdqczgcyl/app.bsky.feed.generator/follow-list";
+
+type ValidationFailure = status::Custom<Json<rsky_feedgen::models::ValidationErrorMessageResponse>>;
+type InternalFailure = status::Custom<Json<rsky_feedgen::models::InternalErrorMessageResponse>>;
 
 #[tracing::instrument(skip(connection))]
 #[get(
@@ -112,12 +125,37 @@ async fn index(
     cursor: Option<&str>,
     connection: ReadReplicaConn,
     _token: Result<AccessToken, AccessTokenError>,
-) -> Result<
-    Json<AlgoResponse>,
-    status::Custom<Json<rsky_feedgen::models::InternalErrorMessageResponse>>,
-> {
+) -> Result<Json<GetFeedSkeletonOutput>, Either<ValidationFailure, InternalFailure>> {
+    let params = GetFeedSkeletonParams {
+        feed: feed.unwrap_or_default().to_string(),
+        // A negative limit can't be a usize; 0 is just as far out of range
+        limit: limit.map(|limit| usize::try_from(limit).unwrap_or(0)),
+        cursor: cursor.map(String::from),
+    };
+    if let Err(errors) = params.validate() {
+        let validation_error = rsky_feedgen::models::ValidationErrorMessageResponse {
+            code: Some(rsky_feedgen::models::ErrorCode::ValidationError),
+            message: Some(errors.to_string()),
+        };
+        return Err(Either::Left(status::Custom(
+            Status::BadRequest,
+            Json(validation_error),
+        )));
+    }
+    feed_skeleton(params, connection, _token)
+        .await
+        .map_err(Either::Right)
+}
+
+async fn feed_skeleton(
+    params: GetFeedSkeletonParams,
+    connection: ReadReplicaConn,
+    _token: Result<AccessToken, AccessTokenError>,
+) -> Result<Json<GetFeedSkeletonOutput>, InternalFailure> {
     let mut did = String::from("did:plc:khvyd3oiw46vif5gm7hijslk");
-    let feed = feed.unwrap_or("");
+    let feed = params.feed.as_str();
+    let limit = params.limit.map(|limit| limit as i64);
+    let cursor = params.cursor.as_deref();
     if let Ok(jwt) = _token {
         match serde_json::from_str::<JwtParts>(&jwt.0) {
             Ok(jwt_obj) => {
@@ -165,18 +203,46 @@ async fn index(
                 }
             }
         }
+        _following_list if FOLLOWING_LIST == _following_list => {
+            if did.is_empty() {
+                let internal_error = rsky_feedgen::models::InternalErrorMessageResponse {
+                    code: Some(rsky_feedgen::models::InternalErrorCode::InternalError),
+                    message: Some("No DID".to_string()),
+                };
+                return Err(status::Custom(
+                    Status::InternalServerError,
+                    Json(internal_error),
+                ));
+            }
+            match rsky_feedgen::apis::get_posts_by_list_feed(did, limit, cursor, connection).await {
+                Ok(response) => Ok(Json(response)),
+                Err(error) => {
+                    tracing::error!("Internal Error: {error}");
+                    let internal_error = rsky_feedgen::models::InternalErrorMessageResponse {
+                        code: Some(rsky_feedgen::models::InternalErrorCode::InternalError),
+                        message: Some(error.to_string()),
+                    };
+                    Err(status::Custom(
+                        Status::InternalServerError,
+                        Json(internal_error),
+                    ))
+                }
+            }
+        }
         _following_trad if FOLLOWING_TRAD == _following_trad => {
             let mut post_results = Vec::new();
-            let post_result = PostResult {
+            let post_result = SkeletonFeedPost {
                 post: String::from(
                     "at://did:plc:cimwguwdlh2i2mebdqczgcyl/app.bsky.feed.post/3l4pi6irzsg2m",
                 ),
                 reason: None,
+                feed_context: None,
             };
             post_results.push(post_result);
-            let response = AlgoResponse {
+            let response = GetFeedSkeletonOutput {
                 cursor: Some(String::from("none")),
                 feed: post_results,
+                req_id: None,
             };
             Ok(Json(response))
         }
@@ -292,6 +358,148 @@ async fn queue_creation(
     }
 }
 
+#[tracing::instrument(skip(connection))]
+#[put("/queue/<lex>/update", format = "json", data = "<body>")]
+async fn queue_update(
+    lex: &str,
+    body: Json<Vec<rsky_feedgen::models::CreateRequest>>,
+    _key: ApiKey<'_>,
+    connection: WriteDbConn,
+) -> Result<(), status::Custom<Json<rsky_feedgen::models::InternalErrorMessageResponse>>> {
+    match rsky_feedgen::apis::queue_update(lex.to_string(), body.into_inner(), connection).await {
+        Ok(_) => Ok(()),
+        Err(error) => {
+            tracing::error!("Internal Error: {error}");
+            let internal_error = rsky_feedgen::models::InternalErrorMessageResponse {
+                code: Some(rsky_feedgen::models::InternalErrorCode::InternalError),
+                message: Some(error.to_string()),
+            };
+            Err(status::Custom(
+                Status::InternalServerError,
+                Json(internal_error),
+            ))
+        }
+    }
+}
+
+#[tracing::instrument(skip(connection))]
+#[put("/queue/identities/update", format = "json", data = "<body>")]
+async fn queue_identity_update(
+    body: Json<Vec<rsky_feedgen::models::IdentityUpdateRequest>>,
+    _key: ApiKey<'_>,
+    connection: WriteDbConn,
+) -> Result<(), status::Custom<Json<rsky_feedgen::models::InternalErrorMessageResponse>>> {
+    match rsky_feedgen::apis::queue_identity_update(body.into_inner(), connection).await {
+        Ok(_) => Ok(()),
+        Err(error) => {
+            tracing::error!("Internal Error: {error}");
+            let internal_error = rsky_feedgen::models::InternalErrorMessageResponse {
+                code: Some(rsky_feedgen::models::InternalErrorCode::InternalError),
+                message: Some(error.to_string()),
+            };
+            Err(status::Custom(
+                Status::InternalServerError,
+                Json(internal_error),
+            ))
+        }
+    }
+}
+
+#[tracing::instrument(skip(connection))]
+#[get("/handle?<did>", format = "json")]
+async fn get_handle(
+    did: &str,
+    _key: ApiKey<'_>,
+    connection: ReadReplicaConn,
+) -> Result<
+    Json<rsky_feedgen::models::ActorHandle>,
+    status::Custom<Json<rsky_feedgen::models::PathUnknownErrorMessageResponse>>,
+> {
+    match rsky_feedgen::apis::get_actor_handle(did.to_string(), connection).await {
+        Ok(response) => Ok(Json(response)),
+        Err(error) => Err(status::Custom(Status::NotFound, Json(error))),
+    }
+}
+
+#[tracing::instrument(skip(connection))]
+#[get("/stats?<visitors>", format = "json")]
+async fn get_stats(
+    visitors: Option<i64>,
+    _key: ApiKey<'_>,
+    connection: ReadReplicaConn,
+) -> Result<
+    Json<rsky_feedgen::models::FeedgenStats>,
+    status::Custom<Json<rsky_feedgen::models::InternalErrorMessageResponse>>,
+> {
+    match rsky_feedgen::apis::get_stats(visitors, connection).await {
+        Ok(response) => Ok(Json(response)),
+        Err(error) => {
+            tracing::error!("Internal Error: {error}");
+            let internal_error = rsky_feedgen::models::InternalErrorMessageResponse {
+                code: Some(rsky_feedgen::models::InternalErrorCode::InternalError),
+                message: Some(error.to_string()),
+            };
+            Err(status::Custom(
+                Status::InternalServerError,
+                Json(internal_error),
+            ))
+        }
+    }
+}
+
+#[derive(Debug, FromForm)]
+struct QueryLabelsParams {
+    #[field(name = "uriPatterns")]
+    uri_patterns: Vec<String>,
+    sources: Vec<String>,
+    limit: Option<i64>,
+    cursor: Option<String>,
+}
+
+#[tracing::instrument(skip(connection))]
+#[get("/xrpc/com.atproto.label.queryLabels?<params..>")]
+async fn query_labels(
+    params: QueryLabelsParams,
+    connection: ReadReplicaConn,
+) -> Result<
+    Json<QueryLabelsOutput>,
+    status::Custom<Json<rsky_feedgen::models::ValidationErrorMessageResponse>>,
+> {
+    match rsky_feedgen::apis::query_labels(
+        params.uri_patterns,
+        params.sources,
+        params.limit,
+        params.cursor,
+        connection,
+    )
+    .await
+    {
+        Ok(response) => Ok(Json(response)),
+        Err(error) => Err(status::Custom(Status::BadRequest, Json(error))),
+    }
+}
+
+#[get("/xrpc/com.atproto.label.subscribeLabels?<cursor>")]
+fn subscribe_labels(
+    ws: WebSocket,
+    cursor: Option<i64>,
+    connection: ReadReplicaConn,
+) -> rocket_ws::Channel<'static> {
+    ws.channel(move |stream| {
+        Box::pin(async move {
+            let latest = rsky_feedgen::apis::get_labeler_seq(&connection).await;
+            rsky_feedgen::labeler::subscribe_labels(stream, cursor, latest, |seq| {
+                rsky_feedgen::apis::get_labeler_labels(
+                    seq,
+                    rsky_feedgen::labeler::LABEL_BATCH_SIZE,
+                    &connection,
+                )
+            })
+            .await
+        })
+    })
+}
+
 #[get("/user_feed_preference?<did>", format = "json")]
 async fn user_config(
     did: &str,
@@ -349,6 +557,121 @@ async fn following_preferences_update(
     }
 }
 
+#[tracing::instrument(skip(connection))]
+#[put("/queue/labels/create", format = "json", data = "<body>")]
+async fn queue_label_creation(
+    body: Json<Vec<Label>>,
+    _key: ApiKey<'_>,
+    connection: WriteDbConn,
+) -> Result<(), status::Custom<Json<rsky_feedgen::models::InternalErrorMessageResponse>>> {
+    match rsky_feedgen::apis::queue_labels(body.into_inner(), connection).await {
+        Ok(_) => Ok(()),
+        Err(error) => {
+            tracing::error!("Internal Error: {error}");
+            let internal_error = rsky_feedgen::models::InternalErrorMessageResponse {
+                code: Some(rsky_feedgen::models::InternalErrorCode::InternalError),
+                message: Some(error.to_string()),
+            };
+            Err(status::Custom(
+                Status::InternalServerError,
+                Json(internal_error),
+            ))
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LabelPreferencesRequest {
+    pub did: String,
+    pub preferences: Vec<ContentLabelPref>,
+}
+
+#[get("/label_preferences?<did>", format = "json")]
+async fn label_preferences_fetch(
+    did: &str,
+    _key: ApiKey<'_>,
+    connection: WriteDbConn,
+) -> Result<
+    Json<Vec<LabelPreference>>,
+    status::Custom<Json<rsky_feedgen::models::InternalErrorMessageResponse>>,
+> {
+    let result = rsky_feedgen::db::label_pref_fetch(String::from(did), connection).await;
+    Ok(Json::from(result))
+}
+
+#[tracing::instrument(skip(connection))]
+#[put("/label_preferences", format = "json", data = "<body>")]
+async fn label_preferences_update(
+    body: Json<LabelPreferencesRequest>,
+    _key: ApiKey<'_>,
+    connection: WriteDbConn,
+) -> Result<(), status::Custom<Json<rsky_feedgen::models::InternalErrorMessageResponse>>> {
+    let body = body.into_inner();
+    let preferences = body
+        .preferences
+        .into_iter()
+        .map(|preference| LabelPreference {
+            did: body.did.clone(),
+            labeler_did: preference.labeler_did.unwrap_or_default(),
+            label: preference.label,
+            visibility: match preference.visibility {
+                ContentLabelVisibility::Ignore => "ignore",
+                ContentLabelVisibility::Show => "show",
+                ContentLabelVisibility::Warn => "warn",
+                ContentLabelVisibility::Hide => "hide",
+            }
+            .to_string(),
+        })
+        .collect();
+    match rsky_feedgen::db::label_pref_update(body.did, preferences, connection).await {
+        Ok(_) => Ok(()),
+        Err(error) => {
+            tracing::error!("Internal Error: {error}");
+            let internal_error = rsky_feedgen::models::InternalErrorMessageResponse {
+                code: Some(rsky_feedgen::models::InternalErrorCode::InternalError),
+                message: Some(error.to_string()),
+            };
+            Err(status::Custom(
+                Status::InternalServerError,
+                Json(internal_error),
+            ))
+        }
+    }
+}
+
+#[get("/list_feed_preference?<did>", format = "json")]
+async fn list_feed_preference_fetch(
+    did: &str,
+    _key: ApiKey<'_>,
+    connection: WriteDbConn,
+) -> Result<
+    Json<Vec<ListFeedPreference>>,
+    status::Custom<Json<rsky_feedgen::models::InternalErrorMessageResponse>>,
+> {
+    let result = rsky_feedgen::db::list_feed_pref_fetch(String::from(did), connection).await;
+    Ok(Json::from(result))
+}
+
+#[tracing::instrument(skip(connection))]
+#[put("/list_feed_preference", format = "json", data = "<body>")]
+async fn list_feed_preference_update(
+    body: Json<ListFeedPreference>,
+    _key: ApiKey<'_>,
+    connection: WriteDbConn,
+) -> Result<(), status::Custom<Json<rsky_feedgen::models::ValidationErrorMessageResponse>>> {
+    match rsky_feedgen::db::list_feed_pref_update(body.into_inner(), connection).await {
+        Ok(_) => Ok(()),
+        Err(error) => {
+            tracing::error!("Validation Error: {error}");
+            let validation_error = rsky_feedgen::models::ValidationErrorMessageResponse {
+                code: Some(rsky_feedgen::models::ErrorCode::ValidationError),
+                message: Some(error),
+            };
+            Err(status::Custom(Status::BadRequest, Json(validation_error)))
+        }
+    }
+}
+
 #[tracing::instrument(skip(connection))]
 #[put("/user_feed_preference", format = "json", data = "<body>")]
 async fn update_user_config(
@@ -551,13 +874,24 @@ fn rocket() -> _ {
                 user_config,
                 update_user_config,
                 queue_creation,
+                queue_update,
                 queue_deletion,
+                queue_identity_update,
+                get_handle,
+                get_stats,
                 well_known,
                 get_cursor,
                 update_cursor,
                 all_options,
                 following_preferences_fetch,
-                following_preferences_update
+                following_preferences_update,
+                list_feed_preference_fetch,
+                list_feed_preference_update,
+                queue_label_creation,
+                label_preferences_fetch,
+                label_preferences_update,
+                query_labels,
+                subscribe_labels
             ],
         )
         .register(
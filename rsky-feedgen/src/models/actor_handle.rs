@@ -0,0 +1,29 @@
+use diesel::prelude::*;
+
+/// The latest handle Jetstream reported for a DID.
+#[derive(
+    Queryable,
+    Selectable,
+    Clone,
+    Debug,
+    PartialEq,
+    Default,
+    Serialize,
+    Deserialize,
+    AsChangeset,
+    Insertable,
+)]
+#[diesel(table_name = crate::schema::actor_handle)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(treat_none_as_null = true)]
+pub struct ActorHandle {
+    #[serde(rename = "did")]
+    pub did: String,
+    #[serde(rename = "handle")]
+    pub handle: Option<String>,
+    #[serde(rename = "seq")]
+    pub seq: i64,
+    #[serde(rename = "indexedAt")]
+    #[diesel(column_name = indexedAt)]
+    pub indexed_at: String,
+}
@@ -0,0 +1,25 @@
+use diesel::prelude::*;
+
+#[derive(Queryable, Selectable, Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::block)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Block {
+    #[serde(rename = "uri")]
+    pub uri: String,
+    #[serde(rename = "cid")]
+    pub cid: String,
+    #[serde(rename = "author")]
+    pub author: String,
+    #[serde(rename = "subject")]
+    pub subject: String,
+    #[serde(rename = "createdAt")]
+    #[diesel(column_name = createdAt)]
+    pub created_at: String,
+    #[serde(rename = "indexedAt")]
+    #[diesel(column_name = indexedAt)]
+    pub indexed_at: String,
+    #[serde(rename = "prev", skip_serializing_if = "Option::is_none")]
+    pub prev: Option<String>,
+    #[serde(rename = "sequence")]
+    pub sequence: Option<i64>,
+}
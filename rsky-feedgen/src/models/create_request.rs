@@ -1,3 +1,5 @@
+use rsky_lexicon::validation::{Validate, ValidationErrors};
+
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(tag = "$type")]
 pub enum Lexicon {
@@ -15,6 +17,60 @@ pub enum Lexicon {
         serialize = "app.bsky.graph.follow"
     ))]
     AppBskyFeedFollow(rsky_lexicon::app::bsky::graph::follow::Follow),
+    #[serde(rename(
+        deserialize = "app.bsky.graph.block",
+        serialize = "app.bsky.graph.block"
+    ))]
+    AppBskyGraphBlock(rsky_lexicon::app::bsky::graph::block::Block),
+    #[serde(rename(deserialize = "app.bsky.graph.list", serialize = "app.bsky.graph.list"))]
+    AppBskyGraphList(Box<rsky_lexicon::app::bsky::graph::list::List>),
+    #[serde(rename(
+        deserialize = "app.bsky.graph.listitem",
+        serialize = "app.bsky.graph.listitem"
+    ))]
+    AppBskyGraphListItem(rsky_lexicon::app::bsky::graph::listitem::ListItem),
+    #[serde(rename(
+        deserialize = "app.bsky.feed.threadgate",
+        serialize = "app.bsky.feed.threadgate"
+    ))]
+    AppBskyFeedThreadgate(rsky_lexicon::app::bsky::feed::threadgate::Threadgate),
+    #[serde(rename(
+        deserialize = "app.bsky.feed.postgate",
+        serialize = "app.bsky.feed.postgate"
+    ))]
+    AppBskyFeedPostgate(rsky_lexicon::app::bsky::feed::postgate::Postgate),
+}
+
+impl Lexicon {
+    /// The collection NSID this record was received as.
+    pub fn nsid(&self) -> &'static str {
+        match self {
+            Lexicon::AppBskyFeedPost(_) => "app.bsky.feed.post",
+            Lexicon::AppBskyFeedRepost(_) => "app.bsky.feed.repost",
+            Lexicon::AppBskyFeedLike(_) => "app.bsky.feed.like",
+            Lexicon::AppBskyFeedFollow(_) => "app.bsky.graph.follow",
+            Lexicon::AppBskyGraphBlock(_) => "app.bsky.graph.block",
+            Lexicon::AppBskyGraphList(_) => "app.bsky.graph.list",
+            Lexicon::AppBskyGraphListItem(_) => "app.bsky.graph.listitem",
+            Lexicon::AppBskyFeedThreadgate(_) => "app.bsky.feed.threadgate",
+            Lexicon::AppBskyFeedPostgate(_) => "app.bsky.feed.postgate",
+        }
+    }
+
+    /// Checks the record against its lexicon's constraints.
+    pub fn validate(&self) -> Result<(), ValidationErrors> {
+        match self {
+            Lexicon::AppBskyFeedPost(record) => record.validate(),
+            Lexicon::AppBskyFeedRepost(record) => record.validate(),
+            Lexicon::AppBskyFeedLike(record) => record.validate(),
+            Lexicon::AppBskyFeedFollow(record) => record.validate(),
+            Lexicon::AppBskyGraphBlock(record) => record.validate(),
+            Lexicon::AppBskyGraphList(record) => record.validate(),
+            Lexicon::AppBskyGraphListItem(record) => record.validate(),
+            Lexicon::AppBskyFeedThreadgate(record) => record.validate(),
+            Lexicon::AppBskyFeedPostgate(record) => record.validate(),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
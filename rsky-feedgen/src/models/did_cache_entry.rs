@@ -0,0 +1,11 @@
+use diesel::prelude::*;
+
+/// A cached DID document, serialized as JSON.
+#[derive(Queryable, Selectable, Clone, Debug, PartialEq, Default)]
+#[diesel(table_name = crate::schema::did_cache)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct DidCacheEntry {
+    pub did: String,
+    pub doc: String,
+    pub updated_at: i64,
+}
@@ -0,0 +1,27 @@
+use rsky_identity::types::DidCacheStats;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Serialize)]
+pub struct FeedgenStats {
+    /// Counted since this worker started
+    #[serde(rename = "didCache")]
+    pub did_cache: DidCacheStats,
+    #[serde(rename = "indexedHandles")]
+    pub indexed_handles: i64,
+    /// Records skipped for breaking their lexicon, by collection NSID. Counted since this
+    /// worker started
+    #[serde(rename = "invalidRecords")]
+    pub invalid_records: BTreeMap<String, u64>,
+    #[serde(rename = "topVisitors")]
+    pub top_visitors: Vec<VisitorStats>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VisitorStats {
+    #[serde(rename = "did")]
+    pub did: String,
+    #[serde(rename = "handle", skip_serializing_if = "Option::is_none")]
+    pub handle: Option<String>,
+    #[serde(rename = "visits")]
+    pub visits: i64,
+}
@@ -0,0 +1,9 @@
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IdentityUpdateRequest {
+    #[serde(rename = "did")]
+    pub did: String,
+    #[serde(rename = "handle")]
+    pub handle: Option<String>,
+    #[serde(rename = "seq")]
+    pub seq: i64,
+}
@@ -0,0 +1,28 @@
+use diesel::prelude::*;
+
+#[derive(
+    Queryable,
+    Selectable,
+    Clone,
+    Debug,
+    PartialEq,
+    Default,
+    Serialize,
+    Deserialize,
+    AsChangeset,
+    Insertable,
+)]
+#[diesel(table_name = crate::schema::label_preference)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct LabelPreference {
+    #[serde(rename = "did")]
+    pub did: String,
+    /// Empty when the preference applies to labels from any labeler.
+    #[serde(rename = "labeler_did")]
+    pub labeler_did: String,
+    #[serde(rename = "label")]
+    pub label: String,
+    /// One of `ignore`, `show`, `warn` or `hide`.
+    #[serde(rename = "visibility")]
+    pub visibility: String,
+}
@@ -0,0 +1,17 @@
+use diesel::prelude::*;
+
+/// A label published by this service's labeler, in the order it was emitted.
+#[derive(Queryable, Selectable, Clone, Debug, PartialEq, Default)]
+#[diesel(table_name = crate::schema::labeler_label)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct LabelerLabel {
+    pub seq: i64,
+    pub src: String,
+    pub uri: String,
+    pub cid: Option<String>,
+    pub val: String,
+    pub neg: bool,
+    pub cts: String,
+    pub exp: Option<String>,
+    pub sig: Vec<u8>,
+}
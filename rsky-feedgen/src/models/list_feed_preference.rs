@@ -0,0 +1,22 @@
+use diesel::prelude::*;
+
+#[derive(
+    Queryable,
+    Selectable,
+    Clone,
+    Debug,
+    PartialEq,
+    Default,
+    Serialize,
+    Deserialize,
+    AsChangeset,
+    Insertable,
+)]
+#[diesel(table_name = crate::schema::list_feed_preference)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ListFeedPreference {
+    #[serde(rename = "did")]
+    pub did: String,
+    #[serde(rename = "list_uri")]
+    pub list_uri: String,
+}
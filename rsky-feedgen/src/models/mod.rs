@@ -14,10 +14,12 @@ pub mod post;
 pub use self::post::Post;
 pub mod follow;
 pub use self::follow::Follow;
-pub mod post_result;
-pub use self::post_result::PostResult;
-pub mod algo_response;
-pub use self::algo_response::AlgoResponse;
+pub mod block;
+pub use self::block::Block;
+pub mod list;
+pub use self::list::List;
+pub mod list_item;
+pub use self::list_item::ListItem;
 pub mod sub_state;
 pub use self::sub_state::SubState;
 pub mod create_request;
@@ -35,6 +37,20 @@ pub use self::user_feed_preference::UserFeedPreference;
 pub mod create_user_config_request;
 pub mod following_preference;
 pub use self::following_preference::FollowingPreference;
+pub mod label_preference;
+pub use self::label_preference::LabelPreference;
+pub mod did_cache_entry;
+pub use self::did_cache_entry::DidCacheEntry;
+pub mod actor_handle;
+pub use self::actor_handle::ActorHandle;
+pub mod identity_update_request;
+pub use self::identity_update_request::IdentityUpdateRequest;
+pub mod feedgen_stats;
+pub use self::feedgen_stats::{FeedgenStats, VisitorStats};
+pub mod labeler_label;
+pub use self::labeler_label::LabelerLabel;
+pub mod list_feed_preference;
+pub use self::list_feed_preference::ListFeedPreference;
 pub mod seen_post;
 pub use self::seen_post::SeenPost;
 pub mod fetched_post;
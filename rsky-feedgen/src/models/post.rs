@@ -48,6 +48,10 @@ pub struct Post {
     pub media: bool,
     #[serde(rename = "alt", skip_serializing_if = "Option::is_none")]
     pub alt: Option<String>,
+    #[serde(rename = "tags", default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(rename = "links", default, skip_serializing_if = "Vec::is_empty")]
+    pub links: Vec<String>,
 }
 
 impl Queryable<post::SqlType, DB> for Post {
@@ -70,6 +74,8 @@ impl Queryable<post::SqlType, DB> for Post {
         Option<String>,
         bool,
         Option<String>,
+        Vec<String>,
+        Vec<String>,
     );
 
     fn build(row: Self::Row) -> deserialize::Result<Self> {
@@ -92,6 +98,8 @@ impl Queryable<post::SqlType, DB> for Post {
             quote_uri: row.15,
             media: row.16,
             alt: row.17,
+            tags: row.18,
+            links: row.19,
         })
     }
 }
@@ -119,6 +127,8 @@ where
         post::quoteUri,
         post::media,
         post::alt,
+        post::tags,
+        post::links,
     );
 
     fn construct_selection() -> Self::SelectExpression {
@@ -141,6 +151,8 @@ where
             post::quoteUri,
             post::media,
             post::alt,
+            post::tags,
+            post::links,
         )
     }
 }
@@ -184,6 +196,7 @@ where
             NamedRow::get::<diesel::dsl::SqlTypeOf<post::quoteUri>, _>(row, "quoteUri")?;
         let media = NamedRow::get::<diesel::dsl::SqlTypeOf<post::media>, _>(row, "media")?;
         let alt = NamedRow::get::<diesel::dsl::SqlTypeOf<post::alt>, _>(row, "alt")?;
+        // Feed queries select the columns they serve, which don't include tags or links
         Ok(Self {
             uri,
             cid,
@@ -203,6 +216,8 @@ where
             quote_uri,
             media,
             alt,
+            tags: Vec::new(),
+            links: Vec::new(),
         })
     }
 }
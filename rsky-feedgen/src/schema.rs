@@ -1,5 +1,35 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    actor_handle (did) {
+        did -> Varchar,
+        handle -> Nullable<Varchar>,
+        seq -> Int8,
+        indexedAt -> Varchar,
+    }
+}
+
+diesel::table! {
+    block (uri) {
+        uri -> Varchar,
+        cid -> Varchar,
+        author -> Varchar,
+        subject -> Varchar,
+        createdAt -> Varchar,
+        indexedAt -> Varchar,
+        prev -> Nullable<Varchar>,
+        sequence -> Nullable<Int8>,
+    }
+}
+
+diesel::table! {
+    did_cache (did) {
+        did -> Varchar,
+        doc -> Varchar,
+        updated_at -> Int8,
+    }
+}
+
 diesel::table! {
     follow (uri) {
         uri -> Varchar,
@@ -13,6 +43,41 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    label (src, uri, val) {
+        src -> Varchar,
+        uri -> Varchar,
+        cid -> Nullable<Varchar>,
+        val -> Varchar,
+        cts -> Varchar,
+        exp -> Nullable<Varchar>,
+        indexedAt -> Varchar,
+    }
+}
+
+diesel::table! {
+    label_preference (did, labeler_did, label) {
+        did -> Varchar,
+        labeler_did -> Varchar,
+        label -> Varchar,
+        visibility -> Varchar,
+    }
+}
+
+diesel::table! {
+    labeler_label (seq) {
+        seq -> Int8,
+        src -> Varchar,
+        uri -> Varchar,
+        cid -> Nullable<Varchar>,
+        val -> Varchar,
+        neg -> Bool,
+        cts -> Varchar,
+        exp -> Nullable<Varchar>,
+        sig -> Bytea,
+    }
+}
+
 diesel::table! {
     like (uri) {
         uri -> Varchar,
@@ -27,6 +92,42 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    list (uri) {
+        uri -> Varchar,
+        cid -> Varchar,
+        author -> Varchar,
+        name -> Varchar,
+        purpose -> Varchar,
+        description -> Nullable<Varchar>,
+        createdAt -> Varchar,
+        indexedAt -> Varchar,
+        prev -> Nullable<Varchar>,
+        sequence -> Nullable<Int8>,
+    }
+}
+
+diesel::table! {
+    list_feed_preference (did) {
+        did -> Varchar,
+        list_uri -> Varchar,
+    }
+}
+
+diesel::table! {
+    listitem (uri) {
+        uri -> Varchar,
+        cid -> Varchar,
+        author -> Varchar,
+        subject -> Varchar,
+        list -> Varchar,
+        createdAt -> Varchar,
+        indexedAt -> Varchar,
+        prev -> Nullable<Varchar>,
+        sequence -> Nullable<Int8>,
+    }
+}
+
 diesel::table! {
     post (uri) {
         uri -> Varchar,
@@ -46,7 +147,21 @@ diesel::table! {
         quoteCid -> Nullable<Varchar>,
         quoteUri -> Nullable<Varchar>,
         media -> Bool,
-        alt -> Nullable<Varchar>
+        alt -> Nullable<Varchar>,
+        tags -> Array<Text>,
+        links -> Array<Text>,
+    }
+}
+
+diesel::table! {
+    postgate (uri) {
+        uri -> Varchar,
+        cid -> Varchar,
+        author -> Varchar,
+        post -> Varchar,
+        detachedEmbeddingUris -> Array<Text>,
+        createdAt -> Varchar,
+        indexedAt -> Varchar,
     }
 }
 
@@ -71,6 +186,18 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    threadgate (uri) {
+        uri -> Varchar,
+        cid -> Varchar,
+        author -> Varchar,
+        post -> Varchar,
+        hiddenReplies -> Array<Text>,
+        createdAt -> Varchar,
+        indexedAt -> Varchar,
+    }
+}
+
 diesel::table! {
     user_feed_preference (did) {
         did -> Varchar,
@@ -132,11 +259,22 @@ diesel::table! {
 }
 
 diesel::allow_tables_to_appear_in_same_query!(
+    actor_handle,
+    block,
+    did_cache,
     follow,
+    label,
+    label_preference,
+    labeler_label,
     like,
+    list,
+    list_feed_preference,
+    listitem,
     post,
+    postgate,
     repost,
     sub_state,
+    threadgate,
     user_feed_preference,
     video,
     visitor,
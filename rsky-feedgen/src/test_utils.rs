@@ -0,0 +1,71 @@
+//! Helpers for tests that run against Postgres. Those tests are ignored by default; run them with
+//! `cargo test -- --ignored` and `DATABASE_URL` pointing at a database with the migrations applied.
+use crate::models::{CreateRequest, Follow};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::r2d2::{ConnectionManager, CustomizeConnection, Pool};
+use dotenvy::dotenv;
+use std::env;
+
+/// Connects to `DATABASE_URL` inside a transaction that is never committed, so nothing a test
+/// writes outlives it.
+pub fn test_connection() -> PgConnection {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let mut conn = PgConnection::establish(&database_url).expect("Error connecting to database");
+    conn.begin_test_transaction()
+        .expect("Error starting test transaction");
+    conn
+}
+
+#[derive(Debug)]
+struct TestTransaction;
+
+impl CustomizeConnection<PgConnection, diesel::r2d2::Error> for TestTransaction {
+    fn on_acquire(&self, conn: &mut PgConnection) -> Result<(), diesel::r2d2::Error> {
+        conn.begin_test_transaction()
+            .map_err(diesel::r2d2::Error::QueryError)
+    }
+}
+
+/// A pool of one connection, inside a transaction like [`test_connection`]'s. Everything using
+/// the pool shares the transaction, so sees each other's writes.
+pub fn test_pool() -> Pool<ConnectionManager<PgConnection>> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    Pool::builder()
+        .max_size(1)
+        .connection_customizer(Box::new(TestTransaction))
+        .build(ConnectionManager::new(database_url))
+        .expect("Error connecting to database")
+}
+
+/// A create request for `record`, given as JSON with its `$type`.
+pub fn create_request(uri: &str, author: &str, record: serde_json::Value) -> CreateRequest {
+    serde_json::from_value(serde_json::json!({
+        "uri": uri,
+        "cid": "bafyreifsdaip3s5nm3hcz4fbgkxodnils75oi3rmqhipwtom34rxw4vwdi",
+        "sequence": null,
+        "prev": null,
+        "author": author,
+        "record": record,
+    }))
+    .expect("Invalid create request")
+}
+
+/// Makes `did` a feed user by indexing one of their follows.
+pub fn insert_feed_user(did: &str, conn: &mut PgConnection) {
+    crate::db::insert_follows(
+        vec![Follow {
+            uri: format!("at://{did}/app.bsky.graph.follow/3lauicnw5op2f"),
+            cid: "bafyreifsdaip3s5nm3hcz4fbgkxodnils75oi3rmqhipwtom34rxw4vwdi".to_string(),
+            author: did.to_string(),
+            subject: "did:plc:z72i7hdynmk6r22z27h6tvur".to_string(),
+            created_at: "2024-11-13T23:19:36.449Z".to_string(),
+            indexed_at: "2024-11-13T23:19:36.449Z".to_string(),
+            prev: None,
+            sequence: None,
+        }],
+        conn,
+    );
+}
@@ -1,4 +1,6 @@
-use anyhow::Result;
+use crate::errors::Error;
+use crate::types::{AtprotoData, DidDocument};
+use anyhow::{bail, Result};
 use rsky_crypto::constants::{P256_JWT_ALG, SECP256K1_JWT_ALG};
 use rsky_crypto::did::{format_did_key, parse_multikey};
 use rsky_crypto::multibase::multibase_to_bytes;
@@ -26,3 +28,150 @@ pub fn get_did_key_from_multibase(key: VerificationMaterial) -> Result<Option<St
     };
     Ok(did_key)
 }
+
+pub fn get_key(doc: &DidDocument) -> Result<Option<String>> {
+    match get_verification_material(doc, "atproto") {
+        None => Ok(None),
+        Some(key) => get_did_key_from_multibase(key),
+    }
+}
+
+/// The verification method with the given fragment id, e.g. `atproto` for the signing key. Ids
+/// may be relative (`#atproto`) or absolute (`did:plc:...#atproto`).
+pub fn get_verification_material(doc: &DidDocument, key_id: &str) -> Option<VerificationMaterial> {
+    doc.verification_method
+        .as_ref()?
+        .iter()
+        .find(|method| is_fragment_of(doc, &method.id, key_id))
+        .and_then(|method| {
+            Some(VerificationMaterial {
+                r#type: method.r#type.clone(),
+                public_key_multibase: method.public_key_multibase.clone()?,
+            })
+        })
+}
+
+/// The first `at://` alias in `alsoKnownAs`, which atproto treats as the claimed handle.
+pub fn get_handle(doc: &DidDocument) -> Option<String> {
+    doc.also_known_as
+        .as_ref()?
+        .iter()
+        .find_map(|aka| aka.strip_prefix("at://"))
+        .map(|handle| handle.to_string())
+}
+
+pub fn get_pds_endpoint(doc: &DidDocument) -> Option<String> {
+    get_service_endpoint(doc, "atproto_pds", "AtprotoPersonalDataServer")
+}
+
+pub fn get_service_endpoint(doc: &DidDocument, id: &str, r#type: &str) -> Option<String> {
+    let service = doc
+        .service
+        .as_ref()?
+        .iter()
+        .find(|service| is_fragment_of(doc, &service.id, id))?;
+    if service.r#type != r#type {
+        return None;
+    }
+    // Only plain http(s) origins are usable as endpoints
+    match url::Url::parse(&service.service_endpoint) {
+        Ok(url) if url.scheme() == "http" || url.scheme() == "https" => {
+            Some(service.service_endpoint.clone())
+        }
+        _ => None,
+    }
+}
+
+fn is_fragment_of(doc: &DidDocument, id: &str, fragment: &str) -> bool {
+    match id.split_once('#') {
+        Some(("", id_fragment)) => id_fragment == fragment,
+        Some((did, id_fragment)) => did == doc.id && id_fragment == fragment,
+        None => false,
+    }
+}
+
+/// Pulls the DID, `#atproto` signing key, handle and `#atproto_pds` endpoint out of a document,
+/// failing if any is missing.
+pub fn ensure_atproto_document(doc: &DidDocument) -> Result<AtprotoData> {
+    let Some(signing_key) = get_key(doc)? else {
+        bail!(Error::IncompleteDidDocumentError(
+            doc.id.clone(),
+            "signing key"
+        ))
+    };
+    let Some(handle) = get_handle(doc) else {
+        bail!(Error::IncompleteDidDocumentError(doc.id.clone(), "handle"))
+    };
+    let Some(pds) = get_pds_endpoint(doc) else {
+        bail!(Error::IncompleteDidDocumentError(
+            doc.id.clone(),
+            "pds endpoint"
+        ))
+    };
+    Ok(AtprotoData {
+        did: doc.id.clone(),
+        signing_key,
+        handle,
+        pds,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc() -> DidDocument {
+        serde_json::from_value(serde_json::json!({
+            "@context": [
+                "https://www.w3.org/ns/did/v1",
+                "https://w3id.org/security/multikey/v1",
+                "https://w3id.org/security/suites/secp256k1-2019/v1"
+            ],
+            "id": "did:plc:ewvi7nxzyoun6zhxrhs64oiz",
+            "alsoKnownAs": ["https://example.com", "at://atproto.com"],
+            "verificationMethod": [{
+                "id": "did:plc:ewvi7nxzyoun6zhxrhs64oiz#atproto",
+                "type": "Multikey",
+                "controller": "did:plc:ewvi7nxzyoun6zhxrhs64oiz",
+                "publicKeyMultibase": "zQ3shunBKsXixLxKtC5qeSG9E4J5RkGN57im31pcTzbNQnm5w"
+            }],
+            "service": [{
+                "id": "#atproto_pds",
+                "type": "AtprotoPersonalDataServer",
+                "serviceEndpoint": "https://enoki.us-east.host.bsky.network"
+            }]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_ensure_atproto_document() {
+        assert_eq!(
+            ensure_atproto_document(&doc()).unwrap(),
+            AtprotoData {
+                did: "did:plc:ewvi7nxzyoun6zhxrhs64oiz".to_string(),
+                signing_key: "did:key:zQ3shunBKsXixLxKtC5qeSG9E4J5RkGN57im31pcTzbNQnm5w"
+                    .to_string(),
+                handle: "atproto.com".to_string(),
+                pds: "https://enoki.us-east.host.bsky.network".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_missing_or_foreign_entries() {
+        let mut foreign = doc();
+        // A key or service scoped to another DID doesn't count
+        foreign.verification_method.as_mut().unwrap()[0].id =
+            "did:plc:someoneelse#atproto".to_string();
+        foreign.service.as_mut().unwrap()[0].r#type = "BskyFeedGenerator".to_string();
+        assert_eq!(get_key(&foreign).unwrap(), None);
+        assert_eq!(get_pds_endpoint(&foreign), None);
+        assert!(ensure_atproto_document(&foreign).is_err());
+
+        let mut no_handle = doc();
+        no_handle.also_known_as = None;
+        assert_eq!(get_handle(&no_handle), None);
+        assert!(ensure_atproto_document(&no_handle).is_err());
+    }
+}
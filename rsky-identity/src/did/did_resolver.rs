@@ -1,16 +1,30 @@
+use crate::did::atproto_data::{ensure_atproto_document, get_key};
 use crate::did::plc_resolver::DidPlcResolver;
 use crate::did::web_resolver::DidWebResolver;
 use crate::errors::Error;
-use crate::types::{CacheResult, DidCache, DidDocument, DidResolverOpts};
+use crate::transport::{HttpTransport, ReqwestTransport};
+use crate::types::{AtprotoData, CacheResult, DidCache, DidDocument, DidResolverOpts};
 use anyhow::{bail, Result};
+use async_trait::async_trait;
 use serde_json::Value;
 use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::sync::Arc;
 use std::time::Duration;
 
+/// Resolver for a DID method beyond the built-in `plc` and `web`, registered with
+/// [`DidResolver::register_method`].
+#[async_trait]
+pub trait DidMethodResolver: Send + Sync + Debug {
+    /// The raw DID document, or `None` if the DID positively doesn't exist.
+    async fn resolve_no_check(&self, did: String) -> Result<Option<Value>>;
+}
+
 #[derive(Clone, Debug)]
 pub enum ResolverKind {
     Plc(DidPlcResolver),
     Web(DidWebResolver),
+    Custom(Arc<dyn DidMethodResolver>),
 }
 
 impl ResolverKind {
@@ -18,6 +32,7 @@ impl ResolverKind {
         match self {
             Self::Plc(plc) => plc.resolve_no_check(did).await,
             Self::Web(web) => web.resolve_no_check(did).await,
+            Self::Custom(custom) => custom.resolve_no_check(did).await,
         }
     }
 }
@@ -31,28 +46,45 @@ pub struct DidResolver {
 impl DidResolver {
     pub fn new(opts: DidResolverOpts) -> Self {
         let DidResolverOpts {
-            timeout, plc_url, ..
+            timeout,
+            plc_url,
+            did_cache,
+            transport,
         } = opts;
         let timeout = timeout.unwrap_or_else(|| Duration::new(3, 0));
         let plc_url = plc_url.unwrap_or_else(|| "https://plc.directory".to_string());
+        let transport: Arc<dyn HttpTransport> =
+            transport.unwrap_or_else(|| Arc::new(ReqwestTransport::default()));
 
         let mut methods = BTreeMap::new();
         methods.insert(
             "plc".to_string(),
-            ResolverKind::Plc(DidPlcResolver::new(plc_url, timeout, None)),
+            ResolverKind::Plc(DidPlcResolver::new(
+                plc_url,
+                timeout,
+                None,
+                transport.clone(),
+            )),
         );
         methods.insert(
             "web".to_string(),
-            ResolverKind::Web(DidWebResolver::new(timeout, None)),
+            ResolverKind::Web(DidWebResolver::new(timeout, None, transport)),
         );
 
         // do not pass cache to sub-methods, or we will be double caching
         Self {
-            cache: Some(opts.did_cache),
+            cache: Some(did_cache),
             methods,
         }
     }
 
+    /// Adds support for another DID method, e.g. `register_method("key", ...)` for `did:key`,
+    /// or replaces a built-in one.
+    pub fn register_method(&mut self, method: &str, resolver: Arc<dyn DidMethodResolver>) {
+        self.methods
+            .insert(method.to_string(), ResolverKind::Custom(resolver));
+    }
+
     pub async fn resolve_no_check(&self, did: String) -> Result<Option<Value>> {
         let split = did.split(":").collect::<Vec<&str>>();
         if split[0] != "did" {
@@ -90,7 +122,7 @@ impl DidResolver {
         let resolver = self.clone();
         match self.cache {
             None => Ok(()),
-            Some(ref mut cache) => {
+            Some(ref cache) => {
                 cache
                     .refresh_cache(did.clone(), || resolver.resolve_no_cache(&did))
                     .await
@@ -108,7 +140,7 @@ impl DidResolver {
         match self.cache {
             None => (),
             Some(ref cache) if !force_refresh => {
-                from_cache = cache.check_cache(did.clone())?;
+                from_cache = cache.check_cache(did.clone()).await?;
                 match from_cache {
                     None => (),
                     Some(from_cache) if !from_cache.expired => {
@@ -125,13 +157,13 @@ impl DidResolver {
 
         match self.resolve_no_cache(&did).await? {
             None => {
-                if let Some(ref mut cache) = self.cache {
-                    cache.clear_entry(did)?;
+                if let Some(ref cache) = self.cache {
+                    cache.clear_entry(did).await?;
                 }
                 Ok(None)
             }
             Some(got) => {
-                if let Some(ref mut cache) = self.cache {
+                if let Some(ref cache) = self.cache {
                     cache.cache_did(did, got.clone()).await?;
                 }
                 Ok(Some(got))
@@ -150,4 +182,123 @@ impl DidResolver {
             Some(result) => Ok(result),
         }
     }
+
+    pub async fn resolve_atproto_data(
+        &mut self,
+        did: &String,
+        force_refresh: Option<bool>,
+    ) -> Result<AtprotoData> {
+        let doc = self.ensure_resolve(did, force_refresh).await?;
+        ensure_atproto_document(&doc)
+    }
+
+    /// The `did:key` an account signs with. A `did:key` is its own key.
+    pub async fn resolve_atproto_key(
+        &mut self,
+        did: &String,
+        force_refresh: Option<bool>,
+    ) -> Result<String> {
+        if did.starts_with("did:key:") {
+            return Ok(did.clone());
+        }
+        let doc = self.ensure_resolve(did, force_refresh).await?;
+        match get_key(&doc)? {
+            None => bail!(Error::IncompleteDidDocumentError(
+                did.clone(),
+                "signing key"
+            )),
+            Some(key) => Ok(key),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::MockTransport;
+    use serde_json::json;
+
+    const DID: &str = "did:web:alice.test";
+
+    fn doc(did: &str) -> Value {
+        json!({
+            "id": did,
+            "alsoKnownAs": ["at://alice.test"],
+            "verificationMethod": [{
+                "id": "#atproto",
+                "type": "Multikey",
+                "controller": did,
+                "publicKeyMultibase": "zQ3shunBKsXixLxKtC5qeSG9E4J5RkGN57im31pcTzbNQnm5w"
+            }],
+            "service": [{
+                "id": "#atproto_pds",
+                "type": "AtprotoPersonalDataServer",
+                "serviceEndpoint": "https://pds.example.com"
+            }]
+        })
+    }
+
+    fn resolver(transport: Arc<MockTransport>) -> DidResolver {
+        DidResolver::new(DidResolverOpts {
+            timeout: None,
+            plc_url: None,
+            did_cache: DidCache::new(None, None),
+            transport: Some(transport),
+        })
+    }
+
+    #[derive(Debug)]
+    struct StaticMethod(Value);
+
+    #[async_trait]
+    impl DidMethodResolver for StaticMethod {
+        async fn resolve_no_check(&self, _did: String) -> Result<Option<Value>> {
+            Ok(Some(self.0.clone()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resolves_through_transport_and_cache() {
+        let transport = Arc::new(MockTransport::new());
+        transport.insert_json("https://alice.test/.well-known/did.json", &doc(DID));
+        let mut resolver = resolver(transport.clone());
+
+        let data = resolver
+            .resolve_atproto_data(&DID.to_string(), None)
+            .await
+            .unwrap();
+        assert_eq!(data.handle, "alice.test");
+        assert_eq!(data.pds, "https://pds.example.com");
+        assert_eq!(
+            resolver
+                .resolve_atproto_key(&DID.to_string(), None)
+                .await
+                .unwrap(),
+            "did:key:zQ3shunBKsXixLxKtC5qeSG9E4J5RkGN57im31pcTzbNQnm5w"
+        );
+        // The second lookup is served from the cache
+        assert_eq!(transport.requests().len(), 1);
+
+        assert_eq!(
+            resolver
+                .resolve("did:web:bob.test".to_string(), None)
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_registered_methods() {
+        let did = "did:example:alice";
+        let mut resolver = resolver(Arc::new(MockTransport::new()));
+        assert!(resolver.resolve(did.to_string(), None).await.is_err());
+
+        resolver.register_method("example", Arc::new(StaticMethod(doc(did))));
+        let resolved = resolver
+            .ensure_resolve(&did.to_string(), None)
+            .await
+            .unwrap();
+        assert_eq!(resolved.id, did);
+    }
 }
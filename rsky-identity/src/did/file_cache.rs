@@ -0,0 +1,151 @@
+use crate::did::memory_cache::MemoryDidCache;
+use crate::types::{CacheVal, DidCacheStore};
+use anyhow::Result;
+use async_trait::async_trait;
+use std::fs;
+use std::io::ErrorKind;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// A [`MemoryDidCache`] persisted to a JSON file, so a restarted process starts warm. The whole
+/// cache is rewritten on every change, which suits caches of a few thousand DIDs.
+#[derive(Debug)]
+pub struct FileDidCache {
+    path: PathBuf,
+    memory: MemoryDidCache,
+    /// Held from snapshotting the entries until the rename, so concurrent saves neither share
+    /// a half-written temp file nor land out of order.
+    save_lock: Mutex<()>,
+}
+
+impl FileDidCache {
+    /// Loads `path` if it exists. When it holds more than `max_entries`, the most recently used
+    /// entries are kept.
+    pub fn open(path: impl Into<PathBuf>, max_entries: Option<NonZeroUsize>) -> Result<Self> {
+        let path = path.into();
+        let memory = MemoryDidCache::new(max_entries);
+        match fs::read(&path) {
+            Ok(bytes) => {
+                // Saved least recently used first, so re-inserting in order restores recency
+                let entries: Vec<(String, CacheVal)> = serde_json::from_slice(&bytes)?;
+                memory.extend(entries);
+            }
+            Err(error) if error.kind() == ErrorKind::NotFound => (),
+            Err(error) => return Err(error.into()),
+        }
+        Ok(Self {
+            path,
+            memory,
+            save_lock: Mutex::new(()),
+        })
+    }
+
+    fn save(&self) -> Result<()> {
+        let _guard = self
+            .save_lock
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        // Written beside the target and renamed over it, so a crash can't leave a torn file
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec(&self.memory.entries())?)?;
+        fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl DidCacheStore for FileDidCache {
+    async fn get(&self, did: &str) -> Result<Option<CacheVal>> {
+        self.memory.get(did).await
+    }
+
+    async fn put(&self, did: String, val: CacheVal) -> Result<()> {
+        self.memory.put(did, val).await?;
+        self.save()
+    }
+
+    async fn remove(&self, did: &str) -> Result<()> {
+        self.memory.remove(did).await?;
+        self.save()
+    }
+
+    async fn clear(&self) -> Result<()> {
+        self.memory.clear().await?;
+        self.save()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::DidDocument;
+    use std::process;
+    use std::thread;
+
+    fn val(did: &str) -> CacheVal {
+        CacheVal {
+            doc: DidDocument {
+                context: None,
+                id: did.to_string(),
+                also_known_as: None,
+                verification_method: None,
+                service: None,
+            },
+            updated_at: 1,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_survives_reopen() {
+        let path = std::env::temp_dir().join(format!("rsky-did-cache-{}.json", process::id()));
+        let _ = fs::remove_file(&path);
+
+        let cache = FileDidCache::open(&path, None).unwrap();
+        for did in ["did:plc:a", "did:plc:b", "did:plc:c"] {
+            cache.put(did.to_string(), val(did)).await.unwrap();
+        }
+        cache.get("did:plc:a").await.unwrap();
+        cache.remove("did:plc:c").await.unwrap();
+
+        // Reopened with room for one, only the most recently used survives
+        let reopened = FileDidCache::open(&path, NonZeroUsize::new(1)).unwrap();
+        assert_eq!(
+            reopened.get("did:plc:a").await.unwrap().unwrap().doc.id,
+            "did:plc:a"
+        );
+        assert!(reopened.get("did:plc:b").await.unwrap().is_none());
+        assert!(reopened.get("did:plc:c").await.unwrap().is_none());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_concurrent_puts() {
+        let path =
+            std::env::temp_dir().join(format!("rsky-did-cache-concurrent-{}.json", process::id()));
+        let _ = fs::remove_file(&path);
+
+        let cache = FileDidCache::open(&path, None).unwrap();
+        thread::scope(|scope| {
+            for thread in 0..8 {
+                let cache = &cache;
+                scope.spawn(move || {
+                    let runtime = tokio::runtime::Builder::new_current_thread()
+                        .build()
+                        .unwrap();
+                    for n in 0..20 {
+                        let did = format!("did:plc:{thread}-{n}");
+                        runtime.block_on(cache.put(did.clone(), val(&did))).unwrap();
+                    }
+                });
+            }
+        });
+
+        // The last save ran after every put, so it holds them all
+        let reopened = FileDidCache::open(&path, None).unwrap();
+        assert_eq!(reopened.memory.len(), 8 * 20);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,202 @@
+use crate::types::{CacheVal, DidCacheStore};
+use anyhow::Result;
+use async_trait::async_trait;
+use lru::LruCache;
+use std::num::NonZeroUsize;
+use std::sync::{Mutex, MutexGuard};
+
+/// In-memory store, evicting the least recently used DID once `max_entries` is reached.
+#[derive(Debug)]
+pub struct MemoryDidCache {
+    cache: Mutex<LruCache<String, CacheVal>>,
+}
+
+impl MemoryDidCache {
+    /// Unbounded when `max_entries` is `None`.
+    pub fn new(max_entries: Option<NonZeroUsize>) -> Self {
+        let cache = match max_entries {
+            None => LruCache::unbounded(),
+            Some(max_entries) => LruCache::new(max_entries),
+        };
+        Self {
+            cache: Mutex::new(cache),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, LruCache<String, CacheVal>> {
+        // The cache can't be left half-updated, so a panic elsewhere doesn't invalidate it
+        self.cache
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Entries from least to most recently used, e.g. for persisting them.
+    pub fn entries(&self) -> Vec<(String, CacheVal)> {
+        self.lock()
+            .iter()
+            .rev()
+            .map(|(did, val)| (did.clone(), val.clone()))
+            .collect()
+    }
+
+    /// Inserts entries in order, so the last becomes the most recently used. The counterpart of
+    /// [`MemoryDidCache::entries`].
+    pub fn extend(&self, entries: impl IntoIterator<Item = (String, CacheVal)>) {
+        let mut cache = self.lock();
+        for (did, val) in entries {
+            cache.put(did, val);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+}
+
+#[async_trait]
+impl DidCacheStore for MemoryDidCache {
+    async fn get(&self, did: &str) -> Result<Option<CacheVal>> {
+        Ok(self.lock().get(did).cloned())
+    }
+
+    async fn put(&self, did: String, val: CacheVal) -> Result<()> {
+        self.lock().put(did, val);
+        Ok(())
+    }
+
+    async fn remove(&self, did: &str) -> Result<()> {
+        self.lock().pop(did);
+        Ok(())
+    }
+
+    async fn clear(&self) -> Result<()> {
+        self.lock().clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{DidCache, DidCacheStats, DidDocument};
+    use std::sync::Arc;
+    use std::time::{Duration, SystemTime};
+
+    fn doc(did: &str) -> DidDocument {
+        DidDocument {
+            context: None,
+            id: did.to_string(),
+            also_known_as: None,
+            verification_method: None,
+            service: None,
+        }
+    }
+
+    fn val(did: &str) -> CacheVal {
+        CacheVal {
+            doc: doc(did),
+            updated_at: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_evicts_least_recently_used() {
+        let store = MemoryDidCache::new(NonZeroUsize::new(2));
+        store
+            .put("did:plc:a".to_string(), val("did:plc:a"))
+            .await
+            .unwrap();
+        store
+            .put("did:plc:b".to_string(), val("did:plc:b"))
+            .await
+            .unwrap();
+        // Reading a makes b the least recently used
+        store.get("did:plc:a").await.unwrap();
+        store
+            .put("did:plc:c".to_string(), val("did:plc:c"))
+            .await
+            .unwrap();
+
+        assert!(store.get("did:plc:b").await.unwrap().is_none());
+        assert_eq!(
+            store
+                .entries()
+                .into_iter()
+                .map(|(did, _)| did)
+                .collect::<Vec<_>>(),
+            vec!["did:plc:a", "did:plc:c"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ttls_and_stats() {
+        let store = Arc::new(MemoryDidCache::new(None));
+        let cache = DidCache::with_store(
+            store.clone(),
+            Some(Duration::from_secs(60 * 60)),
+            Some(Duration::from_secs(24 * 60 * 60)),
+        );
+        cache
+            .cache_did("did:plc:fresh".to_string(), doc("did:plc:fresh"))
+            .await
+            .unwrap();
+        let two_hours_ago = SystemTime::now() - Duration::from_secs(2 * 60 * 60);
+        let two_days_ago = SystemTime::now() - Duration::from_secs(2 * 24 * 60 * 60);
+        for (did, updated_at) in [
+            ("did:plc:stale", two_hours_ago),
+            ("did:plc:old", two_days_ago),
+        ] {
+            let updated_at = updated_at
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_micros();
+            store
+                .put(
+                    did.to_string(),
+                    CacheVal {
+                        doc: doc(did),
+                        updated_at,
+                    },
+                )
+                .await
+                .unwrap();
+        }
+
+        let fresh = cache
+            .check_cache("did:plc:fresh".to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!fresh.stale && !fresh.expired);
+        let stale = cache
+            .check_cache("did:plc:stale".to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(stale.stale && !stale.expired);
+        let old = cache
+            .check_cache("did:plc:old".to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(old.expired);
+        assert!(cache
+            .check_cache("did:plc:none".to_string())
+            .await
+            .unwrap()
+            .is_none());
+
+        assert_eq!(
+            cache.stats(),
+            DidCacheStats {
+                hits: 1,
+                misses: 2,
+                stale: 1,
+            }
+        );
+    }
+}
@@ -1,4 +1,7 @@
 pub mod atproto_data;
 pub mod did_resolver;
+pub mod file_cache;
+pub mod memory_cache;
+pub mod plc_log;
 pub mod plc_resolver;
 pub mod web_resolver;
@@ -0,0 +1,461 @@
+use crate::errors::Error;
+use crate::types::{DidDocument, Service, VerificationMethod};
+use anyhow::Result;
+use chrono::{DateTime, Duration};
+use multibase::Base;
+use rsky_crypto::constants::{P256_JWT_ALG, SECP256K1_JWT_ALG};
+use rsky_crypto::dag_cbor::{cid_for_cbor, encode_json};
+use rsky_crypto::did::parse_did_key;
+use rsky_crypto::multibase::bytes_to_multibase;
+use rsky_crypto::types::VerifyOptions;
+use rsky_crypto::utils::base64url_decode;
+use rsky_crypto::verify::verify_signature;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+
+/// How long a higher-priority rotation key has to nullify operations signed by a lower one.
+pub const RECOVERY_WINDOW_HOURS: i64 = 72;
+
+/// One entry of `GET /{did}/log/audit`. The operation is kept as raw JSON, since its CID and
+/// signature cover exactly the fields the directory served.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AuditLogEntry {
+    pub did: String,
+    pub operation: Value,
+    pub cid: String,
+    pub nullified: bool,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlcService {
+    #[serde(rename = "type")]
+    pub r#type: String,
+    pub endpoint: String,
+}
+
+/// The state a DID's operations have built up, with legacy `create` operations normalized.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlcData {
+    #[serde(rename = "rotationKeys")]
+    pub rotation_keys: Vec<String>,
+    #[serde(rename = "verificationMethods")]
+    pub verification_methods: BTreeMap<String, String>,
+    #[serde(rename = "alsoKnownAs")]
+    pub also_known_as: Vec<String>,
+    pub services: BTreeMap<String, PlcService>,
+}
+
+#[derive(Deserialize)]
+struct LegacyCreate {
+    #[serde(rename = "signingKey")]
+    signing_key: String,
+    #[serde(rename = "recoveryKey")]
+    recovery_key: String,
+    handle: String,
+    service: String,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+enum PlcOperation {
+    #[serde(rename = "plc_operation")]
+    Operation(PlcData),
+    #[serde(rename = "plc_tombstone")]
+    Tombstone,
+    #[serde(rename = "create")]
+    LegacyCreate(LegacyCreate),
+}
+
+impl PlcOperation {
+    /// `None` once the DID has been tombstoned.
+    fn into_data(self) -> Option<PlcData> {
+        match self {
+            PlcOperation::Operation(data) => Some(data),
+            PlcOperation::Tombstone => None,
+            PlcOperation::LegacyCreate(create) => Some(PlcData {
+                rotation_keys: vec![create.recovery_key, create.signing_key.clone()],
+                verification_methods: BTreeMap::from([("atproto".to_string(), create.signing_key)]),
+                also_known_as: vec![with_prefix("at://", create.handle)],
+                services: BTreeMap::from([(
+                    "atproto_pds".to_string(),
+                    PlcService {
+                        r#type: "AtprotoPersonalDataServer".to_string(),
+                        endpoint: with_prefix("https://", create.service),
+                    },
+                )]),
+            }),
+        }
+    }
+}
+
+fn with_prefix(prefix: &str, value: String) -> String {
+    match value.starts_with(prefix) || (prefix == "https://" && value.starts_with("http://")) {
+        true => value,
+        false => format!("{prefix}{value}"),
+    }
+}
+
+/// An operation accepted into the chain, with the index of the rotation key that signed it.
+struct ChainLink {
+    cid: String,
+    data: Option<PlcData>,
+    signer: usize,
+    created_at: DateTime<chrono::FixedOffset>,
+}
+
+fn invalid(did: &str, reason: impl Into<String>) -> anyhow::Error {
+    Error::InvalidPlcLogError(did.to_string(), reason.into()).into()
+}
+
+/// `did:plc:` followed by the first 24 base32 characters of the signed genesis op's sha256.
+pub fn did_for_genesis_op(operation: &Value) -> Result<String> {
+    let cid = cid_for_cbor(&encode_json(operation)?);
+    let encoded = bytes_to_multibase(cid.hash().digest(), Base::Base32Lower);
+    // Drop the multibase prefix
+    Ok(format!("did:plc:{}", &encoded[1..25]))
+}
+
+/// Index of the first of `keys` whose signature over the unsigned operation checks out.
+fn find_signer(did: &str, keys: &[String], operation: &Value) -> Result<usize> {
+    let Some(sig) = operation.get("sig").and_then(Value::as_str) else {
+        return Err(invalid(did, "operation is unsigned"));
+    };
+    let sig = base64url_decode(sig)?;
+    let mut unsigned = operation.clone();
+    if let Some(fields) = unsigned.as_object_mut() {
+        fields.remove("sig");
+    }
+    let data = encode_json(&unsigned)?;
+    // Early operations weren't always low-S, and the directory accepted them regardless
+    let opts = || VerifyOptions {
+        allow_malleable_sig: Some(true),
+    };
+    keys.iter()
+        .position(|key| verify_signature(key, &data, &sig, Some(opts())).unwrap_or(false))
+        .ok_or_else(|| invalid(did, "signature does not match any rotation key"))
+}
+
+/// Replays a DID's audit log the way the PLC directory applies operations, checking every CID,
+/// signature and `prev` link, and that exactly the forked-off operations are marked nullified.
+/// Returns the resulting state, or `None` if the DID is tombstoned.
+pub fn verify_audit_log(did: &str, log: &[AuditLogEntry]) -> Result<Option<PlcData>> {
+    let mut chain: Vec<ChainLink> = vec![];
+    let mut nullified: HashSet<String> = HashSet::new();
+
+    for entry in log {
+        if entry.did != did {
+            return Err(invalid(did, format!("entry for {}", entry.did)));
+        }
+        let cid = cid_for_cbor(&encode_json(&entry.operation)?).to_string();
+        if cid != entry.cid {
+            return Err(invalid(did, format!("{} hashes to {cid}", entry.cid)));
+        }
+        let created_at = DateTime::parse_from_rfc3339(&entry.created_at)?;
+        let prev = entry.operation.get("prev").and_then(Value::as_str);
+        let data = serde_json::from_value::<PlcOperation>(entry.operation.clone())?.into_data();
+
+        let Some(prev) = prev else {
+            if !chain.is_empty() {
+                return Err(invalid(did, format!("{cid} restarts the chain")));
+            }
+            if did_for_genesis_op(&entry.operation)? != did {
+                return Err(invalid(did, "genesis operation is for another DID"));
+            }
+            let Some(ref genesis) = data else {
+                return Err(invalid(did, "genesis operation is a tombstone"));
+            };
+            let signer = find_signer(did, &genesis.rotation_keys, &entry.operation)?;
+            chain.push(ChainLink {
+                cid,
+                data,
+                signer,
+                created_at,
+            });
+            continue;
+        };
+
+        let Some(parent) = chain.iter().position(|link| link.cid == prev) else {
+            return Err(invalid(
+                did,
+                format!("{cid} follows unknown or nullified {prev}"),
+            ));
+        };
+        let Some(ref keys) = chain[parent].data else {
+            return Err(invalid(did, format!("{cid} follows a tombstone")));
+        };
+        let signer = find_signer(did, &keys.rotation_keys, &entry.operation)?;
+
+        if let Some(disputed) = chain.get(parent + 1) {
+            // Forking off the chain nullifies what follows, which only a key that outranks the
+            // one that signed the first of those may do, and only for a limited time
+            if signer >= disputed.signer {
+                return Err(invalid(
+                    did,
+                    format!("{cid} can't nullify {}", disputed.cid),
+                ));
+            }
+            if created_at > disputed.created_at + Duration::hours(RECOVERY_WINDOW_HOURS) {
+                return Err(invalid(
+                    did,
+                    format!("{cid} is outside the recovery window"),
+                ));
+            }
+            nullified.extend(chain.drain(parent + 1..).map(|link| link.cid));
+        }
+        chain.push(ChainLink {
+            cid,
+            data,
+            signer,
+            created_at,
+        });
+    }
+
+    for entry in log {
+        if entry.nullified != nullified.contains(&entry.cid) {
+            return Err(invalid(
+                did,
+                format!("{} has the wrong nullified flag", entry.cid),
+            ));
+        }
+    }
+    match chain.pop() {
+        None => Err(invalid(did, "log is empty")),
+        Some(last) => Ok(last.data),
+    }
+}
+
+/// Formats PLC state as the DID document the directory serves for it.
+pub fn format_did_doc(did: &str, data: &PlcData) -> Result<DidDocument> {
+    let mut context = vec![
+        "https://www.w3.org/ns/did/v1".to_string(),
+        "https://w3id.org/security/multikey/v1".to_string(),
+    ];
+    let mut verification_method = vec![];
+    for (id, key) in &data.verification_methods {
+        let suite = match parse_did_key(key)?.jwt_alg.as_str() {
+            SECP256K1_JWT_ALG => "https://w3id.org/security/suites/secp256k1-2019/v1",
+            P256_JWT_ALG => "https://w3id.org/security/suites/ecdsa-2019/v1",
+            _ => continue,
+        };
+        if !context.iter().any(|existing| existing == suite) {
+            context.push(suite.to_string());
+        }
+        verification_method.push(VerificationMethod {
+            id: format!("{did}#{id}"),
+            r#type: "Multikey".to_string(),
+            controller: did.to_string(),
+            public_key_multibase: key.strip_prefix("did:key:").map(str::to_string),
+        });
+    }
+    let service = data
+        .services
+        .iter()
+        .map(|(id, service)| Service {
+            id: format!("#{id}"),
+            r#type: service.r#type.clone(),
+            service_endpoint: service.endpoint.clone(),
+        })
+        .collect();
+
+    Ok(DidDocument {
+        context: Some(context),
+        id: did.to_string(),
+        also_known_as: Some(data.also_known_as.clone()),
+        verification_method: Some(verification_method),
+        service: Some(service),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rsky_crypto::secp256k1::keypair::Secp256k1Keypair;
+    use rsky_crypto::types::Keypair;
+    use rsky_crypto::utils::base64url_encode;
+    use serde_json::json;
+
+    /// Rotation keys in priority order, and the account's atproto signing key.
+    struct Keys {
+        recovery: Secp256k1Keypair,
+        rotation: Secp256k1Keypair,
+        signing: Secp256k1Keypair,
+    }
+
+    fn keys() -> Keys {
+        Keys {
+            recovery: Secp256k1Keypair::create(false),
+            rotation: Secp256k1Keypair::create(false),
+            signing: Secp256k1Keypair::create(false),
+        }
+    }
+
+    fn sign(mut operation: Value, keypair: &Secp256k1Keypair) -> Value {
+        let sig = keypair.sign(&encode_json(&operation).unwrap()).unwrap();
+        operation["sig"] = json!(base64url_encode(&sig));
+        operation
+    }
+
+    fn update(keys: &Keys, handle: &str, prev: Option<&str>) -> Value {
+        json!({
+            "type": "plc_operation",
+            "rotationKeys": [keys.recovery.did().unwrap(), keys.rotation.did().unwrap()],
+            "verificationMethods": { "atproto": keys.signing.did().unwrap() },
+            "alsoKnownAs": [format!("at://{handle}")],
+            "services": {
+                "atproto_pds": {
+                    "type": "AtprotoPersonalDataServer",
+                    "endpoint": "https://pds.example.com"
+                }
+            },
+            "prev": prev
+        })
+    }
+
+    fn entry(did: &str, operation: Value, created_at: &str) -> AuditLogEntry {
+        AuditLogEntry {
+            did: did.to_string(),
+            cid: cid_for_cbor(&encode_json(&operation).unwrap()).to_string(),
+            operation,
+            nullified: false,
+            created_at: created_at.to_string(),
+        }
+    }
+
+    /// A genesis op followed by a handle change, both signed with the lower priority key.
+    fn log(keys: &Keys) -> (String, Vec<AuditLogEntry>) {
+        let genesis = sign(update(keys, "alice.test", None), &keys.rotation);
+        let did = did_for_genesis_op(&genesis).unwrap();
+        let genesis = entry(&did, genesis, "2024-01-01T00:00:00.000Z");
+        let renamed = sign(
+            update(keys, "alice2.test", Some(&genesis.cid)),
+            &keys.rotation,
+        );
+        let renamed = entry(&did, renamed, "2024-01-02T00:00:00.000Z");
+        (did, vec![genesis, renamed])
+    }
+
+    #[test]
+    fn test_verifies_and_formats_log() {
+        let keys = keys();
+        let (did, log) = log(&keys);
+
+        let data = verify_audit_log(&did, &log).unwrap().unwrap();
+        assert_eq!(data.also_known_as, vec!["at://alice2.test"]);
+        assert!(did.starts_with("did:plc:") && did.len() == 32);
+
+        let doc = format_did_doc(&did, &data).unwrap();
+        let signing_key = keys.signing.did().unwrap();
+        assert_eq!(
+            doc.verification_method.unwrap()[0].public_key_multibase,
+            signing_key.strip_prefix("did:key:").map(str::to_string)
+        );
+        assert_eq!(
+            doc.service.unwrap()[0].service_endpoint,
+            "https://pds.example.com"
+        );
+    }
+
+    #[test]
+    fn test_rejects_tampered_operations() {
+        let keys = keys();
+        let (did, log) = log(&keys);
+
+        // Edited after signing, with the cid recomputed to hide it
+        let mut forged = log.clone();
+        forged[1].operation["alsoKnownAs"] = json!(["at://mallory.test"]);
+        forged[1] = entry(&did, forged[1].operation.clone(), &forged[1].created_at);
+        assert!(verify_audit_log(&did, &forged).is_err());
+
+        // Edited without touching the cid
+        let mut mismatched = log.clone();
+        mismatched[1].operation["alsoKnownAs"] = json!(["at://mallory.test"]);
+        assert!(verify_audit_log(&did, &mismatched).is_err());
+
+        // Signed by a key that was never a rotation key
+        let mut unauthorized = log.clone();
+        let op = sign(
+            update(&keys, "mallory.test", Some(&log[0].cid)),
+            &keys.signing,
+        );
+        unauthorized[1] = entry(&did, op, &log[1].created_at);
+        assert!(verify_audit_log(&did, &unauthorized).is_err());
+
+        // Genesis of some other DID
+        assert!(verify_audit_log("did:plc:aaaaaaaaaaaaaaaaaaaaaaaa", &log).is_err());
+    }
+
+    #[test]
+    fn test_recovery_nullifies_within_window() {
+        let keys = keys();
+        let (did, mut log) = log(&keys);
+        let recovered = sign(
+            update(&keys, "alice.test", Some(&log[0].cid)),
+            &keys.recovery,
+        );
+        log.push(entry(&did, recovered, "2024-01-03T00:00:00.000Z"));
+
+        // The directory has to flag the forked-off operation as nullified
+        assert!(verify_audit_log(&did, &log).is_err());
+        log[1].nullified = true;
+        let data = verify_audit_log(&did, &log).unwrap().unwrap();
+        assert_eq!(data.also_known_as, vec!["at://alice.test"]);
+
+        let mut late = log.clone();
+        late[2] = entry(&did, log[2].operation.clone(), "2024-01-05T00:00:01.000Z");
+        assert!(verify_audit_log(&did, &late).is_err());
+
+        // A key of the same rank can't nullify
+        let mut same_rank = log.clone();
+        let op = sign(
+            update(&keys, "alice.test", Some(&log[0].cid)),
+            &keys.rotation,
+        );
+        same_rank[2] = entry(&did, op, "2024-01-03T00:00:00.000Z");
+        assert!(verify_audit_log(&did, &same_rank).is_err());
+    }
+
+    #[test]
+    fn test_tombstone() {
+        let keys = keys();
+        let (did, mut log) = log(&keys);
+        let tombstone = sign(
+            json!({ "type": "plc_tombstone", "prev": log[1].cid }),
+            &keys.rotation,
+        );
+        log.push(entry(&did, tombstone, "2024-01-03T00:00:00.000Z"));
+
+        assert_eq!(verify_audit_log(&did, &log).unwrap(), None);
+    }
+
+    #[test]
+    fn test_legacy_create_is_normalized() {
+        let keys = keys();
+        let create = sign(
+            json!({
+                "type": "create",
+                "signingKey": keys.signing.did().unwrap(),
+                "recoveryKey": keys.recovery.did().unwrap(),
+                "handle": "alice.test",
+                "service": "pds.example.com",
+                "prev": null
+            }),
+            &keys.signing,
+        );
+        let did = did_for_genesis_op(&create).unwrap();
+        let log = vec![entry(&did, create, "2023-01-01T00:00:00.000Z")];
+
+        let data = verify_audit_log(&did, &log).unwrap().unwrap();
+        assert_eq!(
+            data.rotation_keys,
+            vec![keys.recovery.did().unwrap(), keys.signing.did().unwrap()]
+        );
+        assert_eq!(data.also_known_as, vec!["at://alice.test"]);
+        assert_eq!(
+            data.services["atproto_pds"].endpoint,
+            "https://pds.example.com"
+        );
+    }
+}
@@ -1,7 +1,11 @@
 use crate::common::encode_uri_component;
-use crate::types::DidCache;
-use anyhow::{bail, Result};
+use crate::did::plc_log::{format_did_doc, verify_audit_log, AuditLogEntry};
+use crate::transport::HttpTransport;
+use crate::types::{DidCache, DidDocument, Service, VerificationMethod};
+use anyhow::Result;
 use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::Arc;
 use std::time::Duration;
 
 #[derive(Clone, Debug)]
@@ -9,32 +13,197 @@ pub struct DidPlcResolver {
     pub plc_url: String,
     pub timeout: Duration,
     pub cache: Option<DidCache>,
+    pub transport: Arc<dyn HttpTransport>,
 }
 
 impl DidPlcResolver {
-    pub fn new(plc_url: String, timeout: Duration, cache: Option<DidCache>) -> Self {
+    pub fn new(
+        plc_url: String,
+        timeout: Duration,
+        cache: Option<DidCache>,
+        transport: Arc<dyn HttpTransport>,
+    ) -> Self {
         Self {
             plc_url,
             timeout,
             cache,
+            transport,
         }
     }
 
     pub async fn resolve_no_check(&self, did: String) -> Result<Option<Value>> {
-        let client = reqwest::Client::new();
-        let response = client
-            .get(format!("{0}/{1}", self.plc_url, encode_uri_component(&did)))
-            .timeout(self.timeout)
-            .header("Connection", "Keep-Alive")
-            .header("Keep-Alive", "timeout=5, max=1000")
-            .send()
-            .await?;
-        let res = &response;
-        match res.error_for_status_ref() {
-            Ok(_) => Ok(Some(response.json::<Value>().await?)),
-            // Positively not found, versus due to e.g. network error
-            Err(error) if error.status() == Some(reqwest::StatusCode::NOT_FOUND) => Ok(None),
-            Err(error) => bail!(error.to_string()),
+        let url = format!("{0}/{1}", self.plc_url, encode_uri_component(&did));
+        self.transport.get(&url, self.timeout).await?.json_or_none()
+    }
+
+    pub async fn get_audit_log(&self, did: &str) -> Result<Option<Vec<AuditLogEntry>>> {
+        let url = format!("{0}/{1}/log/audit", self.plc_url, encode_uri_component(did));
+        self.transport.get(&url, self.timeout).await?.json_or_none()
+    }
+
+    /// Builds the document from the DID's verified operation log rather than trusting the
+    /// directory's rendering of it. `None` if the DID is unknown or tombstoned.
+    pub async fn resolve_verified(&self, did: &str) -> Result<Option<DidDocument>> {
+        let Some(log) = self.get_audit_log(did).await? else {
+            return Ok(None);
+        };
+        match verify_audit_log(did, &log)? {
+            None => Ok(None),
+            Some(data) => Ok(Some(format_did_doc(did, &data)?)),
+        }
+    }
+
+    /// Whether the document `plc_url` serves matches the one its own audit log produces. A
+    /// mismatch means a mirror is stale or has been tampered with.
+    pub async fn verify_mirror(&self, did: &str) -> Result<bool> {
+        let served = match self.resolve_no_check(did.to_string()).await? {
+            None => None,
+            Some(doc) => Some(serde_json::from_value::<DidDocument>(doc)?),
+        };
+        let verified = self.resolve_verified(did).await?;
+        Ok(served.as_ref().map(DocState::from) == verified.as_ref().map(DocState::from))
+    }
+}
+
+/// What a DID document says, ignoring its `@context` and the order of its keys and services,
+/// which are presentation rather than state. Handles keep their order, since the first `at://`
+/// alias is the one a DID claims.
+#[derive(PartialEq)]
+struct DocState<'a> {
+    id: &'a str,
+    also_known_as: Vec<&'a str>,
+    verification_methods: BTreeMap<&'a str, &'a VerificationMethod>,
+    services: BTreeMap<&'a str, &'a Service>,
+}
+
+impl<'a> From<&'a DidDocument> for DocState<'a> {
+    fn from(doc: &'a DidDocument) -> Self {
+        Self {
+            id: &doc.id,
+            also_known_as: doc
+                .also_known_as
+                .iter()
+                .flatten()
+                .map(String::as_str)
+                .collect(),
+            verification_methods: doc
+                .verification_method
+                .iter()
+                .flatten()
+                .map(|method| (method.id.as_str(), method))
+                .collect(),
+            services: doc
+                .service
+                .iter()
+                .flatten()
+                .map(|service| (service.id.as_str(), service))
+                .collect(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::did::plc_log::did_for_genesis_op;
+    use crate::transport::MockTransport;
+    use rsky_crypto::dag_cbor::{cid_for_cbor, encode_json};
+    use rsky_crypto::secp256k1::keypair::Secp256k1Keypair;
+    use rsky_crypto::types::Keypair;
+    use rsky_crypto::utils::base64url_encode;
+    use serde_json::json;
+
+    const PLC_URL: &str = "https://plc.example.com";
+
+    /// A directory serving `doc` and `log` for `did`.
+    fn plc_stub(did: &str, doc: Option<Value>, log: Option<Value>) -> DidPlcResolver {
+        let transport = MockTransport::new();
+        let did = encode_uri_component(did);
+        if let Some(doc) = doc {
+            transport.insert_json(format!("{PLC_URL}/{did}"), &doc);
+        }
+        if let Some(log) = log {
+            transport.insert_json(format!("{PLC_URL}/{did}/log/audit"), &log);
+        }
+        DidPlcResolver::new(
+            PLC_URL.to_string(),
+            Duration::from_secs(1),
+            None,
+            Arc::new(transport),
+        )
+    }
+
+    /// A single-operation log, recorded the way the directory serves it.
+    fn recorded_log(handle: &str) -> (String, Value) {
+        let keypair = Secp256k1Keypair::create(false);
+        let mut operation = json!({
+            "type": "plc_operation",
+            "rotationKeys": [keypair.did().unwrap()],
+            "verificationMethods": { "atproto": keypair.did().unwrap() },
+            "alsoKnownAs": [format!("at://{handle}"), format!("https://{handle}")],
+            "services": {
+                "atproto_pds": {
+                    "type": "AtprotoPersonalDataServer",
+                    "endpoint": "https://pds.example.com"
+                },
+                "atproto_labeler": {
+                    "type": "AtprotoLabeler",
+                    "endpoint": "https://labeler.example.com"
+                }
+            },
+            "prev": null
+        });
+        let sig = keypair.sign(&encode_json(&operation).unwrap()).unwrap();
+        operation["sig"] = json!(base64url_encode(&sig));
+        let did = did_for_genesis_op(&operation).unwrap();
+        let log = json!([{
+            "did": did,
+            "operation": operation,
+            "cid": cid_for_cbor(&encode_json(&operation).unwrap()).to_string(),
+            "nullified": false,
+            "createdAt": "2024-01-01T00:00:00.000Z"
+        }]);
+        (did, log)
+    }
+
+    fn served_doc(did: &str, handle: &str) -> Value {
+        json!({ "id": did, "alsoKnownAs": [format!("at://{handle}")] })
+    }
+
+    #[tokio::test]
+    async fn test_resolve_verified_and_detect_stale_mirror() {
+        let (did, log) = recorded_log("alice.test");
+        let verified = {
+            let entries: Vec<AuditLogEntry> = serde_json::from_value(log.clone()).unwrap();
+            let data = verify_audit_log(&did, &entries).unwrap().unwrap();
+            serde_json::to_value(format_did_doc(&did, &data).unwrap()).unwrap()
+        };
+
+        let resolver = plc_stub(&did, Some(verified), Some(log.clone()));
+        let doc = resolver.resolve_verified(&did).await.unwrap().unwrap();
+        assert_eq!(
+            doc.also_known_as.as_deref().unwrap(),
+            ["at://alice.test", "https://alice.test"]
+        );
+        assert!(resolver.verify_mirror(&did).await.unwrap());
+
+        // The same document listing its services in another order
+        let mut reordered = serde_json::to_value(&doc).unwrap();
+        reordered["service"].as_array_mut().unwrap().reverse();
+        reordered.as_object_mut().unwrap().remove("@context");
+        let resolver = plc_stub(&did, Some(reordered.clone()), Some(log.clone()));
+        assert!(resolver.verify_mirror(&did).await.unwrap());
+
+        // Reordering its handles changes which one the DID claims
+        reordered["alsoKnownAs"].as_array_mut().unwrap().reverse();
+        let resolver = plc_stub(&did, Some(reordered), Some(log.clone()));
+        assert!(!resolver.verify_mirror(&did).await.unwrap());
+
+        // Serving a document the log never produced
+        let resolver = plc_stub(&did, Some(served_doc(&did, "old.test")), Some(log));
+        assert!(!resolver.verify_mirror(&did).await.unwrap());
+
+        let resolver = plc_stub(&did, None, None);
+        assert_eq!(resolver.resolve_verified(&did).await.unwrap(), None);
+    }
+}
@@ -1,8 +1,10 @@
 use crate::common::decode_uri_component;
 use crate::errors::Error;
+use crate::transport::HttpTransport;
 use crate::types::DidCache;
 use anyhow::{bail, Result};
 use serde_json::Value;
+use std::sync::Arc;
 use std::time::Duration;
 use url::Url;
 
@@ -12,11 +14,20 @@ pub const DOC_PATH: &str = "/.well-known/did.json";
 pub struct DidWebResolver {
     pub timeout: Duration,
     pub cache: Option<DidCache>,
+    pub transport: Arc<dyn HttpTransport>,
 }
 
 impl DidWebResolver {
-    pub fn new(timeout: Duration, cache: Option<DidCache>) -> Self {
-        Self { timeout, cache }
+    pub fn new(
+        timeout: Duration,
+        cache: Option<DidCache>,
+        transport: Arc<dyn HttpTransport>,
+    ) -> Self {
+        Self {
+            timeout,
+            cache,
+            transport,
+        }
     }
 
     pub async fn resolve_no_check(&self, did: String) -> Result<Option<Value>> {
@@ -42,20 +53,9 @@ impl DidWebResolver {
             let _ = url.set_scheme("http");
         }
 
-        let client = reqwest::Client::new();
-        let response = client
-            .get(url.to_string())
-            .timeout(self.timeout)
-            .header("Connection", "Keep-Alive")
-            .header("Keep-Alive", "timeout=5, max=1000")
-            .send()
-            .await?;
-        let res = &response;
-        match res.error_for_status_ref() {
-            Ok(_) => Ok(Some(response.json::<Value>().await?)),
-            // Positively not found, versus due to e.g. network error
-            Err(error) if error.status() == Some(reqwest::StatusCode::NOT_FOUND) => Ok(None),
-            Err(error) => bail!(error.to_string()),
-        }
+        self.transport
+            .get(url.as_str(), self.timeout)
+            .await?
+            .json_or_none()
     }
 }
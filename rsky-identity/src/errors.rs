@@ -13,4 +13,8 @@ pub enum Error {
     PoorlyFormattedDidDocumentError(Value),
     #[error("Unsupported did:web paths: `{0}`")]
     UnsupportedDidWebPathError(String),
+    #[error("DID document for `{0}` has no atproto {1}")]
+    IncompleteDidDocumentError(String, &'static str),
+    #[error("Invalid PLC operation log for `{0}`: {1}")]
+    InvalidPlcLogError(String, String),
 }
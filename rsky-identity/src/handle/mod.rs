@@ -1,48 +1,161 @@
+use crate::common::HOUR;
+use crate::transport::{HttpTransport, ReqwestTransport};
 use crate::types::HandleResolverOpts;
 use anyhow::Result;
+use async_trait::async_trait;
 use hickory_resolver::config::*;
-use hickory_resolver::error::ResolveResult;
-use hickory_resolver::lookup_ip::LookupIp;
-use hickory_resolver::Resolver;
+use hickory_resolver::system_conf::read_system_conf;
+use hickory_resolver::TokioAsyncResolver;
+use rsky_syntax::handle::ResolveHandle;
+use rsky_syntax::{Did, Handle};
+use std::collections::HashMap;
+use std::future::Future;
 use std::net::{IpAddr, SocketAddr};
-use std::time::Duration;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tokio::time::timeout;
 use url::Url;
 
 pub const SUBDOMAIN: &str = "_atproto";
 pub const PREFIX: &str = "did=";
+pub const DEFAULT_NAMESERVER_PORT: u16 = 53;
+
+#[derive(Clone, Debug)]
+struct CachedHandle {
+    did: String,
+    resolved_at: Instant,
+}
 
 #[derive(Clone, Debug)]
 pub struct HandleResolver {
     pub timeout: Duration,
+    /// How long a resolved handle is trusted before it's looked up again.
+    pub cache_ttl: Duration,
     backup_nameservers: Option<Vec<String>>,
+    backup_nameserver_port: u16,
     backup_nameserver_ips: Option<Vec<IpAddr>>,
+    /// Nameservers for the primary lookup, the system's unless they can't be read.
+    dns_config: ResolverConfig,
+    transport: Arc<dyn HttpTransport>,
+    cache: Arc<Mutex<HashMap<String, CachedHandle>>>,
 }
 
 impl HandleResolver {
     pub fn new(opts: HandleResolverOpts) -> Self {
+        let dns_config = read_system_conf()
+            .map(|(config, _)| config)
+            .unwrap_or_default();
         Self {
             timeout: opts.timeout.unwrap_or(Duration::from_millis(3000)),
+            cache_ttl: opts
+                .cache_ttl
+                .unwrap_or_else(|| Duration::from_millis(HOUR as u64)),
             backup_nameservers: opts.backup_nameservers,
+            backup_nameserver_port: opts
+                .backup_nameserver_port
+                .unwrap_or(DEFAULT_NAMESERVER_PORT),
             backup_nameserver_ips: None,
+            dns_config,
+            transport: opts
+                .transport
+                .unwrap_or_else(|| Arc::new(ReqwestTransport::default())),
+            cache: Default::default(),
         }
     }
 
+    /// Resolves a handle to its DID, racing the DNS and HTTP methods and taking the first to
+    /// find one. Backup nameservers are only tried once both come up empty.
     pub async fn resolve(&mut self, handle: &String) -> Result<Option<String>> {
-        let dns_future = self.resolve_dns(handle);
-        let http_future = self.resolve_http(handle);
-
-        match dns_future.await {
-            Ok(dns_res) => Ok(dns_res),
-            Err(_) => match http_future.await {
-                Ok(http_res) => Ok(http_res),
-                Err(_) => self.resolve_backup_dns(handle).await,
-            },
+        if let Some(did) = self.check_cache(handle) {
+            return Ok(Some(did));
+        }
+
+        let found = match self.race(handle).await {
+            Some(did) => Some(did),
+            None => self.resolve_backup_dns(handle).await?,
+        };
+        if let Some(ref did) = found {
+            self.lock_cache().insert(
+                handle.to_lowercase(),
+                CachedHandle {
+                    did: did.clone(),
+                    resolved_at: Instant::now(),
+                },
+            );
         }
+        Ok(found)
     }
 
-    pub async fn resolve_dns(&self, handle: &String) -> Result<Option<String>> {
-        let resolver = Resolver::new(ResolverConfig::default(), ResolverOpts::default())?;
-        let results = match resolver.txt_lookup(format!("{SUBDOMAIN}.{handle}")) {
+    async fn race(&self, handle: &String) -> Option<String> {
+        let dns = self.with_timeout(self.resolve_dns(handle));
+        let http = self.with_timeout(self.resolve_http(handle));
+        tokio::pin!(dns, http);
+
+        let (mut dns_done, mut http_done) = (false, false);
+        while !(dns_done && http_done) {
+            let found = tokio::select! {
+                found = &mut dns, if !dns_done => {
+                    dns_done = true;
+                    found
+                }
+                found = &mut http, if !http_done => {
+                    http_done = true;
+                    found
+                }
+            };
+            if found.is_some() {
+                return found;
+            }
+        }
+        None
+    }
+
+    /// Errors and timeouts both count as not found, so the other methods still get a chance.
+    async fn with_timeout(
+        &self,
+        lookup: impl Future<Output = Result<Option<String>>>,
+    ) -> Option<String> {
+        match timeout(self.timeout, lookup).await {
+            Ok(Ok(found)) => found,
+            _ => None,
+        }
+    }
+
+    fn lock_cache(&self) -> MutexGuard<'_, HashMap<String, CachedHandle>> {
+        self.cache
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn check_cache(&self, handle: &str) -> Option<String> {
+        let mut cache = self.lock_cache();
+        let handle = handle.to_lowercase();
+        match cache.get(&handle) {
+            Some(cached) if cached.resolved_at.elapsed() < self.cache_ttl => {
+                Some(cached.did.clone())
+            }
+            Some(_) => {
+                cache.remove(&handle);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Forgets a cached resolution, e.g. once the handle is known to have moved.
+    pub fn clear_cache_entry(&self, handle: &str) {
+        self.lock_cache().remove(&handle.to_lowercase());
+    }
+
+    fn dns_resolver(&self, config: ResolverConfig) -> TokioAsyncResolver {
+        let mut opts = ResolverOpts::default();
+        opts.timeout = self.timeout;
+        TokioAsyncResolver::tokio(config, opts)
+    }
+
+    async fn lookup_txt(&self, config: ResolverConfig, handle: &String) -> Result<Option<String>> {
+        let resolver = self.dns_resolver(config);
+        let results = match resolver.txt_lookup(format!("{SUBDOMAIN}.{handle}.")).await {
             Ok(res) => res,
             Err(_) => return Ok(None),
         };
@@ -55,18 +168,17 @@ impl HandleResolver {
         self.parse_dns_result(results)
     }
 
+    pub async fn resolve_dns(&self, handle: &String) -> Result<Option<String>> {
+        self.lookup_txt(self.dns_config.clone(), handle).await
+    }
+
     pub async fn resolve_http(&self, handle: &String) -> Result<Option<String>> {
         let url = Url::parse(format!("https://{handle}/.well-known/atproto-did").as_str())?;
-        let client = reqwest::Client::new();
-
-        let res = client
-            .get(url.as_str())
-            .header("Connection", "Keep-Alive")
-            .header("Keep-Alive", "timeout=5, max=1000")
-            .send()
-            .await?;
-
-        let res = res.text().await?;
+        let res = self.transport.get(url.as_str(), self.timeout).await?;
+        if !res.is_success() {
+            return Ok(None);
+        }
+        let res = String::from_utf8_lossy(&res.body);
 
         let did = match res.split("\n").collect::<Vec<&str>>().first() {
             None => return Ok(None),
@@ -83,33 +195,14 @@ impl HandleResolver {
         let backup_ips = self.get_backup_nameserver_ips().await?;
         match backup_ips {
             Some(backup_ips) if !backup_ips.is_empty() => {
-                let mut config = ResolverConfig::default();
-                let _ = backup_ips
-                    .iter()
-                    .map(|ip| {
-                        config.add_name_server(NameServerConfig {
-                            socket_addr: SocketAddr::new(*ip, 8080),
-                            protocol: Default::default(),
-                            tls_dns_name: None,
-                            trust_negative_responses: false,
-                            bind_addr: None,
-                        })
-                    })
-                    .collect::<Vec<()>>();
-
-                let resolver = Resolver::new(config, ResolverOpts::default())?;
-
-                let results = match resolver.txt_lookup(format!("{SUBDOMAIN}.{handle}")) {
-                    Ok(res) => res,
-                    Err(_) => return Ok(None),
-                };
-
-                let results = results
-                    .iter()
-                    .map(|item| item.to_string())
-                    .collect::<Vec<String>>();
-
-                self.parse_dns_result(results)
+                let mut config = ResolverConfig::new();
+                for ip in backup_ips {
+                    config.add_name_server(NameServerConfig::new(
+                        SocketAddr::new(ip, self.backup_nameserver_port),
+                        Protocol::Udp,
+                    ));
+                }
+                Ok(self.with_timeout(self.lookup_txt(config, handle)).await)
             }
             _ => Ok(None),
         }
@@ -132,24 +225,199 @@ impl HandleResolver {
             None => return Ok(None),
             Some(backup_nameservers) => {
                 if self.backup_nameserver_ips.is_none() {
-                    let resolver =
-                        Resolver::new(ResolverConfig::default(), ResolverOpts::default())?;
-                    let responses: Vec<LookupIp> = backup_nameservers
-                        .iter()
-                        .map(|h| resolver.lookup_ip(h))
-                        .collect::<ResolveResult<Vec<LookupIp>>>()?;
-
-                    for response in responses {
-                        let mut backup_nameserver_ips = match &self.backup_nameserver_ips {
-                            None => vec![],
-                            Some(backup_nameserver_ips) => backup_nameserver_ips.clone(),
-                        };
-                        backup_nameserver_ips.append(&mut response.iter().collect::<Vec<IpAddr>>());
-                        self.backup_nameserver_ips = Some(backup_nameserver_ips);
+                    let resolver = self.dns_resolver(self.dns_config.clone());
+                    let mut backup_nameserver_ips = vec![];
+                    for nameserver in backup_nameservers {
+                        let response = resolver.lookup_ip(nameserver.as_str()).await?;
+                        backup_nameserver_ips.extend(response.iter());
                     }
+                    self.backup_nameserver_ips = Some(backup_nameserver_ips);
                 }
             }
         }
         Ok(self.backup_nameserver_ips.clone())
     }
 }
+
+#[async_trait]
+impl ResolveHandle for HandleResolver {
+    async fn resolve_handle(&self, handle: &Handle) -> Result<Option<Did>> {
+        // Clones share the cache, so this is as cheap as resolving on `self`
+        let mut resolver = self.clone();
+        match resolver.resolve(&handle.to_string()).await? {
+            Some(did) => Ok(Some(did.parse()?)),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::MockTransport;
+    use hickory_resolver::proto::op::{Message, MessageType, ResponseCode};
+    use hickory_resolver::proto::rr::rdata::TXT;
+    use hickory_resolver::proto::rr::{RData, Record};
+    use tokio::net::UdpSocket;
+
+    const HANDLE: &str = "alice.test";
+    const DNS_DID: &str = "did:plc:ewvi7nxzyoun6zhxrhs64oiz";
+    const HTTP_DID: &str = "did:web:alice.test";
+    const WELL_KNOWN: &str = "https://alice.test/.well-known/atproto-did";
+
+    /// Answers every TXT query with `records` after `delay`, or NXDOMAIN when there are none.
+    async fn dns_stub(records: Vec<&'static str>, delay: Duration) -> SocketAddr {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 512];
+            loop {
+                let (len, peer) = socket.recv_from(&mut buf).await.unwrap();
+                let query = Message::from_vec(&buf[..len]).unwrap();
+                let mut response = Message::new();
+                response
+                    .set_id(query.id())
+                    .set_message_type(MessageType::Response)
+                    .set_recursion_desired(query.recursion_desired())
+                    .set_recursion_available(true)
+                    .add_queries(query.queries().to_vec());
+                if records.is_empty() {
+                    response.set_response_code(ResponseCode::NXDomain);
+                }
+                for txt in &records {
+                    let name = query.queries()[0].name().clone();
+                    let txt = TXT::new(vec![txt.to_string()]);
+                    response.add_answer(Record::from_rdata(name, 60, RData::TXT(txt)));
+                }
+                tokio::time::sleep(delay).await;
+                socket
+                    .send_to(&response.to_vec().unwrap(), peer)
+                    .await
+                    .unwrap();
+            }
+        });
+        addr
+    }
+
+    /// Serves `body` as the handle's `/.well-known/atproto-did` after `delay`.
+    fn http_stub(body: &'static str, delay: Duration) -> Arc<MockTransport> {
+        let transport = MockTransport::new().with_delay(delay);
+        transport.insert(WELL_KNOWN, 200, body);
+        Arc::new(transport)
+    }
+
+    fn resolver(
+        dns: SocketAddr,
+        http: Arc<MockTransport>,
+        backup: Option<SocketAddr>,
+    ) -> HandleResolver {
+        let mut resolver = HandleResolver::new(HandleResolverOpts {
+            timeout: Some(Duration::from_millis(500)),
+            backup_nameservers: backup.map(|backup| vec![backup.ip().to_string()]),
+            backup_nameserver_port: backup.map(|backup| backup.port()),
+            cache_ttl: None,
+            transport: Some(http),
+        });
+        resolver.dns_config = ResolverConfig::from_parts(
+            None,
+            vec![],
+            vec![NameServerConfig::new(dns, Protocol::Udp)],
+        );
+        resolver
+    }
+
+    #[tokio::test]
+    async fn test_dns_wins_race_against_slow_http() {
+        let dns = dns_stub(vec!["did=did:plc:ewvi7nxzyoun6zhxrhs64oiz"], Duration::ZERO).await;
+        let http = http_stub(HTTP_DID, Duration::from_secs(5));
+        let mut resolver = resolver(dns, http, None);
+
+        let started = Instant::now();
+        let did = resolver.resolve(&HANDLE.to_string()).await.unwrap();
+
+        assert_eq!(did.as_deref(), Some(DNS_DID));
+        assert!(started.elapsed() < Duration::from_millis(400));
+    }
+
+    #[tokio::test]
+    async fn test_http_answers_when_dns_has_nothing() {
+        let dns = dns_stub(vec![], Duration::ZERO).await;
+        let http = http_stub(HTTP_DID, Duration::ZERO);
+        let mut resolver = resolver(dns, http, None);
+
+        let did = resolver.resolve(&HANDLE.to_string()).await.unwrap();
+
+        assert_eq!(did.as_deref(), Some(HTTP_DID));
+    }
+
+    #[tokio::test]
+    async fn test_backup_nameserver_on_custom_port() {
+        let dns = dns_stub(vec![], Duration::ZERO).await;
+        let http = http_stub("not a did", Duration::ZERO);
+        let backup = dns_stub(vec!["did=did:plc:ewvi7nxzyoun6zhxrhs64oiz"], Duration::ZERO).await;
+
+        let did = resolver(dns, http.clone(), Some(backup))
+            .resolve(&HANDLE.to_string())
+            .await
+            .unwrap();
+        assert_eq!(did.as_deref(), Some(DNS_DID));
+
+        let did = resolver(dns, http, None)
+            .resolve(&HANDLE.to_string())
+            .await
+            .unwrap();
+        assert_eq!(did, None);
+    }
+
+    #[tokio::test]
+    async fn test_ambiguous_dns_records_are_ignored() {
+        let dns = dns_stub(
+            vec!["did=did:plc:ewvi7nxzyoun6zhxrhs64oiz", "did=did:plc:other"],
+            Duration::ZERO,
+        )
+        .await;
+        let http = http_stub("", Duration::ZERO);
+
+        let did = resolver(dns, http, None)
+            .resolve(&HANDLE.to_string())
+            .await
+            .unwrap();
+        assert_eq!(did, None);
+    }
+
+    #[tokio::test]
+    async fn test_resolutions_are_cached_until_cleared() {
+        let dns = dns_stub(vec![], Duration::ZERO).await;
+        let http = http_stub(HTTP_DID, Duration::ZERO);
+        let mut resolver = resolver(dns, http, None);
+        resolver.resolve(&HANDLE.to_string()).await.unwrap();
+
+        // With nothing left to answer, only the cache can resolve the handle
+        resolver.transport = http_stub("", Duration::ZERO);
+        let did = resolver.resolve(&"Alice.test".to_string()).await.unwrap();
+        assert_eq!(did.as_deref(), Some(HTTP_DID));
+
+        resolver.clear_cache_entry(HANDLE);
+        assert_eq!(resolver.resolve(&HANDLE.to_string()).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_resolves_bsky_app_urls() {
+        use rsky_syntax::aturi::AtUri;
+
+        let dns = dns_stub(vec![], Duration::ZERO).await;
+        let http = http_stub(HTTP_DID, Duration::ZERO);
+        let resolver = resolver(dns, http, None);
+
+        let uri = AtUri::from_bsky_app_url(
+            "https://bsky.app/profile/alice.test/post/3latjcehsho2n",
+            &resolver,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            uri.to_string(),
+            "at://did:web:alice.test/app.bsky.feed.post/3latjcehsho2n"
+        );
+    }
+}
@@ -2,7 +2,10 @@ extern crate url;
 
 use crate::did::did_resolver::DidResolver;
 use crate::handle::HandleResolver;
+use crate::transport::ReqwestTransport;
 use crate::types::{DidCache, DidResolverOpts, HandleResolverOpts, IdentityResolverOpts};
+use anyhow::Result;
+use std::sync::Arc;
 use std::time::Duration;
 
 #[derive(Clone, Debug)]
@@ -18,30 +21,122 @@ impl IdResolver {
             plc_url,
             did_cache,
             backup_nameservers,
+            backup_nameserver_port,
+            handle_cache_ttl,
+            transport,
         } = opts;
         let timeout = timeout.unwrap_or_else(|| Duration::from_millis(3000));
-        let did_cache = did_cache.unwrap_or_else(|| DidCache {
-            stale_ttl: Default::default(),
-            max_ttl: Default::default(),
-            cache: Default::default(),
-        });
+        let did_cache = did_cache.unwrap_or_else(|| DidCache::new(None, None));
+        let transport =
+            transport.unwrap_or_else(|| Arc::new(ReqwestTransport::default()) as Arc<_>);
 
         Self {
             handle: HandleResolver::new(HandleResolverOpts {
                 timeout: Some(timeout),
                 backup_nameservers,
+                backup_nameserver_port,
+                cache_ttl: handle_cache_ttl,
+                transport: Some(transport.clone()),
             }),
             did: DidResolver::new(DidResolverOpts {
                 timeout: Some(timeout),
                 plc_url,
                 did_cache,
+                transport: Some(transport),
             }),
         }
     }
+
+    /// Resolves a handle and confirms the DID it points to lists `at://<handle>` in its
+    /// `alsoKnownAs`. Returns the DID only if both directions agree.
+    pub async fn verify_handle(&mut self, handle: &String) -> Result<Option<String>> {
+        let Some(did) = self.handle.resolve(handle).await? else {
+            return Ok(None);
+        };
+        let Some(doc) = self.did.resolve(did.clone(), None).await? else {
+            return Ok(None);
+        };
+        let claimed = doc
+            .also_known_as
+            .unwrap_or_default()
+            .iter()
+            .any(|aka| aka.eq_ignore_ascii_case(&format!("at://{handle}")));
+        Ok(claimed.then_some(did))
+    }
 }
 
 pub mod common;
 pub mod did;
 pub mod errors;
 pub mod handle;
+pub mod transport;
 pub mod types;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::MockTransport;
+    use serde_json::json;
+
+    const DID: &str = "did:web:alice.test";
+
+    /// Serves `did:web:alice.test`, claiming `alice.test`, and the `.well-known` DID of each
+    /// handle in `handles`.
+    fn resolver(handles: &[&str]) -> IdResolver {
+        let transport = MockTransport::new();
+        transport.insert_json(
+            "https://alice.test/.well-known/did.json",
+            &json!({
+                "id": DID,
+                "alsoKnownAs": ["at://alice.test"],
+            }),
+        );
+        for handle in handles {
+            transport.insert(
+                format!("https://{handle}/.well-known/atproto-did"),
+                200,
+                DID,
+            );
+        }
+        IdResolver::new(IdentityResolverOpts {
+            timeout: Some(Duration::from_millis(500)),
+            plc_url: None,
+            did_cache: None,
+            backup_nameservers: None,
+            backup_nameserver_port: None,
+            handle_cache_ttl: None,
+            transport: Some(Arc::new(transport)),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_verify_handle_claimed_by_did() {
+        let mut resolver = resolver(&["alice.test"]);
+        let did = resolver
+            .verify_handle(&"Alice.test".to_string())
+            .await
+            .unwrap();
+        assert_eq!(did.as_deref(), Some(DID));
+    }
+
+    #[tokio::test]
+    async fn test_verify_handle_not_claimed_by_did() {
+        // bob.test points at alice's DID, which doesn't claim it back
+        let mut resolver = resolver(&["bob.test"]);
+        let did = resolver
+            .verify_handle(&"bob.test".to_string())
+            .await
+            .unwrap();
+        assert_eq!(did, None);
+    }
+
+    #[tokio::test]
+    async fn test_verify_handle_unresolvable() {
+        let mut resolver = resolver(&[]);
+        let did = resolver
+            .verify_handle(&"carol.test".to_string())
+            .await
+            .unwrap();
+        assert_eq!(did, None);
+    }
+}
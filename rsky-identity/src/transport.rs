@@ -0,0 +1,111 @@
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Mutex;
+use std::time::Duration;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// The parsed body, or `None` for a 404: positively not found, versus e.g. a server error.
+    pub fn json_or_none<T: DeserializeOwned>(&self) -> Result<Option<T>> {
+        match self.status {
+            404 => Ok(None),
+            _ if self.is_success() => Ok(Some(serde_json::from_slice(&self.body)?)),
+            status => bail!("Unexpected HTTP status {status}"),
+        }
+    }
+}
+
+/// How resolvers make HTTP requests, so one pooled client can be shared between them and tests
+/// can run offline.
+#[async_trait]
+pub trait HttpTransport: Send + Sync + Debug {
+    async fn get(&self, url: &str, timeout: Duration) -> Result<HttpResponse>;
+}
+
+/// The default transport. Clones share the client's connection pool.
+#[derive(Clone, Debug, Default)]
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+}
+
+impl ReqwestTransport {
+    pub fn new(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl HttpTransport for ReqwestTransport {
+    async fn get(&self, url: &str, timeout: Duration) -> Result<HttpResponse> {
+        let response = self.client.get(url).timeout(timeout).send().await?;
+        Ok(HttpResponse {
+            status: response.status().as_u16(),
+            body: response.bytes().await?.to_vec(),
+        })
+    }
+}
+
+/// Serves canned responses by exact URL, and 404s everything else.
+#[derive(Debug, Default)]
+pub struct MockTransport {
+    responses: Mutex<HashMap<String, HttpResponse>>,
+    requests: Mutex<Vec<String>>,
+    delay: Duration,
+}
+
+impl MockTransport {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Holds every response back by `delay`, failing requests whose timeout is shorter.
+    pub fn with_delay(self, delay: Duration) -> Self {
+        Self { delay, ..self }
+    }
+
+    pub fn insert(&self, url: impl Into<String>, status: u16, body: impl Into<Vec<u8>>) {
+        let response = HttpResponse {
+            status,
+            body: body.into(),
+        };
+        self.responses.lock().unwrap().insert(url.into(), response);
+    }
+
+    pub fn insert_json(&self, url: impl Into<String>, body: &serde_json::Value) {
+        self.insert(url, 200, body.to_string());
+    }
+
+    /// URLs requested so far, in order.
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl HttpTransport for MockTransport {
+    async fn get(&self, url: &str, timeout: Duration) -> Result<HttpResponse> {
+        self.requests.lock().unwrap().push(url.to_string());
+        if self.delay > timeout {
+            tokio::time::sleep(timeout).await;
+            bail!("Request to {url} timed out");
+        }
+        tokio::time::sleep(self.delay).await;
+        let response = self.responses.lock().unwrap().get(url).cloned();
+        Ok(response.unwrap_or(HttpResponse {
+            status: 404,
+            body: vec![],
+        }))
+    }
+}
@@ -1,11 +1,16 @@
 use crate::common::{DAY, HOUR};
+use crate::did::memory_cache::MemoryDidCache;
+use crate::transport::HttpTransport;
 use anyhow::Result;
+use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use std::fmt::Debug;
 use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 use std::time::{Duration, SystemTime};
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct VerificationMethod {
     pub id: String,
     #[serde(rename = "type")]
@@ -15,7 +20,7 @@ pub struct VerificationMethod {
     pub public_key_multibase: Option<String>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Service {
     pub id: String,
     #[serde(rename = "type")]
@@ -24,7 +29,7 @@ pub struct Service {
     pub service_endpoint: String,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct DidDocument {
     #[serde(rename = "@context")]
     pub context: Option<Vec<String>>,
@@ -41,19 +46,30 @@ pub struct IdentityResolverOpts {
     pub plc_url: Option<String>,
     pub did_cache: Option<DidCache>,
     pub backup_nameservers: Option<Vec<String>>,
+    pub backup_nameserver_port: Option<u16>,
+    pub handle_cache_ttl: Option<Duration>,
+    /// Shared by every resolver. Defaults to a pooled [`ReqwestTransport`].
+    pub transport: Option<Arc<dyn HttpTransport>>,
 }
 
 pub struct HandleResolverOpts {
     pub timeout: Option<Duration>,
     pub backup_nameservers: Option<Vec<String>>,
+    /// Port the backup nameservers listen on. Defaults to 53.
+    pub backup_nameserver_port: Option<u16>,
+    /// How long resolved handles are cached. Defaults to an hour.
+    pub cache_ttl: Option<Duration>,
+    pub transport: Option<Arc<dyn HttpTransport>>,
 }
 
 pub struct DidResolverOpts {
     pub timeout: Option<Duration>,
     pub plc_url: Option<String>,
     pub did_cache: DidCache,
+    pub transport: Option<Arc<dyn HttpTransport>>,
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct AtprotoData {
     pub did: String,
     pub signing_key: String,
@@ -69,45 +85,87 @@ pub struct CacheResult {
     pub expired: bool,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CacheVal {
     pub doc: DidDocument,
     pub updated_at: u128,
 }
 
-/// MemoryCache implementation of DidCache
+/// Storage behind a [`DidCache`]. Stores do their own locking, so one store can be shared between
+/// resolvers, and bound their own size.
+#[async_trait]
+pub trait DidCacheStore: Send + Sync + Debug {
+    async fn get(&self, did: &str) -> Result<Option<CacheVal>>;
+    async fn put(&self, did: String, val: CacheVal) -> Result<()>;
+    async fn remove(&self, did: &str) -> Result<()>;
+    async fn clear(&self) -> Result<()>;
+}
+
+/// Counts of [`DidCache::check_cache`] outcomes. Expired entries count as misses, since the
+/// resolver has to go to the network for them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct DidCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub stale: u64,
+}
+
+#[derive(Debug, Default)]
+struct DidCacheCounters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    stale: AtomicU64,
+}
+
+/// DID document cache with stale/max TTLs over a pluggable [`DidCacheStore`]. Clones share the
+/// same store and stats.
 #[derive(Clone, Debug)]
 pub struct DidCache {
     pub stale_ttl: Duration,
     pub max_ttl: Duration,
-    pub cache: BTreeMap<String, CacheVal>,
+    pub store: Arc<dyn DidCacheStore>,
+    counters: Arc<DidCacheCounters>,
+}
+
+fn now_micros() -> u128 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .expect("timestamp in micros since UNIX epoch")
+        .as_micros()
 }
 
 impl DidCache {
+    /// An unbounded in-memory cache.
     pub fn new(stale_ttl: Option<Duration>, max_ttl: Option<Duration>) -> Self {
+        Self::with_store(Arc::new(MemoryDidCache::new(None)), stale_ttl, max_ttl)
+    }
+
+    pub fn with_store(
+        store: Arc<dyn DidCacheStore>,
+        stale_ttl: Option<Duration>,
+        max_ttl: Option<Duration>,
+    ) -> Self {
         Self {
             stale_ttl: stale_ttl.unwrap_or_else(|| Duration::new(HOUR as u64, 0)),
             max_ttl: max_ttl.unwrap_or_else(|| Duration::new(DAY as u64, 0)),
-            cache: BTreeMap::new(),
+            store,
+            counters: Default::default(),
         }
     }
 
-    pub async fn cache_did(&mut self, did: String, doc: DidDocument) -> Result<()> {
-        let now = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .expect("timestamp in micros since UNIX epoch")
-            .as_micros();
-        self.cache.insert(
-            did,
-            CacheVal {
-                doc,
-                updated_at: now,
-            },
-        );
-        Ok(())
+    pub async fn cache_did(&self, did: String, doc: DidDocument) -> Result<()> {
+        self.store
+            .put(
+                did,
+                CacheVal {
+                    doc,
+                    updated_at: now_micros(),
+                },
+            )
+            .await
     }
 
-    pub async fn refresh_cache<Fut>(&mut self, did: String, get_doc: impl Fn() -> Fut) -> Result<()>
+    pub async fn refresh_cache<Fut>(&self, did: String, get_doc: impl Fn() -> Fut) -> Result<()>
     where
         Fut: Future<Output = Result<Option<DidDocument>>>,
     {
@@ -117,17 +175,22 @@ impl DidCache {
         }
     }
 
-    pub fn check_cache(&self, did: String) -> Result<Option<CacheResult>> {
-        match self.cache.get(&did) {
-            None => Ok(None),
-            Some(val) => {
-                let now = SystemTime::now()
-                    .duration_since(SystemTime::UNIX_EPOCH)
-                    .expect("timestamp in micros since UNIX epoch")
-                    .as_micros();
-                let expired = now > val.updated_at + self.max_ttl.as_micros();
-                let stale = now > val.updated_at + self.stale_ttl.as_micros();
-                let CacheVal { doc, updated_at } = val.clone();
+    pub async fn check_cache(&self, did: String) -> Result<Option<CacheResult>> {
+        match self.store.get(&did).await? {
+            None => {
+                self.counters.misses.fetch_add(1, Ordering::Relaxed);
+                Ok(None)
+            }
+            Some(CacheVal { doc, updated_at }) => {
+                let now = now_micros();
+                let expired = now > updated_at + self.max_ttl.as_micros();
+                let stale = now > updated_at + self.stale_ttl.as_micros();
+                let counter = match (expired, stale) {
+                    (true, _) => &self.counters.misses,
+                    (false, true) => &self.counters.stale,
+                    (false, false) => &self.counters.hits,
+                };
+                counter.fetch_add(1, Ordering::Relaxed);
                 Ok(Some(CacheResult {
                     did,
                     doc,
@@ -139,13 +202,19 @@ impl DidCache {
         }
     }
 
-    pub fn clear_entry(&mut self, did: String) -> Result<()> {
-        self.cache.remove(&did);
-        Ok(())
+    pub async fn clear_entry(&self, did: String) -> Result<()> {
+        self.store.remove(&did).await
     }
 
-    pub fn clear(&mut self) -> Result<()> {
-        self.cache.clear();
-        Ok(())
+    pub async fn clear(&self) -> Result<()> {
+        self.store.clear().await
+    }
+
+    pub fn stats(&self) -> DidCacheStats {
+        DidCacheStats {
+            hits: self.counters.hits.load(Ordering::Relaxed),
+            misses: self.counters.misses.load(Ordering::Relaxed),
+            stale: self.counters.stale.load(Ordering::Relaxed),
+        }
     }
 }
@@ -46,4 +46,36 @@ fn clean_db(database_url: &str) {
             &[],
         )
         .expect("Failed to clean likes");
+    client
+        .execute(
+            "DELETE FROM video where date(\"indexedAt\") < now() - interval '2 days'",
+            &[],
+        )
+        .expect("Failed to clean videos");
+    // Self-labels (source is the record author) only matter while the post is kept
+    client
+        .execute(
+            "DELETE FROM label where src = split_part(uri, '/', 3) and date(\"indexedAt\") < now() - interval '2 days'",
+            &[],
+        )
+        .expect("Failed to clean self-labels");
+    client
+        .execute(
+            "DELETE FROM label where exp is not null and exp::timestamptz < now()",
+            &[],
+        )
+        .expect("Failed to clean expired labels");
+    // Gates only matter while a stored post is gated by them
+    client
+        .execute(
+            "DELETE FROM threadgate t where date(t.\"indexedAt\") < now() - interval '2 days' and not exists (select 1 from post p where p.uri = t.post or p.\"replyRoot\" = t.post)",
+            &[],
+        )
+        .expect("Failed to clean threadgates");
+    client
+        .execute(
+            "DELETE FROM postgate g where date(g.\"indexedAt\") < now() - interval '2 days' and not exists (select 1 from post p where p.uri = g.post or p.\"quoteUri\" = g.post)",
+            &[],
+        )
+        .expect("Failed to clean postgates");
 }
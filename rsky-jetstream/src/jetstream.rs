@@ -1,8 +1,16 @@
 use anyhow::{bail, Result};
 use chrono::{DateTime, Utc};
+use rsky_crypto::dag_cbor;
 use rsky_lexicon::app::bsky::feed::like::Like;
+use rsky_lexicon::app::bsky::feed::postgate::Postgate;
+use rsky_lexicon::app::bsky::feed::threadgate::Threadgate;
 use rsky_lexicon::app::bsky::feed::{Post, Repost};
+use rsky_lexicon::app::bsky::graph::block::Block;
 use rsky_lexicon::app::bsky::graph::follow::Follow;
+use rsky_lexicon::app::bsky::graph::list::List;
+use rsky_lexicon::app::bsky::graph::listitem::ListItem;
+use rsky_syntax::aturi::AtUri;
+use rsky_syntax::{Did, Nsid, RecordKey};
 use serde::Deserialize;
 
 #[derive(Debug, Deserialize)]
@@ -60,6 +68,16 @@ pub enum Lexicon {
     AppBskyFeedLike(Like),
     #[serde(rename(deserialize = "app.bsky.graph.follow"))]
     AppBskyFeedFollow(Follow),
+    #[serde(rename(deserialize = "app.bsky.graph.block"))]
+    AppBskyGraphBlock(Block),
+    #[serde(rename(deserialize = "app.bsky.graph.list"))]
+    AppBskyGraphList(Box<List>),
+    #[serde(rename(deserialize = "app.bsky.graph.listitem"))]
+    AppBskyGraphListItem(ListItem),
+    #[serde(rename(deserialize = "app.bsky.feed.threadgate"))]
+    AppBskyFeedThreadgate(Threadgate),
+    #[serde(rename(deserialize = "app.bsky.feed.postgate"))]
+    AppBskyFeedPostgate(Postgate),
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -71,7 +89,9 @@ pub struct LikeSubject {
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 pub struct JetstreamRepoIdentity {
     pub did: String,
-    pub handle: String,
+    /// Omitted when the DID's handle no longer verifies
+    #[serde(default)]
+    pub handle: Option<String>,
     pub seq: i64,
     pub time: DateTime<Utc>,
 }
@@ -110,9 +130,36 @@ pub fn read(data: &str) -> Result<JetstreamRepoMessage> {
     Ok(body)
 }
 
+/// Whether a commit's record hashes to the `cid` it claims. Checked against the raw JSON, since
+/// re-serializing the lexicon structs would drop fields they don't model. Messages without a
+/// record, such as deletes, identity and account events, pass trivially.
+pub fn verify_record_cid(data: &str) -> Result<bool> {
+    let data_json: serde_json::Value = serde_json::from_str(data)?;
+    match (
+        data_json.pointer("/commit/record"),
+        data_json
+            .pointer("/commit/cid")
+            .and_then(|cid| cid.as_str()),
+    ) {
+        (Some(record), Some(cid)) => dag_cbor::verify_record_cid(record, cid),
+        (Some(_), None) => bail!("Commit record has no cid"),
+        _ => Ok(true),
+    }
+}
+
+/// The URI of the record a commit touches, built from its validated DID, collection and rkey.
+pub fn record_uri(commit: &JetstreamRepoCommitMessage) -> Result<AtUri> {
+    Ok(AtUri::record(
+        commit.did.parse::<Did>()?,
+        commit.commit.collection.parse::<Nsid>()?,
+        commit.commit.rkey.parse::<RecordKey>()?,
+    ))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rsky_lexicon::app::bsky::feed::threadgate::ThreadgateRule;
     use rsky_lexicon::com::atproto::repo::StrongRef;
 
     #[test]
@@ -143,7 +190,76 @@ mod tests {
 
         match response {
             JetstreamRepoMessage::Commit(commit) => {
-                assert_eq!(commit, expected_response);
+                assert_eq!(*commit, expected_response);
+            }
+            JetstreamRepoMessage::Identity(_) => {
+                panic!()
+            }
+            JetstreamRepoMessage::Account(_) => {
+                panic!()
+            }
+        }
+    }
+
+    #[test]
+    fn test_verify_record_cid() {
+        let data = "{\"did\":\"did:plc:uhtptnlcrj4wrxfjfcanf34q\",\"time_us\":1731539977109649,\"kind\":\"commit\",\"commit\":{\"rev\":\"3lauicnwejh2f\",\"operation\":\"create\",\"collection\":\"app.bsky.feed.like\",\"rkey\":\"3lauicnw5op2f\",\"record\":{\"$type\":\"app.bsky.feed.like\",\"createdAt\":\"2024-11-13T23:19:36.449Z\",\"subject\":{\"cid\":\"bafyreigw5ufnkavdzcczl2dusa3bcnkckhi4tscp6qsrsmg76s3ckseney\",\"uri\":\"at://did:plc:6wthaiuqiys3y7eztkpsdam2/app.bsky.feed.post/3latjcehsho2n\"}},\"cid\":\"bafyreifsdaip3s5nm3hcz4fbgkxodnils75oi3rmqhipwtom34rxw4vwdi\"}}";
+        let tampered = data.replace("23:19:36.449Z", "23:19:36.450Z");
+        let delete = "{\"did\":\"did:plc:uhtptnlcrj4wrxfjfcanf34q\",\"time_us\":1731539977109649,\"kind\":\"commit\",\"commit\":{\"rev\":\"3lauicnwejh2f\",\"operation\":\"delete\",\"collection\":\"app.bsky.feed.like\",\"rkey\":\"3lauicnw5op2f\"}}";
+
+        assert!(verify_record_cid(data).unwrap());
+        assert!(!verify_record_cid(&tampered).unwrap());
+        assert!(verify_record_cid(delete).unwrap());
+    }
+
+    #[test]
+    fn test_read_commit_create_block() {
+        let data = "{\"did\":\"did:plc:uhtptnlcrj4wrxfjfcanf34q\",\"time_us\":1731539977109649,\"kind\":\"commit\",\"commit\":{\"rev\":\"3lauicnwejh2f\",\"operation\":\"create\",\"collection\":\"app.bsky.graph.block\",\"rkey\":\"3lauicnw5op2f\",\"record\":{\"$type\":\"app.bsky.graph.block\",\"createdAt\":\"2024-11-13T23:19:36.449Z\",\"subject\":\"did:plc:6wthaiuqiys3y7eztkpsdam2\"},\"cid\":\"bafyreifsdaip3s5nm3hcz4fbgkxodnils75oi3rmqhipwtom34rxw4vwdi\"}}";
+        let response = read(data).unwrap();
+        let expected_record = Lexicon::AppBskyGraphBlock(Block {
+            created_at: "2024-11-13T23:19:36.449Z".to_string(),
+            subject: "did:plc:6wthaiuqiys3y7eztkpsdam2".to_string(),
+        });
+
+        match response {
+            JetstreamRepoMessage::Commit(commit) => {
+                assert_eq!(commit.commit.collection, "app.bsky.graph.block");
+                assert_eq!(commit.commit.record, Some(expected_record));
+            }
+            JetstreamRepoMessage::Identity(_) => {
+                panic!()
+            }
+            JetstreamRepoMessage::Account(_) => {
+                panic!()
+            }
+        }
+    }
+
+    #[test]
+    fn test_read_commit_update_threadgate() {
+        let data = "{\"did\":\"did:plc:uhtptnlcrj4wrxfjfcanf34q\",\"time_us\":1731539977109649,\"kind\":\"commit\",\"commit\":{\"rev\":\"3lauicnwejh2f\",\"operation\":\"update\",\"collection\":\"app.bsky.feed.threadgate\",\"rkey\":\"3latjcehsho2n\",\"record\":{\"$type\":\"app.bsky.feed.threadgate\",\"allow\":[{\"$type\":\"app.bsky.feed.threadgate#mentionRule\"},{\"$type\":\"app.bsky.feed.threadgate#listRule\",\"list\":\"at://did:plc:uhtptnlcrj4wrxfjfcanf34q/app.bsky.graph.list/3l6ssqq2psc2a\"}],\"createdAt\":\"2024-11-13T23:19:36.449Z\",\"hiddenReplies\":[\"at://did:plc:6wthaiuqiys3y7eztkpsdam2/app.bsky.feed.post/3lauicnw5op2f\"],\"post\":\"at://did:plc:uhtptnlcrj4wrxfjfcanf34q/app.bsky.feed.post/3latjcehsho2n\"},\"cid\":\"bafyreifsdaip3s5nm3hcz4fbgkxodnils75oi3rmqhipwtom34rxw4vwdi\"}}";
+        let response = read(data).unwrap();
+        let expected_record = Lexicon::AppBskyFeedThreadgate(Threadgate {
+            post: "at://did:plc:uhtptnlcrj4wrxfjfcanf34q/app.bsky.feed.post/3latjcehsho2n"
+                .to_string(),
+            allow: Some(vec![
+                ThreadgateRule::MentionRule,
+                ThreadgateRule::ListRule {
+                    list: "at://did:plc:uhtptnlcrj4wrxfjfcanf34q/app.bsky.graph.list/3l6ssqq2psc2a"
+                        .to_string(),
+                },
+            ]),
+            created_at: "2024-11-13T23:19:36.449Z".to_string(),
+            hidden_replies: Some(vec![
+                "at://did:plc:6wthaiuqiys3y7eztkpsdam2/app.bsky.feed.post/3lauicnw5op2f"
+                    .to_string(),
+            ]),
+        });
+
+        match response {
+            JetstreamRepoMessage::Commit(commit) => {
+                assert_eq!(commit.commit.operation, "update");
+                assert_eq!(commit.commit.record, Some(expected_record));
             }
             JetstreamRepoMessage::Identity(_) => {
                 panic!()
@@ -174,7 +290,7 @@ mod tests {
 
         match response {
             JetstreamRepoMessage::Commit(commit) => {
-                assert_eq!(commit, expected_response);
+                assert_eq!(*commit, expected_response);
             }
             JetstreamRepoMessage::Identity(_) => {
                 panic!()
@@ -226,7 +342,7 @@ mod tests {
             kind: "identity".to_string(),
             identity: JetstreamRepoIdentity {
                 did: "did:plc:sh5zdynqtvfavtkv6estb73d".to_string(),
-                handle: "irlasajj.bsky.social".to_string(),
+                handle: Some("irlasajj.bsky.social".to_string()),
                 seq: 3478739942,
                 time: DateTime::parse_from_str("2024-11-14T22:23:49.147Z", "%+")
                     .unwrap()
@@ -246,4 +362,29 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_read_identity_without_handle() {
+        let data = "{\"did\":\"did:plc:sh5zdynqtvfavtkv6estb73d\",\"time_us\":1731623029695659,\"kind\":\"identity\",\"identity\":{\"did\":\"did:plc:sh5zdynqtvfavtkv6estb73d\",\"seq\":3478739942,\"time\":\"2024-11-14T22:23:49.147Z\"}}";
+        match read(data).unwrap() {
+            JetstreamRepoMessage::Identity(identity) => {
+                assert_eq!(identity.identity.handle, None);
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn test_record_uri() {
+        let data = "{\"did\":\"did:plc:uhtptnlcrj4wrxfjfcanf34q\",\"time_us\":1731539977109649,\"kind\":\"commit\",\"commit\":{\"rev\":\"3lauicnwejh2f\",\"operation\":\"delete\",\"collection\":\"app.bsky.feed.like\",\"rkey\":\"3lauicnw5op2f\"}}";
+        let JetstreamRepoMessage::Commit(mut commit) = read(data).unwrap() else {
+            panic!()
+        };
+        assert_eq!(
+            record_uri(&commit).unwrap().to_string(),
+            "at://did:plc:uhtptnlcrj4wrxfjfcanf34q/app.bsky.feed.like/3lauicnw5op2f"
+        );
+        commit.commit.rkey = "../3lauicnw5op2f".to_string();
+        assert!(record_uri(&commit).is_err());
+    }
 }
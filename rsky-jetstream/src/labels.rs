@@ -0,0 +1,255 @@
+use anyhow::{bail, Result};
+use futures::StreamExt as _;
+use rsky_lexicon::com::atproto::label::{
+    SubscribeLabels, SubscribeLabelsInfo, SubscribeLabelsLabels,
+};
+use serde::Deserialize;
+use std::future::Future;
+use tokio_tungstenite::tungstenite::protocol::Message;
+use url::Url;
+
+/// Header preceding every event stream frame. Error frames carry `op = -1` and no type.
+#[derive(Debug, Deserialize)]
+pub struct FrameHeader {
+    #[serde(rename(deserialize = "op"))]
+    pub operation: i8,
+    #[serde(rename(deserialize = "t"))]
+    pub type_: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ErrorFrame {
+    pub error: String,
+    pub message: Option<String>,
+}
+
+/// Decodes one binary `com.atproto.label.subscribeLabels` frame: a DAG-CBOR header followed by
+/// a DAG-CBOR body.
+pub fn read(data: &[u8]) -> Result<SubscribeLabels> {
+    let mut deserializer = serde_cbor::Deserializer::from_slice(data);
+    let header = FrameHeader::deserialize(&mut deserializer)?;
+
+    if header.operation == -1 {
+        let error = ErrorFrame::deserialize(&mut deserializer)?;
+        bail!(format!(
+            "Labeler returned error {:?}: {:?}",
+            error.error, error.message
+        ))
+    }
+
+    let body = match header.type_.as_deref() {
+        Some("#labels") => {
+            SubscribeLabels::Labels(SubscribeLabelsLabels::deserialize(&mut deserializer)?)
+        }
+        Some("#info") => {
+            SubscribeLabels::Info(SubscribeLabelsInfo::deserialize(&mut deserializer)?)
+        }
+        other => bail!(format!("Received unknown label frame type {:?}", other)),
+    };
+
+    Ok(body)
+}
+
+/// Builds the `subscribeLabels` URL for a labeler service endpoint, resuming after `cursor`.
+pub fn subscribe_url(endpoint: &str, cursor: Option<i64>) -> String {
+    let endpoint = endpoint.trim_end_matches('/');
+    match cursor {
+        Some(cursor) => {
+            format!("{endpoint}/xrpc/com.atproto.label.subscribeLabels?cursor={cursor}")
+        }
+        None => format!("{endpoint}/xrpc/com.atproto.label.subscribeLabels"),
+    }
+}
+
+/// Reads a labeler's stream until it closes, handing every batch of labels to `on_labels`.
+/// Frames that fail to decode are logged and skipped; an error from `on_labels` ends the stream.
+pub async fn subscribe<F, Fut>(url: &str, mut on_labels: F) -> Result<()>
+where
+    F: FnMut(SubscribeLabelsLabels) -> Fut,
+    Fut: Future<Output = Result<()>>,
+{
+    let (mut socket, _response) = tokio_tungstenite::connect_async(Url::parse(url)?).await?;
+    while let Some(message) = socket.next().await {
+        match message? {
+            Message::Binary(data) => match read(&data) {
+                Ok(SubscribeLabels::Labels(labels)) => on_labels(labels).await?,
+                Ok(SubscribeLabels::Info(info)) => {
+                    tracing::info!("Labeler info {:?}: {:?}", info.name, info.message)
+                }
+                Err(error) => tracing::error!("@LOG: Failed to read label frame: {error:?}"),
+            },
+            Message::Close(_) => break,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Utc};
+    use futures::SinkExt as _;
+    use rsky_lexicon::com::atproto::label::Label;
+    use serde::Serialize;
+    use tokio::net::TcpListener;
+
+    #[derive(Serialize)]
+    struct TestHeader {
+        op: i8,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        t: Option<&'static str>,
+    }
+
+    fn frame<T: Serialize>(header: TestHeader, body: &T) -> Vec<u8> {
+        let mut data = serde_cbor::to_vec(&header).unwrap();
+        data.extend(serde_cbor::to_vec(body).unwrap());
+        data
+    }
+
+    fn label(val: &str, neg: Option<bool>, exp: Option<&str>) -> Label {
+        Label {
+            ver: Some(1),
+            src: "did:plc:ar7c4by46qjdydhdevvrndac".to_string(),
+            uri: "at://did:plc:6wthaiuqiys3y7eztkpsdam2/app.bsky.feed.post/3latjcehsho2n"
+                .to_string(),
+            cid: None,
+            val: val.to_string(),
+            neg,
+            cts: "2024-11-13T23:19:36.449Z".parse::<DateTime<Utc>>().unwrap(),
+            exp: exp.map(|exp| exp.parse::<DateTime<Utc>>().unwrap()),
+            sig: Some(vec![1, 2, 3]),
+        }
+    }
+
+    #[test]
+    fn test_read_labels_frame() {
+        let body = SubscribeLabelsLabels {
+            seq: 42,
+            labels: vec![label("porn", None, Some("2024-11-20T00:00:00Z"))],
+        };
+        let data = frame(
+            TestHeader {
+                op: 1,
+                t: Some("#labels"),
+            },
+            &body,
+        );
+
+        assert_eq!(read(&data).unwrap(), SubscribeLabels::Labels(body));
+    }
+
+    #[test]
+    fn test_read_error_frame() {
+        #[derive(Serialize)]
+        struct TestError {
+            error: &'static str,
+            message: &'static str,
+        }
+        let data = frame(
+            TestHeader { op: -1, t: None },
+            &TestError {
+                error: "FutureCursor",
+                message: "Cursor in the future.",
+            },
+        );
+
+        assert!(read(&data).is_err());
+    }
+
+    /// Serves `frames` over one websocket connection, then closes it, returning the URL to
+    /// subscribe to.
+    async fn serve_labeler(frames: Vec<Vec<u8>>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+            for data in frames {
+                // The client may already have gone away
+                if socket.send(Message::Binary(data)).await.is_err() {
+                    return;
+                }
+            }
+            let _ = socket.close(None).await;
+        });
+        subscribe_url(format!("ws://{addr}/").as_str(), Some(0))
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_local_labeler() {
+        let first = SubscribeLabelsLabels {
+            seq: 1,
+            labels: vec![label("spam", None, None)],
+        };
+        let second = SubscribeLabelsLabels {
+            seq: 2,
+            labels: vec![label("spam", Some(true), None)],
+        };
+        let frames = vec![
+            frame(
+                TestHeader {
+                    op: 1,
+                    t: Some("#info"),
+                },
+                &SubscribeLabelsInfo {
+                    name: "OutdatedCursor".to_string(),
+                    message: None,
+                },
+            ),
+            frame(
+                TestHeader {
+                    op: 1,
+                    t: Some("#labels"),
+                },
+                &first,
+            ),
+            frame(
+                TestHeader {
+                    op: 1,
+                    t: Some("#labels"),
+                },
+                &second,
+            ),
+        ];
+
+        let url = serve_labeler(frames).await;
+
+        let mut received = Vec::new();
+        subscribe(url.as_str(), |labels| {
+            received.push(labels);
+            async { Ok(()) }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(received, vec![first, second]);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_stops_when_labels_fail() {
+        let labels = |seq| SubscribeLabelsLabels {
+            seq,
+            labels: vec![label("spam", None, None)],
+        };
+        let header = || TestHeader {
+            op: 1,
+            t: Some("#labels"),
+        };
+        let url = serve_labeler(vec![
+            frame(header(), &labels(1)),
+            frame(header(), &labels(2)),
+        ])
+        .await;
+
+        let mut received = Vec::new();
+        let result = subscribe(url.as_str(), |labels| {
+            received.push(labels.seq);
+            async { bail!("feedgen unavailable") }
+        })
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(received, vec![1]);
+    }
+}
@@ -5,4 +5,5 @@ extern crate serde;
 extern crate serde_json;
 
 pub mod jetstream;
+pub mod labels;
 pub mod models;
@@ -4,13 +4,19 @@ use chrono::DateTime;
 use dotenvy::dotenv;
 use futures::StreamExt as _;
 use rsky_jetstream::jetstream::{
-    read, JetstreamRepoAccount, JetstreamRepoAccountMessage, JetstreamRepoCommit,
-    JetstreamRepoCommitMessage, JetstreamRepoIdentity, JetstreamRepoIdentityMessage,
-    JetstreamRepoMessage, Lexicon,
+    read, record_uri, verify_record_cid, JetstreamRepoAccount, JetstreamRepoAccountMessage,
+    JetstreamRepoCommit, JetstreamRepoCommitMessage, JetstreamRepoIdentity,
+    JetstreamRepoIdentityMessage, JetstreamRepoMessage, Lexicon,
 };
 use rsky_lexicon::app::bsky::feed::like::Like;
+use rsky_lexicon::app::bsky::feed::postgate::Postgate;
+use rsky_lexicon::app::bsky::feed::threadgate::Threadgate;
 use rsky_lexicon::app::bsky::feed::{Post, Repost};
+use rsky_lexicon::app::bsky::graph::block::Block;
 use rsky_lexicon::app::bsky::graph::follow::Follow;
+use rsky_lexicon::app::bsky::graph::list::List;
+use rsky_lexicon::app::bsky::graph::listitem::ListItem;
+use rsky_lexicon::com::atproto::label::Label;
 use rsky_lexicon::com::atproto::sync::SubscribeRepos;
 use serde::Deserialize;
 use std::env;
@@ -60,6 +66,26 @@ async fn queue_create<T: serde::ser::Serialize>(
     Ok(())
 }
 
+async fn queue_identities(
+    url: String,
+    identities: Vec<rsky_jetstream::models::IdentityOp>,
+    client: &reqwest::Client,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let token = env::var("RSKY_API_KEY").map_err(|_| {
+        "Pass a valid preshared token via `RSKY_API_KEY` environment variable.".to_string()
+    })?;
+    client
+        .put(url)
+        .json(&identities)
+        .header("X-RSKY-KEY", token)
+        .header("Connection", "Keep-Alive")
+        .header("Keep-Alive", "timeout=5, max=1000")
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
 async fn update_cursor(
     url: String,
     service: String,
@@ -80,6 +106,101 @@ async fn update_cursor(
     Ok(())
 }
 
+async fn queue_labels(
+    url: String,
+    labels: Vec<Label>,
+    client: &reqwest::Client,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let token = env::var("RSKY_API_KEY").map_err(|_| {
+        "Pass a valid preshared token via `RSKY_API_KEY` environment variable.".to_string()
+    })?;
+    client
+        .put(url)
+        .json(&labels)
+        .header("X-RSKY-KEY", token)
+        .header("Connection", "Keep-Alive")
+        .header("Keep-Alive", "timeout=5, max=1000")
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+struct StoredCursor {
+    cursor: i64,
+}
+
+async fn get_cursor(
+    url: String,
+    service: String,
+    client: &reqwest::Client,
+) -> Result<Option<i64>, Box<dyn std::error::Error>> {
+    let token = env::var("RSKY_API_KEY").map_err(|_| {
+        "Pass a valid preshared token via `RSKY_API_KEY` environment variable.".to_string()
+    })?;
+    let query = vec![("service", service)];
+    let resp = client
+        .get(url)
+        .query(&query)
+        .header("X-RSKY-KEY", token)
+        .header("Accept", "application/json")
+        .send()
+        .await?;
+    if resp.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    let stored: StoredCursor = resp.error_for_status()?.json().await?;
+    Ok(Some(stored.cursor))
+}
+
+/// Follows one labeler's `subscribeLabels` stream forever, forwarding labels to the feedgen and
+/// resuming from the last stored sequence after a disconnect.
+async fn subscribe_labeler(endpoint: String, client: reqwest::Client) {
+    let default_queue_path =
+        env::var("FEEDGEN_QUEUE_ENDPOINT").unwrap_or("http://127.0.0.1:8000".into());
+    let cursor_endpoint = format!("{}/cursor", default_queue_path);
+    let label_endpoint = format!("{}/queue/labels/create", default_queue_path);
+
+    loop {
+        let cursor = match get_cursor(cursor_endpoint.clone(), endpoint.clone(), &client).await {
+            Ok(cursor) => cursor,
+            Err(error) => {
+                tracing::error!("@LOG: Failed to fetch cursor for {endpoint:?}: {error:?}");
+                None
+            }
+        };
+        let url = rsky_jetstream::labels::subscribe_url(endpoint.as_str(), cursor);
+        tracing::info!("Connecting to labeler {url:?}.");
+        let result = rsky_jetstream::labels::subscribe(url.as_str(), |labels| {
+            let client = client.clone();
+            let endpoint = endpoint.clone();
+            let cursor_endpoint = cursor_endpoint.clone();
+            let label_endpoint = label_endpoint.clone();
+            async move {
+                // Unqueued labels end the subscription, so it resumes from the stored cursor
+                // and tries them again rather than moving past them
+                if !labels.labels.is_empty() {
+                    queue_labels(label_endpoint, labels.labels, &client)
+                        .await
+                        .map_err(|error| anyhow::anyhow!("Labels failed to queue: {error}"))?;
+                }
+                if let Err(error) =
+                    update_cursor(cursor_endpoint, endpoint, &labels.seq, &client).await
+                {
+                    tracing::error!("@LOG: Failed to update cursor: {error:?}");
+                }
+                Ok(())
+            }
+        })
+        .await;
+        if let Err(error) = result {
+            tracing::error!("Error reading labeler {endpoint:?}. Waiting to reconnect: {error:?}");
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+}
+
 #[tracing::instrument]
 async fn process(message: String, client: &reqwest::Client) {
     let default_queue_path =
@@ -87,22 +208,59 @@ async fn process(message: String, client: &reqwest::Client) {
     let default_subscriber_path = env::var("FEEDGEN_SUBSCRIPTION_ENDPOINT")
         .unwrap_or("wss://jetstream1.us-west.bsky.network".into());
 
+    // Jetstream doesn't carry proofs, but a record that doesn't match its own cid was corrupted
+    // or forged somewhere between the PDS and us
+    if env::var("VERIFY_RECORD_CIDS").is_ok_and(|verify| verify == "true") {
+        match verify_record_cid(&message) {
+            Ok(true) => (),
+            Ok(false) => {
+                tracing::warn!("@LOG: Skipping record that doesn't match its cid: {message}");
+                return;
+            }
+            Err(error) => {
+                tracing::warn!("@LOG: Skipping record with unverifiable cid: {error:?}");
+                return;
+            }
+        }
+    }
+
     match read(&message) {
         Ok(body) => {
             let mut posts_to_delete = Vec::new();
             let mut posts_to_create = Vec::new();
+            let mut posts_to_update = Vec::new();
             let mut reposts_to_delete = Vec::new();
             let mut reposts_to_create = Vec::new();
             let mut likes_to_delete = Vec::new();
             let mut likes_to_create = Vec::new();
             let mut follows_to_delete = Vec::new();
             let mut follows_to_create = Vec::new();
+            let mut blocks_to_delete = Vec::new();
+            let mut blocks_to_create = Vec::new();
+            let mut lists_to_delete = Vec::new();
+            let mut lists_to_create = Vec::new();
+            let mut listitems_to_delete = Vec::new();
+            let mut listitems_to_create = Vec::new();
+            let mut threadgates_to_delete = Vec::new();
+            let mut threadgates_to_upsert = Vec::new();
+            let mut postgates_to_delete = Vec::new();
+            let mut postgates_to_upsert = Vec::new();
+            let mut identities_to_update = Vec::new();
 
             match body {
                 JetstreamRepoMessage::Commit(commit) => {
                     if commit.kind.is_empty() {
                         tracing::info!("Operations empty.");
                     }
+                    let record_uri = match record_uri(&commit) {
+                        Ok(uri) => uri.to_string(),
+                        Err(error) => {
+                            tracing::warn!(
+                                "@LOG: Skipping commit with invalid record uri: {error:?}"
+                            );
+                            return;
+                        }
+                    };
                     // update stored cursor every 20 events or so
                     if commit.time_us.rem_euclid(20) == 0 {
                         let cursor_endpoint = format!("{}/cursor", default_queue_path);
@@ -122,16 +280,54 @@ async fn process(message: String, client: &reqwest::Client) {
                     }
 
                     match commit.commit.operation.as_str() {
-                        "update" => {}
+                        "update" => {
+                            // Gates are edited in place (e.g. hiding another reply), so their
+                            // updates are sent on to be upserted. Post edits (e.g. adding alt
+                            // text) go to their own queue; other updates are ignored.
+                            let cid = commit.commit.cid;
+                            match commit.commit.record {
+                                Some(Lexicon::AppBskyFeedPost(r)) => {
+                                    let post: Box<Post> = r;
+                                    let uri = record_uri.clone();
+                                    let update = rsky_jetstream::models::CreateOp {
+                                        uri: uri.to_owned(),
+                                        cid: cid.unwrap().to_string(),
+                                        author: commit.did.to_owned(),
+                                        record: post,
+                                    };
+                                    posts_to_update.push(update);
+                                }
+                                Some(Lexicon::AppBskyFeedThreadgate(r)) => {
+                                    let threadgate: Threadgate = r;
+                                    let uri = record_uri.clone();
+                                    let create = rsky_jetstream::models::CreateOp {
+                                        uri: uri.to_owned(),
+                                        cid: cid.unwrap().to_string(),
+                                        author: commit.did.to_owned(),
+                                        record: threadgate,
+                                    };
+                                    threadgates_to_upsert.push(create);
+                                }
+                                Some(Lexicon::AppBskyFeedPostgate(r)) => {
+                                    let postgate: Postgate = r;
+                                    let uri = record_uri.clone();
+                                    let create = rsky_jetstream::models::CreateOp {
+                                        uri: uri.to_owned(),
+                                        cid: cid.unwrap().to_string(),
+                                        author: commit.did.to_owned(),
+                                        record: postgate,
+                                    };
+                                    postgates_to_upsert.push(create);
+                                }
+                                _ => {}
+                            }
+                        }
                         "create" => {
                             let cid = commit.commit.cid;
                             match commit.commit.record {
                                 Some(Lexicon::AppBskyFeedPost(r)) => {
                                     let post: Box<Post> = r;
-                                    let uri = String::from("at://")
-                                        + commit.did.as_str()
-                                        + "/app.bsky.feed.post/"
-                                        + commit.commit.rkey.as_str();
+                                    let uri = record_uri.clone();
                                     let create = rsky_jetstream::models::CreateOp {
                                         uri: uri.to_owned(),
                                         cid: cid.unwrap().to_string(),
@@ -142,10 +338,7 @@ async fn process(message: String, client: &reqwest::Client) {
                                 }
                                 Some(Lexicon::AppBskyFeedRepost(r)) => {
                                     let repost: Repost = r;
-                                    let uri = String::from("at://")
-                                        + commit.did.as_str()
-                                        + "/app.bsky.feed.repost/"
-                                        + commit.commit.rkey.as_str();
+                                    let uri = record_uri.clone();
                                     let create = rsky_jetstream::models::CreateOp {
                                         uri: uri.to_owned(),
                                         cid: cid.unwrap().to_string(),
@@ -156,10 +349,7 @@ async fn process(message: String, client: &reqwest::Client) {
                                 }
                                 Some(Lexicon::AppBskyFeedLike(r)) => {
                                     let like: Like = r;
-                                    let uri = String::from("at://")
-                                        + commit.did.as_str()
-                                        + "/app.bsky.feed.like/"
-                                        + commit.commit.rkey.as_str();
+                                    let uri = record_uri.clone();
                                     let create = rsky_jetstream::models::CreateOp {
                                         uri: uri.to_owned(),
                                         cid: cid.unwrap().to_string(),
@@ -170,10 +360,7 @@ async fn process(message: String, client: &reqwest::Client) {
                                 }
                                 Some(Lexicon::AppBskyFeedFollow(r)) => {
                                     let follow: Follow = r;
-                                    let uri = String::from("at://")
-                                        + commit.did.as_str()
-                                        + "/app.bsky.graph.follow/"
-                                        + commit.commit.rkey.as_str();
+                                    let uri = record_uri.clone();
                                     let create = rsky_jetstream::models::CreateOp {
                                         uri: uri.to_owned(),
                                         cid: cid.unwrap().to_string(),
@@ -182,45 +369,116 @@ async fn process(message: String, client: &reqwest::Client) {
                                     };
                                     follows_to_create.push(create);
                                 }
+                                Some(Lexicon::AppBskyGraphBlock(r)) => {
+                                    let block: Block = r;
+                                    let uri = record_uri.clone();
+                                    let create = rsky_jetstream::models::CreateOp {
+                                        uri: uri.to_owned(),
+                                        cid: cid.unwrap().to_string(),
+                                        author: commit.did.to_owned(),
+                                        record: block,
+                                    };
+                                    blocks_to_create.push(create);
+                                }
+                                Some(Lexicon::AppBskyGraphList(r)) => {
+                                    let list: Box<List> = r;
+                                    let uri = record_uri.clone();
+                                    let create = rsky_jetstream::models::CreateOp {
+                                        uri: uri.to_owned(),
+                                        cid: cid.unwrap().to_string(),
+                                        author: commit.did.to_owned(),
+                                        record: list,
+                                    };
+                                    lists_to_create.push(create);
+                                }
+                                Some(Lexicon::AppBskyGraphListItem(r)) => {
+                                    let listitem: ListItem = r;
+                                    let uri = record_uri.clone();
+                                    let create = rsky_jetstream::models::CreateOp {
+                                        uri: uri.to_owned(),
+                                        cid: cid.unwrap().to_string(),
+                                        author: commit.did.to_owned(),
+                                        record: listitem,
+                                    };
+                                    listitems_to_create.push(create);
+                                }
+                                Some(Lexicon::AppBskyFeedThreadgate(r)) => {
+                                    let threadgate: Threadgate = r;
+                                    let uri = record_uri.clone();
+                                    let create = rsky_jetstream::models::CreateOp {
+                                        uri: uri.to_owned(),
+                                        cid: cid.unwrap().to_string(),
+                                        author: commit.did.to_owned(),
+                                        record: threadgate,
+                                    };
+                                    threadgates_to_upsert.push(create);
+                                }
+                                Some(Lexicon::AppBskyFeedPostgate(r)) => {
+                                    let postgate: Postgate = r;
+                                    let uri = record_uri.clone();
+                                    let create = rsky_jetstream::models::CreateOp {
+                                        uri: uri.to_owned(),
+                                        cid: cid.unwrap().to_string(),
+                                        author: commit.did.to_owned(),
+                                        record: postgate,
+                                    };
+                                    postgates_to_upsert.push(create);
+                                }
                                 _ => {}
                             }
                         }
                         "delete" => {
                             let collection = commit.commit.collection;
                             if collection == "app.bsky.feed.post" {
-                                let uri = String::from("at://")
-                                    + commit.did.as_str()
-                                    + "/app.bsky.feed.post/"
-                                    + commit.commit.rkey.as_str();
+                                let uri = record_uri.clone();
                                 let del = rsky_jetstream::models::DeleteOp { uri };
                                 posts_to_delete.push(del);
                             } else if collection == "app.bsky.feed.repost" {
-                                let uri = String::from("at://")
-                                    + commit.did.as_str()
-                                    + "/app.bsky.feed.repost/"
-                                    + commit.commit.rkey.as_str();
+                                let uri = record_uri.clone();
                                 let del = rsky_jetstream::models::DeleteOp { uri };
                                 reposts_to_delete.push(del);
                             } else if collection == "app.bsky.feed.like" {
-                                let uri = String::from("at://")
-                                    + commit.did.as_str()
-                                    + "/app.bsky.feed.like/"
-                                    + commit.commit.rkey.as_str();
+                                let uri = record_uri.clone();
                                 let del = rsky_jetstream::models::DeleteOp { uri };
                                 likes_to_delete.push(del);
                             } else if collection == "app.bsky.graph.follow" {
-                                let uri = String::from("at://")
-                                    + commit.did.as_str()
-                                    + "/app.bsky.graph.follow/"
-                                    + commit.commit.rkey.as_str();
+                                let uri = record_uri.clone();
                                 let del = rsky_jetstream::models::DeleteOp { uri };
                                 follows_to_delete.push(del);
+                            } else if collection == "app.bsky.graph.block" {
+                                let uri = record_uri.clone();
+                                let del = rsky_jetstream::models::DeleteOp { uri };
+                                blocks_to_delete.push(del);
+                            } else if collection == "app.bsky.graph.list" {
+                                let uri = record_uri.clone();
+                                let del = rsky_jetstream::models::DeleteOp { uri };
+                                lists_to_delete.push(del);
+                            } else if collection == "app.bsky.graph.listitem" {
+                                let uri = record_uri.clone();
+                                let del = rsky_jetstream::models::DeleteOp { uri };
+                                listitems_to_delete.push(del);
+                            } else if collection == "app.bsky.feed.threadgate" {
+                                let uri = record_uri.clone();
+                                let del = rsky_jetstream::models::DeleteOp { uri };
+                                threadgates_to_delete.push(del);
+                            } else if collection == "app.bsky.feed.postgate" {
+                                let uri = record_uri.clone();
+                                let del = rsky_jetstream::models::DeleteOp { uri };
+                                postgates_to_delete.push(del);
                             }
                         }
                         _ => {}
                     }
                 }
-                JetstreamRepoMessage::Identity(_) => {}
+                JetstreamRepoMessage::Identity(message) => {
+                    // Sent for key rotations as well as handle changes, so the feedgen drops its
+                    // cached DID document either way
+                    identities_to_update.push(rsky_jetstream::models::IdentityOp {
+                        did: message.identity.did,
+                        handle: message.identity.handle,
+                        seq: message.identity.seq,
+                    });
+                }
                 JetstreamRepoMessage::Account(_) => {}
             }
 
@@ -232,6 +490,14 @@ async fn process(message: String, client: &reqwest::Client) {
                     Err(error) => tracing::error!("Records failed to queue: {error:?}"),
                 };
             }
+            if !posts_to_update.is_empty() {
+                let queue_endpoint = format!("{}/queue/{}/update", default_queue_path, "posts");
+                let resp = queue_create(queue_endpoint, posts_to_update, client).await;
+                match resp {
+                    Ok(()) => (),
+                    Err(error) => tracing::error!("Records failed to queue: {error:?}"),
+                };
+            }
             if !posts_to_delete.is_empty() {
                 let queue_endpoint = format!("{}/queue/{}/delete", default_queue_path, "posts");
                 let resp = queue_delete(queue_endpoint, posts_to_delete, client).await;
@@ -288,6 +554,96 @@ async fn process(message: String, client: &reqwest::Client) {
                     Err(error) => tracing::error!("Records failed to queue: {error:?}"),
                 };
             }
+            if !blocks_to_create.is_empty() {
+                let queue_endpoint = format!("{}/queue/{}/create", default_queue_path, "blocks");
+                let resp = queue_create(queue_endpoint, blocks_to_create, client).await;
+                match resp {
+                    Ok(()) => (),
+                    Err(error) => tracing::error!("Records failed to queue: {error:?}"),
+                };
+            }
+            if !blocks_to_delete.is_empty() {
+                let queue_endpoint = format!("{}/queue/{}/delete", default_queue_path, "blocks");
+                let resp = queue_delete(queue_endpoint, blocks_to_delete, client).await;
+                match resp {
+                    Ok(()) => (),
+                    Err(error) => tracing::error!("Records failed to queue: {error:?}"),
+                };
+            }
+            if !lists_to_create.is_empty() {
+                let queue_endpoint = format!("{}/queue/{}/create", default_queue_path, "lists");
+                let resp = queue_create(queue_endpoint, lists_to_create, client).await;
+                match resp {
+                    Ok(()) => (),
+                    Err(error) => tracing::error!("Records failed to queue: {error:?}"),
+                };
+            }
+            if !lists_to_delete.is_empty() {
+                let queue_endpoint = format!("{}/queue/{}/delete", default_queue_path, "lists");
+                let resp = queue_delete(queue_endpoint, lists_to_delete, client).await;
+                match resp {
+                    Ok(()) => (),
+                    Err(error) => tracing::error!("Records failed to queue: {error:?}"),
+                };
+            }
+            if !listitems_to_create.is_empty() {
+                let queue_endpoint = format!("{}/queue/{}/create", default_queue_path, "listitems");
+                let resp = queue_create(queue_endpoint, listitems_to_create, client).await;
+                match resp {
+                    Ok(()) => (),
+                    Err(error) => tracing::error!("Records failed to queue: {error:?}"),
+                };
+            }
+            if !listitems_to_delete.is_empty() {
+                let queue_endpoint = format!("{}/queue/{}/delete", default_queue_path, "listitems");
+                let resp = queue_delete(queue_endpoint, listitems_to_delete, client).await;
+                match resp {
+                    Ok(()) => (),
+                    Err(error) => tracing::error!("Records failed to queue: {error:?}"),
+                };
+            }
+            if !threadgates_to_upsert.is_empty() {
+                let queue_endpoint =
+                    format!("{}/queue/{}/create", default_queue_path, "threadgates");
+                let resp = queue_create(queue_endpoint, threadgates_to_upsert, client).await;
+                match resp {
+                    Ok(()) => (),
+                    Err(error) => tracing::error!("Records failed to queue: {error:?}"),
+                };
+            }
+            if !threadgates_to_delete.is_empty() {
+                let queue_endpoint =
+                    format!("{}/queue/{}/delete", default_queue_path, "threadgates");
+                let resp = queue_delete(queue_endpoint, threadgates_to_delete, client).await;
+                match resp {
+                    Ok(()) => (),
+                    Err(error) => tracing::error!("Records failed to queue: {error:?}"),
+                };
+            }
+            if !postgates_to_upsert.is_empty() {
+                let queue_endpoint = format!("{}/queue/{}/create", default_queue_path, "postgates");
+                let resp = queue_create(queue_endpoint, postgates_to_upsert, client).await;
+                match resp {
+                    Ok(()) => (),
+                    Err(error) => tracing::error!("Records failed to queue: {error:?}"),
+                };
+            }
+            if !postgates_to_delete.is_empty() {
+                let queue_endpoint = format!("{}/queue/{}/delete", default_queue_path, "postgates");
+                let resp = queue_delete(queue_endpoint, postgates_to_delete, client).await;
+                match resp {
+                    Ok(()) => (),
+                    Err(error) => tracing::error!("Records failed to queue: {error:?}"),
+                };
+            }
+            if !identities_to_update.is_empty() {
+                let queue_endpoint = format!("{}/queue/identities/update", default_queue_path);
+                let resp = queue_identities(queue_endpoint, identities_to_update, client).await;
+                match resp {
+                    Ok(()) => (),
+                    Err(error) => tracing::error!("Identities failed to queue: {error:?}"),
+                };
+            }
         }
         Err(error) => tracing::error!(
             "@LOG: Error unwrapping message and header: {}",
@@ -302,10 +658,22 @@ async fn main() {
     let default_subscriber_path = env::var("FEEDGEN_SUBSCRIPTION_ENDPOINT")
         .unwrap_or("wss://jetstream1.us-west.bsky.network".into());
     let wanted_collections = env::var("WANTED_COLLECTIONS")
-        .unwrap_or("wantedCollections=app.bsky.feed.post&wantedCollections=app.bsky.feed.repost&wantedCollections=app.bsky.graph.follow".into());
+        .unwrap_or("wantedCollections=app.bsky.feed.post&wantedCollections=app.bsky.feed.repost&wantedCollections=app.bsky.graph.follow&wantedCollections=app.bsky.graph.block&wantedCollections=app.bsky.graph.list&wantedCollections=app.bsky.graph.listitem&wantedCollections=app.bsky.feed.threadgate&wantedCollections=app.bsky.feed.postgate".into());
     let client = reqwest::Client::new();
     let subscriber = tracing_subscriber::FmtSubscriber::new();
     tracing::subscriber::set_global_default(subscriber).unwrap();
+    // Comma separated labeler service endpoints, e.g. `wss://mod.bsky.app`
+    if let Ok(labeler_endpoints) = env::var("LABELER_ENDPOINTS") {
+        for endpoint in labeler_endpoints
+            .split(',')
+            .filter(|e| !e.trim().is_empty())
+        {
+            tokio::spawn(subscribe_labeler(
+                endpoint.trim().to_string(),
+                client.clone(),
+            ));
+        }
+    }
     loop {
         match tokio_tungstenite::connect_async(
             Url::parse(
@@ -342,6 +710,49 @@ async fn main() {
 mod tests {
     use super::*;
 
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
     #[test]
     fn test_read_commit_create_like() {}
+
+    /// Serves one request with `status`, returning the URL to send it to.
+    async fn respond_once(status: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!(
+            "http://{}/queue/identities/update",
+            listener.local_addr().unwrap()
+        );
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0; 4096];
+            let _ = socket.read(&mut request).await;
+            let response =
+                format!("HTTP/1.1 {status}\r\ncontent-length: 0\r\nconnection: close\r\n\r\n");
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_queue_identities_checks_status() {
+        env::set_var("RSKY_API_KEY", "test");
+        let client = reqwest::Client::new();
+
+        let url = respond_once("200 OK").await;
+        assert!(queue_identities(url, vec![], &client).await.is_ok());
+        let url = respond_once("500 Internal Server Error").await;
+        assert!(queue_identities(url, vec![], &client).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_queue_labels_checks_status() {
+        env::set_var("RSKY_API_KEY", "test");
+        let client = reqwest::Client::new();
+
+        let url = respond_once("200 OK").await;
+        assert!(queue_labels(url, vec![], &client).await.is_ok());
+        let url = respond_once("503 Service Unavailable").await;
+        assert!(queue_labels(url, vec![], &client).await.is_err());
+    }
 }
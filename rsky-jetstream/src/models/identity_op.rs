@@ -0,0 +1,9 @@
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct IdentityOp {
+    #[serde(rename = "did")]
+    pub did: String,
+    #[serde(rename = "handle")]
+    pub handle: Option<String>,
+    #[serde(rename = "seq")]
+    pub seq: i64,
+}
@@ -2,3 +2,5 @@ pub mod create_op;
 pub use self::create_op::CreateOp;
 pub mod delete_op;
 pub use self::delete_op::DeleteOp;
+pub mod identity_op;
+pub use self::identity_op::IdentityOp;
@@ -0,0 +1,955 @@
+use crate::lexicon::{BodySchema, Def, Field, LexiconDoc, Object, RefField, UnionField};
+use anyhow::{bail, Result};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Hand-written rsky-lexicon types that generated code may refer to, keyed by lexicon ref.
+const EXTERNAL_TYPES: &[(&str, &str)] = &[
+    (
+        "app.bsky.actor.defs#profileView",
+        "crate::app::bsky::actor::ProfileView",
+    ),
+    (
+        "app.bsky.feed.defs#interaction",
+        "crate::app::bsky::feed::Interaction",
+    ),
+    (
+        "app.bsky.feed.defs#skeletonFeedPost",
+        "crate::app::bsky::feed::SkeletonFeedPost",
+    ),
+    (
+        "app.bsky.graph.defs#listPurpose",
+        "crate::app::bsky::graph::ListPurpose",
+    ),
+    (
+        "app.bsky.richtext.facet",
+        "crate::app::bsky::richtext::Facet",
+    ),
+    (
+        "com.atproto.label.defs#selfLabels",
+        "crate::com::atproto::label::SelfLabels",
+    ),
+    (
+        "com.atproto.repo.strongRef",
+        "crate::com::atproto::repo::StrongRef",
+    ),
+];
+
+/// Rust names that differ from the default derived from the lexicon, to avoid clashes or keep
+/// names the crate already exported. Keyed by ref, or by `ref.property` for unions.
+const RENAMES: &[(&str, &str)] = &[
+    ("app.bsky.embed.external#external", "ExternalObject"),
+    (
+        "app.bsky.feed.postgate.embeddingRules",
+        "PostgateEmbeddingRule",
+    ),
+    ("app.bsky.feed.threadgate.allow", "ThreadgateRule"),
+    ("app.bsky.graph.listitem", "ListItem"),
+];
+
+const BLOB: &str = "crate::com::atproto::repo::Blob";
+const CID_LINK: &str = "crate::com::atproto::repo::Link";
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "box", "const", "crate", "dyn", "enum", "fn", "impl", "let", "loop",
+    "match", "mod", "move", "override", "priv", "ref", "self", "static", "struct", "super",
+    "trait", "type", "use", "where", "yield",
+];
+
+/// The catch-all variant of open union `ty`, keeping types it doesn't know as they came.
+fn unknown_variant(ty: &str) -> String {
+    format!(
+        "    /// A `$type` this union doesn't know, kept as-is so it round-trips unchanged. Known types
+    /// that don't parse are errors rather than landing here.
+    #[serde(untagged)]
+    Unknown {{
+        #[serde(
+            rename = \"$type\",
+            deserialize_with = \"crate::open_union::unknown_type::<{ty}, _>\"
+        )]
+        r#type: String,
+        #[serde(flatten)]
+        data: serde_json::Value,
+    }},
+"
+    )
+}
+
+const DERIVES: &str = "#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]";
+
+/// Generates the Rust module for each lexicon document in a set.
+pub struct Codegen<'a> {
+    docs: &'a BTreeMap<String, LexiconDoc>,
+}
+
+impl<'a> Codegen<'a> {
+    pub fn new(docs: &'a BTreeMap<String, LexiconDoc>) -> Self {
+        Self { docs }
+    }
+
+    /// Unformatted source for the module generated from `nsid`. `samples` holds the test vectors
+    /// for the document, keyed by def name, which every generated struct must have.
+    pub fn generate(&self, nsid: &str, samples: &Value) -> Result<String> {
+        let Some(doc) = self.docs.get(nsid) else {
+            bail!("no lexicon document for {nsid}");
+        };
+        let mut file = File {
+            codegen: self,
+            doc,
+            imports: BTreeSet::new(),
+            items: Vec::new(),
+            tests: Vec::new(),
+        };
+        file.generate()?;
+
+        let mut tests = Vec::new();
+        for (key, name) in &file.tests {
+            match samples.get(key).and_then(Value::as_array) {
+                Some(samples) if !samples.is_empty() => tests.push(format!(
+                    "crate::assert_round_trips::<{name}>(&samples[\"{key}\"]);"
+                )),
+                _ => bail!("{nsid} has no samples for {key:?} ({name})"),
+            }
+        }
+
+        let mut out = format!(
+            "// Generated by rsky-lexgen from lexicons/{}.json.\n\
+             // Do not edit by hand: update the lexicon and run `cargo run -p rsky-lexgen` instead.\n\n",
+            nsid.replace('.', "/")
+        );
+        let mut imports: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for import in &file.imports {
+            let (module, name) = import.rsplit_once("::").unwrap_or(("", import));
+            imports.entry(module).or_default().push(name);
+        }
+        for (module, names) in imports {
+            match names.as_slice() {
+                [name] => out.push_str(&format!("use {module}::{name};\n")),
+                names => out.push_str(&format!("use {module}::{{{}}};\n", names.join(", "))),
+            }
+        }
+        for item in &file.items {
+            out.push('\n');
+            out.push_str(item);
+        }
+        out.push_str(&format!(
+            "\n#[cfg(test)]\nmod tests {{\n    use super::*;\n\n    #[test]\n    \
+             fn round_trips_samples() {{\n        let samples = crate::test_vectors(include_str!(\
+             \"{}test-vectors/{}.json\"));\n{}\n    }}\n}}\n",
+            "../".repeat(nsid.split('.').count()),
+            nsid.replace('.', "/"),
+            tests.join("\n")
+        ));
+        Ok(out)
+    }
+
+    /// Whether a def is only ever reached through a union, so it becomes an enum variant rather
+    /// than a struct of its own.
+    fn is_inlined(&self, nsid: &str, name: &str) -> bool {
+        if !matches!(
+            self.docs.get(nsid).and_then(|doc| doc.defs.get(name)),
+            Some(Def::Object(_))
+        ) {
+            return false;
+        }
+        let mut via_union = false;
+        for doc in self.docs.values() {
+            for (r, union) in doc.refs() {
+                if split_ref(&doc.id, r) == (nsid, name) {
+                    if !union || doc.id != nsid {
+                        return false;
+                    }
+                    via_union = true;
+                }
+            }
+        }
+        via_union
+    }
+}
+
+struct File<'a> {
+    codegen: &'a Codegen<'a>,
+    doc: &'a LexiconDoc,
+    imports: BTreeSet<String>,
+    items: Vec<String>,
+    /// `(sample key, type name)` for every struct that gets a round-trip test.
+    tests: Vec<(String, String)>,
+}
+
+impl File<'_> {
+    fn generate(&mut self) -> Result<()> {
+        let nsid = self.doc.id.as_str();
+        let doc_description = self.doc.description.as_deref();
+        for (name, def) in &self.doc.defs {
+            match def {
+                Def::Record(record) => {
+                    let ty = type_name(nsid, name);
+                    let description = record.description.as_deref().or(doc_description);
+                    self.push_struct(&ty, description, Some(nsid), &record.record)?;
+                    self.tests.push((name.clone(), ty));
+                }
+                Def::Object(object) => {
+                    if self.codegen.is_inlined(nsid, name) {
+                        continue;
+                    }
+                    let ty = type_name(nsid, name);
+                    let description = match name.as_str() {
+                        "main" => object.description.as_deref().or(doc_description),
+                        _ => object.description.as_deref(),
+                    };
+                    let tag = (name == "view").then(|| format!("{nsid}#view"));
+                    self.push_struct(&ty, description, tag.as_deref(), object)?;
+                    self.tests.push((name.clone(), ty));
+                }
+                Def::Query(xrpc) | Def::Procedure(xrpc) => {
+                    let base = pascal_case(last_segment(nsid));
+                    self.items
+                        .push(format!("pub const NSID: &str = \"{nsid}\";\n"));
+                    let mut description = xrpc.description.as_deref().or(doc_description);
+                    let bodies = [
+                        ("params", xrpc.parameters.as_ref().map(BodyRef::Object)),
+                        ("input", body_ref(&xrpc.input)),
+                        ("output", body_ref(&xrpc.output)),
+                    ];
+                    for (key, body) in bodies {
+                        let Some(body) = body else { continue };
+                        let ty = format!("{base}{}", pascal_case(key));
+                        match body {
+                            BodyRef::Object(object) => {
+                                self.push_struct(&ty, description.take(), None, object)?;
+                                self.tests.push((key.to_string(), ty));
+                            }
+                            BodyRef::Ref(field) => {
+                                let target = self.resolve(&field.r#ref)?;
+                                self.items.push(format!(
+                                    "{}pub type {ty} = {target};\n",
+                                    doc_comment(description.take(), "")
+                                ));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn push_struct(
+        &mut self,
+        ty: &str,
+        description: Option<&str>,
+        tag: Option<&str>,
+        object: &Object,
+    ) -> Result<()> {
+        let mut unions = Vec::new();
+        let fields = self.fields(ty, object, false, &mut unions)?;
+        let mut item = doc_comment(description, "");
+        item.push_str(DERIVES);
+        item.push('\n');
+        if let Some(tag) = tag {
+            item.push_str(&format!(
+                "#[serde(tag = \"$type\")]\n#[serde(rename = \"{tag}\")]\n"
+            ));
+        }
+        item.push_str("#[serde(rename_all = \"camelCase\")]\n");
+        item.push_str(&format!("pub struct {ty} {{\n{fields}}}\n"));
+        self.items.push(item);
+
+        let (checks, _) = self.object_checks(object, false)?;
+        let body = self.validate_body(&checks);
+        self.items
+            .push(format!("impl Validate for {ty} {{\n{body}}}\n"));
+        self.items.extend(unions);
+        Ok(())
+    }
+
+    /// The `validate_at` method of a `Validate` impl whose body is `checks`.
+    fn validate_body(&mut self, checks: &str) -> String {
+        self.import("crate::validation::Validate");
+        self.import("crate::validation::ValidationError");
+        let (path, errors) = match checks.is_empty() {
+            true => ("_path", "_errors"),
+            false => ("path", "errors"),
+        };
+        format!(
+            "    fn validate_at(&self, {path}: &str, {errors}: &mut Vec<ValidationError>) {{\n\
+             {checks}    }}\n"
+        )
+    }
+
+    /// Checks on each property of `object` that has constraints, along with the Rust names of
+    /// the properties checked. Struct variant fields are bindings rather than `self.` fields.
+    fn object_checks(&mut self, object: &Object, variant: bool) -> Result<(String, Vec<String>)> {
+        let mut out = String::new();
+        let mut checked = Vec::new();
+        for (name, field) in &object.properties {
+            let rust_name = escape_keyword(&snake_case(name));
+            let path = format!("&field(path, \"{name}\")");
+            let required = object.required.contains(name);
+            let check = match (required, variant) {
+                (true, false) => self.checks(field, &format!("self.{rust_name}"), false, &path)?,
+                (true, true) => self.checks(field, &rust_name, true, &path)?,
+                (false, _) => self.checks(field, "value", true, &path)?.map(|check| {
+                    let value = match variant {
+                        true => rust_name.clone(),
+                        false => format!("&self.{rust_name}"),
+                    };
+                    // an array's checks are already a block of their own
+                    let check = match check.strip_prefix("{\n") {
+                        Some(inner) => inner.strip_suffix("}\n").unwrap_or(inner).to_string(),
+                        None => check,
+                    };
+                    format!("if let Some(value) = {value} {{\n{check}}}\n")
+                }),
+            };
+            if let Some(check) = check {
+                self.import("crate::validation::field");
+                out.push_str(&check);
+                checked.push(rust_name);
+            }
+        }
+        Ok((out, checked))
+    }
+
+    /// Statements checking `value` (a reference if `is_ref`, else a place) against the
+    /// constraints `field` puts on it, reporting under the `&str` expression `path`.
+    fn checks(
+        &mut self,
+        field: &Field,
+        value: &str,
+        is_ref: bool,
+        path: &str,
+    ) -> Result<Option<String>> {
+        let value_ref = match is_ref {
+            true => value.to_string(),
+            false => format!("&{value}"),
+        };
+        Ok(match field {
+            Field::String(field) => {
+                let mut rules = Vec::new();
+                if let Some(format) = &field.format {
+                    let format = format_variant(format)?;
+                    self.import("crate::validation::Format");
+                    rules.push(format!("format: Some(Format::{format})"));
+                }
+                push_rule(&mut rules, "min_length", field.min_length);
+                push_rule(&mut rules, "max_length", field.max_length);
+                push_rule(&mut rules, "min_graphemes", field.min_graphemes);
+                push_rule(&mut rules, "max_graphemes", field.max_graphemes);
+                self.rules("StringRules", rules, 5)
+                    .map(|rules| format!("{rules}.check({path}, {value_ref}, errors);\n"))
+            }
+            Field::Integer(field) => {
+                let mut rules = Vec::new();
+                push_rule(&mut rules, "minimum", field.minimum);
+                push_rule(&mut rules, "maximum", field.maximum);
+                let value = match is_ref {
+                    true => format!("*{value}"),
+                    false => value.to_string(),
+                };
+                // non-negative integers are generated as usize
+                let value = match field.minimum {
+                    Some(minimum) if minimum >= 0 => format!("{value} as i64"),
+                    _ => value,
+                };
+                self.rules("IntegerRules", rules, 2)
+                    .map(|rules| format!("{rules}.check({path}, {value}, errors);\n"))
+            }
+            Field::Blob(field) => {
+                let mut rules = Vec::new();
+                if !field.accept.is_empty() {
+                    let accept: Vec<String> = field
+                        .accept
+                        .iter()
+                        .map(|accept| format!("{accept:?}"))
+                        .collect();
+                    rules.push(format!("accept: &[{}]", accept.join(", ")));
+                }
+                push_rule(&mut rules, "max_size", field.max_size);
+                self.rules("BlobRules", rules, 2)
+                    .map(|rules| format!("{rules}.check({path}, {value_ref}, errors);\n"))
+            }
+            Field::Array(field) => {
+                let mut rules = Vec::new();
+                push_rule(&mut rules, "min_length", field.min_length);
+                push_rule(&mut rules, "max_length", field.max_length);
+                let length = self
+                    .rules("ArrayRules", rules, 2)
+                    .map(|rules| format!("{rules}.check(path, {value}.len(), errors);\n"));
+                let items = self.checks(&field.items, "value", true, "&index(path, i)")?;
+                if items.is_some() {
+                    self.import("crate::validation::index");
+                }
+                let items = items.map(|items| {
+                    format!("for (i, value) in {value}.iter().enumerate() {{\n{items}}}\n")
+                });
+                match (length, items) {
+                    (None, None) => None,
+                    (length, items) => Some(format!(
+                        "{{\nlet path = {path};\n{}{}}}\n",
+                        length.unwrap_or_default(),
+                        items.unwrap_or_default()
+                    )),
+                }
+            }
+            Field::Ref(_) | Field::Union(_) => {
+                Some(format!("{value}.validate_at({path}, errors);\n"))
+            }
+            Field::Boolean(_) | Field::Unknown(_) | Field::CidLink(_) => None,
+        })
+    }
+
+    /// A `rules` struct literal, or `None` when there are no rules to check. `count` is how many
+    /// fields the struct has, since spelling out `..Default::default()` for none is a lint.
+    fn rules(&mut self, rules_type: &str, rules: Vec<String>, count: usize) -> Option<String> {
+        if rules.is_empty() {
+            return None;
+        }
+        self.import(&format!("crate::validation::{rules_type}"));
+        let rest = match rules.len() < count {
+            true => ", ..Default::default()",
+            false => "",
+        };
+        Some(format!("{rules_type} {{ {}{rest} }}", rules.join(", ")))
+    }
+
+    /// The field list of a struct or struct variant. Unions met along the way are generated into
+    /// `unions`, named after `owner` and the property.
+    fn fields(
+        &mut self,
+        owner: &str,
+        object: &Object,
+        variant: bool,
+        unions: &mut Vec<String>,
+    ) -> Result<String> {
+        let (indent, vis) = match variant {
+            true => ("        ", ""),
+            false => ("    ", "pub "),
+        };
+        let mut out = String::new();
+        for (name, field) in &object.properties {
+            let rust_name = snake_case(name);
+            let mut ty = self.field_type(owner, name, field, unions)?;
+            let required = object.required.contains(name);
+            if !required {
+                ty = format!("Option<{ty}>");
+            }
+            out.push_str(&doc_comment(field.description(), indent));
+            // struct variants don't pick up the enum's rename_all, so spell out any renames
+            if (variant && rust_name != *name) || camel_case(&rust_name) != *name {
+                out.push_str(&format!("{indent}#[serde(rename = \"{name}\")]\n"));
+            }
+            if !required {
+                out.push_str(&format!(
+                    "{indent}#[serde(skip_serializing_if = \"Option::is_none\")]\n"
+                ));
+            }
+            let rust_name = escape_keyword(&rust_name);
+            out.push_str(&format!("{indent}{vis}{rust_name}: {ty},\n"));
+        }
+        Ok(out)
+    }
+
+    fn field_type(
+        &mut self,
+        owner: &str,
+        name: &str,
+        field: &Field,
+        unions: &mut Vec<String>,
+    ) -> Result<String> {
+        Ok(match field {
+            Field::Boolean(_) => "bool".to_string(),
+            Field::Integer(field) => match field.minimum {
+                Some(minimum) if minimum >= 0 => "usize".to_string(),
+                _ => "i64".to_string(),
+            },
+            Field::String(_) => "String".to_string(),
+            Field::Unknown(_) => "serde_json::Value".to_string(),
+            Field::Blob(_) => self.import(BLOB),
+            Field::CidLink(_) => self.import(CID_LINK),
+            Field::Array(field) => {
+                format!(
+                    "Vec<{}>",
+                    self.field_type(owner, name, &field.items, unions)?
+                )
+            }
+            Field::Ref(field) => self.resolve(&field.r#ref)?,
+            Field::Union(field) => {
+                let key = format!("{}.{name}", self.owner_ref(owner));
+                let ty = rename(&key).unwrap_or_else(|| format!("{owner}{}", pascal_case(name)));
+                let item = self.union(&ty, field, unions)?;
+                unions.push(item);
+                ty
+            }
+        })
+    }
+
+    /// The ref of the def that generated struct `owner` came from, for looking up renames.
+    fn owner_ref(&self, owner: &str) -> String {
+        let nsid = self.doc.id.as_str();
+        match self
+            .doc
+            .defs
+            .keys()
+            .find(|name| type_name(nsid, name) == owner)
+        {
+            Some(name) if name != "main" => format!("{nsid}#{name}"),
+            _ => nsid.to_string(),
+        }
+    }
+
+    fn union(&mut self, ty: &str, union: &UnionField, unions: &mut Vec<String>) -> Result<String> {
+        let nsid = self.doc.id.as_str();
+        let mut variants = String::new();
+        let mut arms = String::new();
+        let mut any_checks = false;
+        let mut tags = String::new();
+        for r in &union.refs {
+            let (target_nsid, name) = split_ref(nsid, r);
+            let tag = match name {
+                "main" => target_nsid.to_string(),
+                _ => format!("{target_nsid}#{name}"),
+            };
+            tags.push_str(&format!("        \"{tag}\",\n"));
+            let variant = match name {
+                "main" => pascal_case(last_segment(target_nsid)),
+                _ => pascal_case(name),
+            };
+            if self.codegen.is_inlined(target_nsid, name) {
+                let Some(Def::Object(object)) = self.doc.defs.get(name) else {
+                    bail!("{r} in {nsid} is not an object");
+                };
+                variants.push_str(&doc_comment(object.description.as_deref(), "    "));
+                variants.push_str(&format!("    #[serde(rename = \"{tag}\")]\n"));
+                if object.properties.is_empty() {
+                    variants.push_str(&format!("    {variant},\n"));
+                    arms.push_str(&format!("{ty}::{variant} => {{}}\n"));
+                } else {
+                    let fields = self.fields(&variant, object, true, unions)?;
+                    variants.push_str(&format!("    {variant} {{\n{fields}    }},\n"));
+                    let (checks, checked) = self.object_checks(object, true)?;
+                    any_checks |= !checks.is_empty();
+                    let bindings = match checked.len() == object.properties.len() {
+                        true => checked.join(", "),
+                        false => {
+                            checked
+                                .iter()
+                                .map(|name| format!("{name}, "))
+                                .collect::<String>()
+                                + ".."
+                        }
+                    };
+                    arms.push_str(&format!(
+                        "{ty}::{variant} {{ {bindings} }} => {{\n{checks}}}\n"
+                    ));
+                }
+            } else {
+                let target = self.resolve(r)?;
+                variants.push_str(&format!("    #[serde(rename = \"{tag}\")]\n"));
+                variants.push_str(&format!("    {variant}({target}),\n"));
+                arms.push_str(&format!(
+                    "{ty}::{variant}(value) => value.validate_at(path, errors),\n"
+                ));
+                any_checks = true;
+            }
+        }
+        let mut open_union = String::new();
+        if !union.closed {
+            variants.push_str(&unknown_variant(ty));
+            arms.push_str(&format!("{ty}::Unknown {{ .. }} => {{}}\n"));
+            open_union = format!(
+                "\nimpl crate::open_union::OpenUnion for {ty} {{\n    \
+                 const KNOWN_TYPES: &'static [&'static str] = &[\n{tags}    ];\n}}\n"
+            );
+        }
+        let checks = match any_checks {
+            true => format!("match self {{\n{arms}}}\n"),
+            false => String::new(),
+        };
+        let body = self.validate_body(&checks);
+        Ok(format!(
+            "{DERIVES}\n#[serde(tag = \"$type\")]\npub enum {ty} {{\n{variants}}}\n{open_union}\n\
+             impl Validate for {ty} {{\n{body}}}\n"
+        ))
+    }
+
+    /// The Rust type a ref points at, importing it if it lives in another module.
+    fn resolve(&mut self, r: &str) -> Result<String> {
+        let (nsid, name) = split_ref(&self.doc.id, r);
+        let key = match name {
+            "main" => nsid.to_string(),
+            _ => format!("{nsid}#{name}"),
+        };
+        if let Some((_, path)) = EXTERNAL_TYPES.iter().find(|(k, _)| *k == key) {
+            return Ok(self.import(path));
+        }
+        match self.codegen.docs.get(nsid) {
+            Some(doc) if doc.defs.contains_key(name) => {
+                let ty = type_name(nsid, name);
+                if nsid == self.doc.id {
+                    Ok(ty)
+                } else {
+                    Ok(self.import(&format!("crate::{}::{ty}", module_path(nsid))))
+                }
+            }
+            _ => bail!("unresolved ref {r:?} in {}", self.doc.id),
+        }
+    }
+
+    fn import(&mut self, path: &str) -> String {
+        self.imports.insert(path.to_string());
+        path.rsplit("::").next().unwrap_or(path).to_string()
+    }
+}
+
+enum BodyRef<'a> {
+    Object(&'a Object),
+    Ref(&'a RefField),
+}
+
+fn body_ref(body: &Option<crate::lexicon::Body>) -> Option<BodyRef<'_>> {
+    match body.as_ref()?.schema.as_ref()? {
+        BodySchema::Object(object) => Some(BodyRef::Object(object)),
+        BodySchema::Ref(field) => Some(BodyRef::Ref(field)),
+    }
+}
+
+/// Splits a ref into its NSID and def name, resolving local `#name` refs against `nsid`.
+pub fn split_ref<'a>(nsid: &'a str, r: &'a str) -> (&'a str, &'a str) {
+    match r.split_once('#') {
+        Some(("", name)) => (nsid, name),
+        Some((nsid, name)) => (nsid, name),
+        None => (r, "main"),
+    }
+}
+
+fn push_rule<T: std::fmt::Display>(rules: &mut Vec<String>, name: &str, value: Option<T>) {
+    if let Some(value) = value {
+        rules.push(format!("{name}: Some({value})"));
+    }
+}
+
+/// The `validation::Format` variant for a lexicon string format.
+fn format_variant(format: &str) -> Result<&'static str> {
+    Ok(match format {
+        "at-identifier" => "AtIdentifier",
+        "at-uri" => "AtUri",
+        "cid" => "Cid",
+        "datetime" => "Datetime",
+        "did" => "Did",
+        "handle" => "Handle",
+        "language" => "Language",
+        "nsid" => "Nsid",
+        "record-key" => "RecordKey",
+        "tid" => "Tid",
+        "uri" => "Uri",
+        _ => bail!("unsupported string format {format:?}"),
+    })
+}
+
+fn rename(key: &str) -> Option<String> {
+    RENAMES
+        .iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, name)| name.to_string())
+}
+
+fn type_name(nsid: &str, name: &str) -> String {
+    match name {
+        "main" => rename(nsid).unwrap_or_else(|| pascal_case(last_segment(nsid))),
+        _ => rename(&format!("{nsid}#{name}")).unwrap_or_else(|| pascal_case(name)),
+    }
+}
+
+fn last_segment(nsid: &str) -> &str {
+    nsid.rsplit('.').next().unwrap_or(nsid)
+}
+
+/// The module a lexicon is generated into, eg `app::bsky::graph::get_blocks`.
+pub fn module_path(nsid: &str) -> String {
+    nsid.split('.')
+        .map(snake_case)
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+pub fn pascal_case(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+pub fn snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut out = String::new();
+    for (i, c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_lower = chars.get(i + 1).is_some_and(|next| next.is_lowercase());
+            if prev.is_lowercase() || prev.is_ascii_digit() || (prev.is_uppercase() && next_lower) {
+                out.push('_');
+            }
+        }
+        out.extend(c.to_lowercase());
+    }
+    out
+}
+
+/// How serde's `rename_all = "camelCase"` spells a snake_case field.
+pub fn camel_case(name: &str) -> String {
+    let mut out = String::new();
+    for (i, word) in name.split('_').enumerate() {
+        match i {
+            0 => out.push_str(word),
+            _ => out.push_str(&pascal_case(word)),
+        }
+    }
+    out
+}
+
+fn escape_keyword(name: &str) -> String {
+    match KEYWORDS.contains(&name) {
+        true => format!("r#{name}"),
+        false => name.to_string(),
+    }
+}
+
+/// `///` lines for a description, wrapped to fit within 100 columns after `indent`.
+fn doc_comment(description: Option<&str>, indent: &str) -> String {
+    let Some(description) = description else {
+        return String::new();
+    };
+    let width = 100 - indent.len() - 4;
+    let mut out = String::new();
+    let mut line = String::new();
+    for word in description.split_whitespace() {
+        if !line.is_empty() && line.len() + 1 + word.len() > width {
+            out.push_str(&format!("{indent}/// {line}\n"));
+            line.clear();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        out.push_str(&format!("{indent}/// {line}\n"));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn docs(json: &[&str]) -> BTreeMap<String, LexiconDoc> {
+        json.iter()
+            .map(|json| {
+                let doc: LexiconDoc = serde_json::from_str(json).unwrap();
+                (doc.id.clone(), doc)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn converts_case() {
+        assert_eq!(snake_case("aspectRatio"), "aspect_ratio");
+        assert_eq!(
+            snake_case("detachedEmbeddingUris"),
+            "detached_embedding_uris"
+        );
+        assert_eq!(snake_case("getDIDs"), "get_di_ds");
+        assert_eq!(snake_case("getFeedSkeleton"), "get_feed_skeleton");
+        assert_eq!(camel_case("aspect_ratio"), "aspectRatio");
+        assert_eq!(pascal_case("viewImage"), "ViewImage");
+        assert_eq!(
+            module_path("app.bsky.graph.getBlocks"),
+            "app::bsky::graph::get_blocks"
+        );
+    }
+
+    #[test]
+    fn splits_refs() {
+        assert_eq!(split_ref("a.b.c", "#view"), ("a.b.c", "view"));
+        assert_eq!(split_ref("a.b.c", "x.y.z#view"), ("x.y.z", "view"));
+        assert_eq!(split_ref("a.b.c", "x.y.z"), ("x.y.z", "main"));
+    }
+
+    #[test]
+    fn wraps_doc_comments() {
+        let description = "word ".repeat(30);
+        let doc = doc_comment(Some(&description), "    ");
+        assert!(doc.lines().all(|line| line.len() <= 100));
+        assert_eq!(doc.lines().count(), 2);
+    }
+
+    #[test]
+    fn generates_records_with_renamed_fields_and_unions() {
+        let docs = docs(&[r##"{
+            "lexicon": 1,
+            "id": "com.example.gate",
+            "defs": {
+                "main": {
+                    "type": "record",
+                    "description": "A gate.",
+                    "record": {
+                        "type": "object",
+                        "required": ["createdAt"],
+                        "properties": {
+                            "createdAt": { "type": "string", "format": "datetime" },
+                            "aspectRatio": { "type": "integer", "minimum": 1 },
+                            "rules": {
+                                "type": "array",
+                                "items": { "type": "union", "refs": ["#open", "#listRule"] }
+                            }
+                        }
+                    }
+                },
+                "open": { "type": "object", "properties": {} },
+                "listRule": {
+                    "type": "object",
+                    "required": ["listUri"],
+                    "properties": { "listUri": { "type": "string" } }
+                }
+            }
+        }"##]);
+        let samples = serde_json::json!({ "main": [{ "createdAt": "" }] });
+        let out = Codegen::new(&docs)
+            .generate("com.example.gate", &samples)
+            .unwrap();
+        assert!(out.contains("#[serde(rename = \"com.example.gate\")]\n"));
+        assert!(out.contains("pub struct Gate {\n"));
+        assert!(out.contains("    pub created_at: String,\n"));
+        assert!(out.contains("    pub aspect_ratio: Option<usize>,\n"));
+        assert!(out.contains("    pub rules: Option<Vec<GateRules>>,\n"));
+        assert!(out.contains("pub enum GateRules {\n"));
+        assert!(out.contains("    #[serde(rename = \"com.example.gate#open\")]\n    Open,\n"));
+        assert!(out.contains(
+            "    ListRule {\n        #[serde(rename = \"listUri\")]\n        list_uri: String,"
+        ));
+        assert!(!out.contains("pub struct Open"));
+        assert!(out.contains("crate::assert_round_trips::<Gate>(&samples[\"main\"]);"));
+        assert!(out.contains("impl Validate for Gate {"));
+        assert!(out.contains(
+            "IntegerRules { minimum: Some(1), ..Default::default() }\
+             .check(&field(path, \"aspectRatio\"), *value as i64, errors);"
+        ));
+        assert!(out.contains("    #[serde(untagged)]\n    Unknown {\n"));
+        assert!(
+            out.contains("deserialize_with = \"crate::open_union::unknown_type::<GateRules, _>\"")
+        );
+        assert!(out.contains(
+            "impl crate::open_union::OpenUnion for GateRules {\n    \
+             const KNOWN_TYPES: &'static [&'static str] = &[\n        \
+             \"com.example.gate#open\",\n"
+        ));
+        // nothing in the union is constrained
+        assert!(out.contains(
+            "impl Validate for GateRules {\n    fn validate_at(&self, _path: &str, _errors"
+        ));
+    }
+
+    #[test]
+    fn leaves_closed_unions_closed() {
+        let docs = docs(&[r##"{
+            "lexicon": 1,
+            "id": "com.example.pick",
+            "defs": {
+                "main": {
+                    "type": "object",
+                    "required": ["choice"],
+                    "properties": {
+                        "choice": { "type": "union", "refs": ["#yes", "#no"], "closed": true }
+                    }
+                },
+                "yes": { "type": "object", "properties": {} },
+                "no": { "type": "object", "properties": {} }
+            }
+        }"##]);
+        let samples = serde_json::json!({ "main": [{}] });
+        let out = Codegen::new(&docs)
+            .generate("com.example.pick", &samples)
+            .unwrap();
+        assert!(out.contains("pub enum PickChoice {\n"));
+        assert!(!out.contains("Unknown"));
+    }
+
+    #[test]
+    fn generates_validation_for_constrained_fields() {
+        let docs = docs(&[r##"{
+            "lexicon": 1,
+            "id": "com.example.note",
+            "defs": {
+                "main": {
+                    "type": "object",
+                    "required": ["text", "tags"],
+                    "properties": {
+                        "text": { "type": "string", "maxLength": 3000, "maxGraphemes": 300 },
+                        "tags": {
+                            "type": "array",
+                            "maxLength": 8,
+                            "items": { "type": "string", "format": "language" }
+                        },
+                        "image": { "type": "blob", "accept": ["image/*"], "maxSize": 1000 },
+                        "pinned": { "type": "boolean" }
+                    }
+                }
+            }
+        }"##]);
+        let samples = serde_json::json!({ "main": [{}] });
+        let out = Codegen::new(&docs)
+            .generate("com.example.note", &samples)
+            .unwrap();
+        assert!(out.contains(
+            "StringRules { max_length: Some(3000), max_graphemes: Some(300), ..Default::default() }\
+             .check(&field(path, \"text\"), &self.text, errors);"
+        ));
+        assert!(out.contains(
+            "ArrayRules { max_length: Some(8), ..Default::default() }\
+             .check(path, self.tags.len(), errors);"
+        ));
+        assert!(out.contains("for (i, value) in self.tags.iter().enumerate() {"));
+        assert!(out.contains(
+            "StringRules { format: Some(Format::Language), ..Default::default() }\
+             .check(&index(path, i), value, errors);"
+        ));
+        assert!(out.contains(
+            "if let Some(value) = &self.image {\n\
+             BlobRules { accept: &[\"image/*\"], max_size: Some(1000) }\
+             .check(&field(path, \"image\"), value, errors);"
+        ));
+        assert!(!out.contains("\"pinned\")"));
+    }
+
+    #[test]
+    fn requires_samples_and_resolvable_refs() {
+        let docs = docs(&[
+            r#"{
+                "lexicon": 1,
+                "id": "com.example.thing",
+                "defs": {
+                    "main": {
+                        "type": "object",
+                        "properties": { "other": { "type": "ref", "ref": "com.example.other" } }
+                    }
+                }
+            }"#,
+            r#"{
+                "lexicon": 1,
+                "id": "com.example.plain",
+                "defs": { "main": { "type": "object", "properties": {} } }
+            }"#,
+        ]);
+        let codegen = Codegen::new(&docs);
+        let samples = serde_json::json!({ "main": [{}] });
+        let error = codegen.generate("com.example.thing", &samples).unwrap_err();
+        assert!(error.to_string().contains("unresolved ref"));
+        assert!(codegen.generate("com.example.plain", &samples).is_ok());
+        let error = codegen
+            .generate("com.example.plain", &serde_json::json!({}))
+            .unwrap_err();
+        assert!(error.to_string().contains("no samples"));
+    }
+}
@@ -0,0 +1,186 @@
+use indexmap::IndexMap;
+use serde::Deserialize;
+
+/// A lexicon schema document, eg `lexicons/app/bsky/feed/like.json`. Only the parts the
+/// generator needs are modelled; other keys are ignored.
+#[derive(Debug, Deserialize)]
+pub struct LexiconDoc {
+    pub lexicon: u32,
+    pub id: String,
+    pub description: Option<String>,
+    pub defs: IndexMap<String, Def>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Def {
+    Record(Record),
+    Object(Object),
+    Query(Xrpc),
+    Procedure(Xrpc),
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Record {
+    pub description: Option<String>,
+    pub record: Object,
+}
+
+/// An `object`, or the `params` of an XRPC method, which share the same shape.
+#[derive(Debug, Default, Deserialize)]
+pub struct Object {
+    pub description: Option<String>,
+    #[serde(default)]
+    pub required: Vec<String>,
+    #[serde(default)]
+    pub properties: IndexMap<String, Field>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Xrpc {
+    pub description: Option<String>,
+    pub parameters: Option<Object>,
+    pub input: Option<Body>,
+    pub output: Option<Body>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Body {
+    pub schema: Option<BodySchema>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum BodySchema {
+    Object(Object),
+    Ref(RefField),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Field {
+    Boolean(Described),
+    Integer(IntegerField),
+    String(StringField),
+    Unknown(Described),
+    Blob(BlobField),
+    CidLink(Described),
+    Array(ArrayField),
+    Ref(RefField),
+    Union(UnionField),
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Described {
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct IntegerField {
+    pub description: Option<String>,
+    pub minimum: Option<i64>,
+    pub maximum: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StringField {
+    pub description: Option<String>,
+    pub format: Option<String>,
+    pub min_length: Option<usize>,
+    pub max_length: Option<usize>,
+    pub min_graphemes: Option<usize>,
+    pub max_graphemes: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlobField {
+    pub description: Option<String>,
+    #[serde(default)]
+    pub accept: Vec<String>,
+    pub max_size: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArrayField {
+    pub description: Option<String>,
+    pub items: Box<Field>,
+    pub min_length: Option<usize>,
+    pub max_length: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RefField {
+    pub description: Option<String>,
+    #[serde(rename = "ref")]
+    pub r#ref: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UnionField {
+    pub description: Option<String>,
+    pub refs: Vec<String>,
+    /// Closed unions only ever hold one of `refs`; open ones may hold types added later.
+    #[serde(default)]
+    pub closed: bool,
+}
+
+impl Field {
+    pub fn description(&self) -> Option<&str> {
+        match self {
+            Field::Boolean(field) | Field::Unknown(field) | Field::CidLink(field) => {
+                field.description.as_deref()
+            }
+            Field::Integer(field) => field.description.as_deref(),
+            Field::String(field) => field.description.as_deref(),
+            Field::Blob(field) => field.description.as_deref(),
+            Field::Array(field) => field.description.as_deref(),
+            Field::Ref(field) => field.description.as_deref(),
+            Field::Union(field) => field.description.as_deref(),
+        }
+    }
+
+    /// Every `(ref, via_union)` this field points at, including through arrays.
+    pub fn refs(&self) -> Vec<(&str, bool)> {
+        match self {
+            Field::Array(field) => field.items.refs(),
+            Field::Ref(field) => vec![(field.r#ref.as_str(), false)],
+            Field::Union(field) => field.refs.iter().map(|r| (r.as_str(), true)).collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl LexiconDoc {
+    /// Every `(ref, via_union)` made anywhere in this document.
+    pub fn refs(&self) -> Vec<(&str, bool)> {
+        let mut objects: Vec<&Object> = Vec::new();
+        let mut refs = Vec::new();
+        for def in self.defs.values() {
+            match def {
+                Def::Record(record) => objects.push(&record.record),
+                Def::Object(object) => objects.push(object),
+                Def::Query(xrpc) | Def::Procedure(xrpc) => {
+                    objects.extend(xrpc.parameters.as_ref());
+                    for body in [&xrpc.input, &xrpc.output].into_iter().flatten() {
+                        match &body.schema {
+                            Some(BodySchema::Object(object)) => objects.push(object),
+                            Some(BodySchema::Ref(field)) => {
+                                refs.push((field.r#ref.as_str(), false))
+                            }
+                            None => (),
+                        }
+                    }
+                }
+            }
+        }
+        for object in objects {
+            for field in object.properties.values() {
+                refs.extend(field.refs());
+            }
+        }
+        refs
+    }
+}
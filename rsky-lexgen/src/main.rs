@@ -0,0 +1,121 @@
+//! Generates rsky-lexicon modules from the lexicon JSON schemas vendored in
+//! `rsky-lexicon/lexicons`.
+//!
+//! ```text
+//! cargo run -p rsky-lexgen            # regenerate
+//! cargo run -p rsky-lexgen -- --check # fail if any generated module is stale
+//! ```
+mod codegen;
+mod lexicon;
+
+use crate::codegen::{module_path, Codegen};
+use crate::lexicon::LexiconDoc;
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::{env, fs};
+
+fn main() -> Result<()> {
+    let mut check = false;
+    let mut crate_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../rsky-lexicon");
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--check" => check = true,
+            _ if arg.starts_with('-') => bail!("usage: rsky-lexgen [--check] [LEXICON_CRATE_DIR]"),
+            _ => crate_dir = PathBuf::from(arg),
+        }
+    }
+
+    let docs = load_docs(&crate_dir.join("lexicons"))?;
+    let codegen = Codegen::new(&docs);
+    let mut stale = Vec::new();
+    for nsid in docs.keys() {
+        let relative = nsid.replace('.', "/");
+        let samples_path = crate_dir.join(format!("test-vectors/{relative}.json"));
+        let samples: Value = match fs::read_to_string(&samples_path) {
+            Ok(samples) => serde_json::from_str(&samples)
+                .with_context(|| format!("malformed samples in {}", samples_path.display()))?,
+            Err(_) => Value::Null,
+        };
+        let source = rustfmt(&codegen.generate(nsid, &samples)?)?;
+
+        let path = crate_dir.join(format!("src/{}.rs", module_path(nsid).replace("::", "/")));
+        if fs::read_to_string(&path).ok().as_deref() == Some(source.as_str()) {
+            continue;
+        }
+        if check {
+            stale.push(path.display().to_string());
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, source)?;
+        println!("wrote {}", path.display());
+        warn_if_undeclared(&path);
+    }
+    if !stale.is_empty() {
+        bail!("generated modules are out of date:\n{}", stale.join("\n"));
+    }
+    Ok(())
+}
+
+/// Every `*.json` lexicon under `dir`, keyed by NSID.
+fn load_docs(dir: &Path) -> Result<BTreeMap<String, LexiconDoc>> {
+    let mut paths = vec![dir.to_path_buf()];
+    let mut docs = BTreeMap::new();
+    while let Some(path) = paths.pop() {
+        if path.is_dir() {
+            for entry in fs::read_dir(&path)? {
+                paths.push(entry?.path());
+            }
+        } else if path.extension().is_some_and(|ext| ext == "json") {
+            let json = fs::read_to_string(&path)?;
+            let doc: LexiconDoc = serde_json::from_str(&json)
+                .with_context(|| format!("failed to parse {}", path.display()))?;
+            if doc.lexicon != 1 {
+                bail!("unsupported lexicon version {} in {}", doc.lexicon, doc.id);
+            }
+            docs.insert(doc.id.clone(), doc);
+        }
+    }
+    Ok(docs)
+}
+
+fn rustfmt(source: &str) -> Result<String> {
+    let mut child = Command::new("rustfmt")
+        .args(["--edition", "2021"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .context("failed to run rustfmt")?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(source.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        bail!("rustfmt rejected generated code:\n{source}");
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// Generated modules still need a `pub mod` in their hand-written parent.
+fn warn_if_undeclared(path: &Path) {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_stem()) else {
+        return;
+    };
+    let declaration = format!("pub mod {};", name.to_string_lossy());
+    let parents = [dir.join("mod.rs"), dir.with_extension("rs")];
+    let declared = parents
+        .iter()
+        .any(|parent| fs::read_to_string(parent).is_ok_and(|source| source.contains(&declaration)));
+    if !declared {
+        eprintln!(
+            "warning: add `{declaration}` to the parent of {}",
+            path.display()
+        );
+    }
+}
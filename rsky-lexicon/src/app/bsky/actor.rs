@@ -1,6 +1,8 @@
 use crate::app::bsky::graph::ListViewBasic;
 use crate::com::atproto::label::{Label, SelfLabels};
 use crate::com::atproto::repo::{Blob, StrongRef};
+use crate::open_union::OpenUnion;
+use crate::validation::{Validate, ValidationError};
 use chrono::{DateTime, Utc};
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -34,6 +36,22 @@ pub struct Profile {
 pub enum ProfileLabels {
     #[serde(rename = "com.atproto.label.defs#selfLabels")]
     SelfLabels(SelfLabels),
+    /// A `$type` this union doesn't know, kept as-is so it round-trips unchanged. Known types
+    /// that don't parse are errors rather than landing here.
+    #[serde(untagged)]
+    Unknown {
+        #[serde(
+            rename = "$type",
+            deserialize_with = "crate::open_union::unknown_type::<ProfileLabels, _>"
+        )]
+        r#type: String,
+        #[serde(flatten)]
+        data: serde_json::Value,
+    },
+}
+
+impl OpenUnion for ProfileLabels {
+    const KNOWN_TYPES: &'static [&'static str] = &["com.atproto.label.defs#selfLabels"];
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -61,6 +79,11 @@ pub struct ProfileView {
     pub indexed_at: Option<String>,
 }
 
+/// Views are built by the AppView rather than ingested, so they aren't checked.
+impl Validate for ProfileView {
+    fn validate_at(&self, _path: &str, _errors: &mut Vec<ValidationError>) {}
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ProfileViewDetailed {
@@ -155,6 +178,35 @@ pub enum RefPreferences {
     BskyAppStatePref(BskyAppStatePref),
     #[serde(rename = "app.bsky.actor.defs#labelersPref")]
     LabelersPref(LabelersPref),
+    /// A `$type` this union doesn't know, kept as-is so it round-trips unchanged. Known types
+    /// that don't parse are errors rather than landing here.
+    #[serde(untagged)]
+    Unknown {
+        #[serde(
+            rename = "$type",
+            deserialize_with = "crate::open_union::unknown_type::<RefPreferences, _>"
+        )]
+        r#type: String,
+        #[serde(flatten)]
+        data: serde_json::Value,
+    },
+}
+
+impl OpenUnion for RefPreferences {
+    const KNOWN_TYPES: &'static [&'static str] = &[
+        "app.bsky.actor.defs#adultContentPref",
+        "app.bsky.actor.defs#contentLabelPref",
+        "app.bsky.actor.defs#savedFeedsPref",
+        "app.bsky.actor.defs#savedFeedsPrefV2",
+        "app.bsky.actor.defs#personalDetailsPref",
+        "app.bsky.actor.defs#feedViewPref",
+        "app.bsky.actor.defs#threadViewPref",
+        "app.bsky.actor.defs#interestsPref",
+        "app.bsky.actor.defs#mutedWordsPref",
+        "app.bsky.actor.defs#hiddenPostsPref",
+        "app.bsky.actor.defs#bskyAppStatePref",
+        "app.bsky.actor.defs#labelersPref",
+    ];
 }
 
 impl RefPreferences {
@@ -172,6 +224,7 @@ impl RefPreferences {
             RefPreferences::HiddenPostsPref(_) => "app.bsky.actor.defs#hiddenPostsPref",
             RefPreferences::BskyAppStatePref(_) => "app.bsky.actor.defs#bskyAppStatePref",
             RefPreferences::LabelersPref(_) => "app.bsky.actor.defs#labelersPref",
+            RefPreferences::Unknown { r#type, .. } => r#type,
         };
         r#type.to_string()
     }
@@ -0,0 +1,41 @@
+// Generated by rsky-lexgen from lexicons/app/bsky/embed/defs.json.
+// Do not edit by hand: update the lexicon and run `cargo run -p rsky-lexgen` instead.
+
+use crate::validation::{field, IntegerRules, Validate, ValidationError};
+
+/// width:height represents an aspect ratio. It may be approximate, and may not correspond to
+/// absolute dimensions in any given unit.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AspectRatio {
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Validate for AspectRatio {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        IntegerRules {
+            minimum: Some(1),
+            ..Default::default()
+        }
+        .check(&field(path, "width"), self.width as i64, errors);
+        IntegerRules {
+            minimum: Some(1),
+            ..Default::default()
+        }
+        .check(&field(path, "height"), self.height as i64, errors);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_samples() {
+        let samples = crate::test_vectors(include_str!(
+            "../../../../test-vectors/app/bsky/embed/defs.json"
+        ));
+        crate::assert_round_trips::<AspectRatio>(&samples["aspectRatio"]);
+    }
+}
@@ -1,22 +1,50 @@
+// Generated by rsky-lexgen from lexicons/app/bsky/embed/external.json.
+// Do not edit by hand: update the lexicon and run `cargo run -p rsky-lexgen` instead.
+
 use crate::com::atproto::repo::Blob;
+use crate::validation::{field, BlobRules, Format, StringRules, Validate, ValidationError};
 
-/// A representation of some externally linked content (eg, a URL and 'card'),
-/// embedded in a Bluesky record (eg, a post).
+/// A representation of some externally linked content (eg, a URL and 'card'), embedded in a Bluesky
+/// record (eg, a post).
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct External {
     pub external: ExternalObject,
 }
 
+impl Validate for External {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        self.external.validate_at(&field(path, "external"), errors);
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ExternalObject {
     pub uri: String,
     pub title: String,
     pub description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub thumb: Option<Blob>,
 }
 
+impl Validate for ExternalObject {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        StringRules {
+            format: Some(Format::Uri),
+            ..Default::default()
+        }
+        .check(&field(path, "uri"), &self.uri, errors);
+        if let Some(value) = &self.thumb {
+            BlobRules {
+                accept: &["image/*"],
+                max_size: Some(1000000),
+            }
+            .check(&field(path, "thumb"), value, errors);
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(tag = "$type")]
 #[serde(rename = "app.bsky.embed.external#view")]
@@ -25,11 +53,51 @@ pub struct View {
     pub external: ViewExternal,
 }
 
+impl Validate for View {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        self.external.validate_at(&field(path, "external"), errors);
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ViewExternal {
     pub uri: String,
     pub title: String,
     pub description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub thumb: Option<String>,
 }
+
+impl Validate for ViewExternal {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        StringRules {
+            format: Some(Format::Uri),
+            ..Default::default()
+        }
+        .check(&field(path, "uri"), &self.uri, errors);
+        if let Some(value) = &self.thumb {
+            StringRules {
+                format: Some(Format::Uri),
+                ..Default::default()
+            }
+            .check(&field(path, "thumb"), value, errors);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_samples() {
+        let samples = crate::test_vectors(include_str!(
+            "../../../../test-vectors/app/bsky/embed/external.json"
+        ));
+        crate::assert_round_trips::<External>(&samples["main"]);
+        crate::assert_round_trips::<ExternalObject>(&samples["external"]);
+        crate::assert_round_trips::<View>(&samples["view"]);
+        crate::assert_round_trips::<ViewExternal>(&samples["viewExternal"]);
+    }
+}
@@ -1,4 +1,11 @@
+// Generated by rsky-lexgen from lexicons/app/bsky/embed/images.json.
+// Do not edit by hand: update the lexicon and run `cargo run -p rsky-lexgen` instead.
+
+use crate::app::bsky::embed::defs::AspectRatio;
 use crate::com::atproto::repo::Blob;
+use crate::validation::{
+    field, index, ArrayRules, BlobRules, Format, StringRules, Validate, ValidationError,
+};
 
 /// A set of images embedded in a Bluesky record (eg, a post).
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -7,20 +14,43 @@ pub struct Images {
     pub images: Vec<Image>,
 }
 
+impl Validate for Images {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        {
+            let path = &field(path, "images");
+            ArrayRules {
+                max_length: Some(4),
+                ..Default::default()
+            }
+            .check(path, self.images.len(), errors);
+            for (i, value) in self.images.iter().enumerate() {
+                value.validate_at(&index(path, i), errors);
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Image {
     pub image: Blob,
-    /// Alt text description of the image, for accessibility
+    /// Alt text description of the image, for accessibility.
     pub alt: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub aspect_ratio: Option<AspectRatio>,
 }
 
-/// width:height represents an aspect ratio. It may be approximate,
-/// and may not correspond to absolute dimensions in any given unit.
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
-pub struct AspectRatio {
-    pub width: usize,
-    pub height: usize,
+impl Validate for Image {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        BlobRules {
+            accept: &["image/*"],
+            max_size: Some(1000000),
+        }
+        .check(&field(path, "image"), &self.image, errors);
+        if let Some(value) = &self.aspect_ratio {
+            value.validate_at(&field(path, "aspectRatio"), errors);
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -31,16 +61,67 @@ pub struct View {
     pub images: Vec<ViewImage>,
 }
 
+impl Validate for View {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        {
+            let path = &field(path, "images");
+            ArrayRules {
+                max_length: Some(4),
+                ..Default::default()
+            }
+            .check(path, self.images.len(), errors);
+            for (i, value) in self.images.iter().enumerate() {
+                value.validate_at(&index(path, i), errors);
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ViewImage {
-    /// Fully-qualified URL where a thumbnail of the image can be fetched.
-    /// For example, CDN location provided by the App View.
+    /// Fully-qualified URL where a thumbnail of the image can be fetched. For example, CDN location
+    /// provided by the App View.
     pub thumb: String,
-    /// Fully-qualified URL where a large version of the image can be fetched.
-    /// May or may not be the exact original blob. For example, CDN location provided by the App View.
+    /// Fully-qualified URL where a large version of the image can be fetched. May or may not be the
+    /// exact original blob. For example, CDN location provided by the App View.
     pub fullsize: String,
     /// Alt text description of the image, for accessibility.
     pub alt: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub aspect_ratio: Option<AspectRatio>,
 }
+
+impl Validate for ViewImage {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        StringRules {
+            format: Some(Format::Uri),
+            ..Default::default()
+        }
+        .check(&field(path, "thumb"), &self.thumb, errors);
+        StringRules {
+            format: Some(Format::Uri),
+            ..Default::default()
+        }
+        .check(&field(path, "fullsize"), &self.fullsize, errors);
+        if let Some(value) = &self.aspect_ratio {
+            value.validate_at(&field(path, "aspectRatio"), errors);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_samples() {
+        let samples = crate::test_vectors(include_str!(
+            "../../../../test-vectors/app/bsky/embed/images.json"
+        ));
+        crate::assert_round_trips::<Images>(&samples["main"]);
+        crate::assert_round_trips::<Image>(&samples["image"]);
+        crate::assert_round_trips::<View>(&samples["view"]);
+        crate::assert_round_trips::<ViewImage>(&samples["viewImage"]);
+    }
+}
@@ -1,3 +1,4 @@
+pub mod defs;
 pub mod external;
 pub mod images;
 pub mod record;
@@ -9,6 +10,8 @@ use crate::app::bsky::embed::images::{Images, View as ImagesView};
 use crate::app::bsky::embed::record::{Record, View as RecordView};
 use crate::app::bsky::embed::record_with_media::{RecordWithMedia, View as RecordWithMediaView};
 use crate::app::bsky::embed::video::{Video, View as VideoView};
+use crate::open_union::OpenUnion;
+use crate::validation::{Validate, ValidationError};
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(tag = "$type")]
@@ -19,6 +22,37 @@ pub enum MediaUnion {
     Video(Video),
     #[serde(rename = "app.bsky.embed.external")]
     External(External),
+    /// A `$type` this union doesn't know, kept as-is so it round-trips unchanged. Known types
+    /// that don't parse are errors rather than landing here.
+    #[serde(untagged)]
+    Unknown {
+        #[serde(
+            rename = "$type",
+            deserialize_with = "crate::open_union::unknown_type::<MediaUnion, _>"
+        )]
+        r#type: String,
+        #[serde(flatten)]
+        data: serde_json::Value,
+    },
+}
+
+impl OpenUnion for MediaUnion {
+    const KNOWN_TYPES: &'static [&'static str] = &[
+        "app.bsky.embed.images",
+        "app.bsky.embed.video",
+        "app.bsky.embed.external",
+    ];
+}
+
+impl Validate for MediaUnion {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        match self {
+            MediaUnion::Images(images) => images.validate_at(path, errors),
+            MediaUnion::Video(video) => video.validate_at(path, errors),
+            MediaUnion::External(external) => external.validate_at(path, errors),
+            MediaUnion::Unknown { .. } => {}
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -30,6 +64,26 @@ pub enum MediaViewUnion {
     VideoView(VideoView),
     #[serde(rename = "app.bsky.embed.external#view")]
     ExternalView(ExternalView),
+    /// A `$type` this union doesn't know, kept as-is so it round-trips unchanged. Known types
+    /// that don't parse are errors rather than landing here.
+    #[serde(untagged)]
+    Unknown {
+        #[serde(
+            rename = "$type",
+            deserialize_with = "crate::open_union::unknown_type::<MediaViewUnion, _>"
+        )]
+        r#type: String,
+        #[serde(flatten)]
+        data: serde_json::Value,
+    },
+}
+
+impl OpenUnion for MediaViewUnion {
+    const KNOWN_TYPES: &'static [&'static str] = &[
+        "app.bsky.embed.images#view",
+        "app.bsky.embed.video#view",
+        "app.bsky.embed.external#view",
+    ];
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -52,6 +106,43 @@ pub enum Embeds {
 
     #[serde(rename = "app.bsky.embed.recordWithMedia")]
     RecordWithMedia(RecordWithMedia),
+
+    /// A `$type` this union doesn't know, kept as-is so it round-trips unchanged. Known types
+    /// that don't parse are errors rather than landing here.
+    #[serde(untagged)]
+    Unknown {
+        #[serde(
+            rename = "$type",
+            deserialize_with = "crate::open_union::unknown_type::<Embeds, _>"
+        )]
+        r#type: String,
+        #[serde(flatten)]
+        data: serde_json::Value,
+    },
+}
+
+impl OpenUnion for Embeds {
+    const KNOWN_TYPES: &'static [&'static str] = &[
+        "app.bsky.embed.images",
+        "app.bsky.embed.video",
+        "app.bsky.embed.external",
+        "app.bsky.embed.external#main",
+        "app.bsky.embed.record",
+        "app.bsky.embed.recordWithMedia",
+    ];
+}
+
+impl Validate for Embeds {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        match self {
+            Embeds::Images(images) => images.validate_at(path, errors),
+            Embeds::Video(video) => video.validate_at(path, errors),
+            Embeds::External(external) => external.validate_at(path, errors),
+            Embeds::Record(record) => record.validate_at(path, errors),
+            Embeds::RecordWithMedia(record) => record.validate_at(path, errors),
+            Embeds::Unknown { .. } => {}
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -62,4 +153,25 @@ pub enum EmbedViews {
     VideoView(VideoView),
     RecordView(RecordView),
     RecordWithMediaView(RecordWithMediaView),
+    /// A `$type` this union doesn't know, kept as-is so it round-trips unchanged. Known types
+    /// that don't parse are errors rather than landing here.
+    Unknown {
+        #[serde(
+            rename = "$type",
+            deserialize_with = "crate::open_union::unknown_type::<EmbedViews, _>"
+        )]
+        r#type: String,
+        #[serde(flatten)]
+        data: serde_json::Value,
+    },
+}
+
+impl OpenUnion for EmbedViews {
+    const KNOWN_TYPES: &'static [&'static str] = &[
+        "app.bsky.embed.images#view",
+        "app.bsky.embed.external#view",
+        "app.bsky.embed.video#view",
+        "app.bsky.embed.record#view",
+        "app.bsky.embed.recordWithMedia#view",
+    ];
 }
@@ -5,6 +5,8 @@ use crate::app::bsky::graph::{ListView, StarterPackViewBasic};
 use crate::app::bsky::labeler::LabelerView;
 use crate::com::atproto::label::Label;
 use crate::com::atproto::repo::StrongRef;
+use crate::open_union::OpenUnion;
+use crate::validation::{field, Validate, ValidationError};
 use serde_json::Value;
 
 /// A representation of a record embedded in a Bluesky record (eg, a post).
@@ -14,6 +16,12 @@ pub struct Record {
     pub record: StrongRef,
 }
 
+impl Validate for Record {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        self.record.validate_at(&field(path, "record"), errors);
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(tag = "$type")]
 #[serde(rename = "app.bsky.embed.record#viewRecord")]
@@ -49,6 +57,29 @@ pub enum ViewUnion {
     ListView(ListView),
     LabelerView(LabelerView),
     StarterPackViewBasic(StarterPackViewBasic),
+    /// A `$type` this union doesn't know, kept as-is so it round-trips unchanged. Known types
+    /// that don't parse are errors rather than landing here.
+    Unknown {
+        #[serde(
+            rename = "$type",
+            deserialize_with = "crate::open_union::unknown_type::<ViewUnion, _>"
+        )]
+        r#type: String,
+        #[serde(flatten)]
+        data: Value,
+    },
+}
+
+impl OpenUnion for ViewUnion {
+    const KNOWN_TYPES: &'static [&'static str] = &[
+        "app.bsky.embed.record#viewRecord",
+        "app.bsky.embed.record#viewNotFound",
+        "app.bsky.embed.record#viewBlocked",
+        "app.bsky.feed.defs#generatorView",
+        "app.bsky.graph.defs#listView",
+        "app.bsky.labeler.defs#labelerView",
+        "app.bsky.graph.defs#starterPackViewBasic",
+    ];
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
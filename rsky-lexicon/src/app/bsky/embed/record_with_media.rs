@@ -1,5 +1,6 @@
 use crate::app::bsky::embed::record::{Record, View as ViewRecord};
 use crate::app::bsky::embed::{MediaUnion, MediaViewUnion};
+use crate::validation::{field, Validate, ValidationError};
 
 /// A representation of a record embedded in a Bluesky record (eg, a post),
 /// alongside other compatible embeds. For example, a quote post and image,
@@ -11,6 +12,13 @@ pub struct RecordWithMedia {
     pub media: MediaUnion,
 }
 
+impl Validate for RecordWithMedia {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        self.record.validate_at(&field(path, "record"), errors);
+        self.media.validate_at(&field(path, "media"), errors);
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(tag = "$type")]
 #[serde(rename = "app.bsky.embed.recordWithMedia#view")]
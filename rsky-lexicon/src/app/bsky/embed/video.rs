@@ -1,22 +1,80 @@
-use crate::app::bsky::embed::images::AspectRatio;
+// Generated by rsky-lexgen from lexicons/app/bsky/embed/video.json.
+// Do not edit by hand: update the lexicon and run `cargo run -p rsky-lexgen` instead.
+
+use crate::app::bsky::embed::defs::AspectRatio;
 use crate::com::atproto::repo::Blob;
+use crate::validation::{
+    field, index, ArrayRules, BlobRules, Format, StringRules, Validate, ValidationError,
+};
 
+/// A video embedded in a Bluesky record (eg, a post).
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Video {
     pub video: Blob,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub captions: Option<Vec<Caption>>,
-    /// Alt text description of video image, for accessibility
+    /// Alt text description of the video, for accessibility.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub alt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub aspect_ratio: Option<AspectRatio>,
 }
 
+impl Validate for Video {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        BlobRules {
+            accept: &["video/mp4"],
+            max_size: Some(50000000),
+        }
+        .check(&field(path, "video"), &self.video, errors);
+        if let Some(value) = &self.captions {
+            let path = &field(path, "captions");
+            ArrayRules {
+                max_length: Some(20),
+                ..Default::default()
+            }
+            .check(path, value.len(), errors);
+            for (i, value) in value.iter().enumerate() {
+                value.validate_at(&index(path, i), errors);
+            }
+        }
+        if let Some(value) = &self.alt {
+            StringRules {
+                max_length: Some(10000),
+                max_graphemes: Some(1000),
+                ..Default::default()
+            }
+            .check(&field(path, "alt"), value, errors);
+        }
+        if let Some(value) = &self.aspect_ratio {
+            value.validate_at(&field(path, "aspectRatio"), errors);
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Caption {
     pub lang: String,
     pub file: Blob,
 }
 
+impl Validate for Caption {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        StringRules {
+            format: Some(Format::Language),
+            ..Default::default()
+        }
+        .check(&field(path, "lang"), &self.lang, errors);
+        BlobRules {
+            accept: &["text/vtt"],
+            max_size: Some(20000),
+        }
+        .check(&field(path, "file"), &self.file, errors);
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(tag = "$type")]
 #[serde(rename = "app.bsky.embed.video#view")]
@@ -24,7 +82,58 @@ pub struct Caption {
 pub struct View {
     pub cid: String,
     pub playlist: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub thumbnail: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub alt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub aspect_ratio: Option<AspectRatio>,
 }
+
+impl Validate for View {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        StringRules {
+            format: Some(Format::Cid),
+            ..Default::default()
+        }
+        .check(&field(path, "cid"), &self.cid, errors);
+        StringRules {
+            format: Some(Format::Uri),
+            ..Default::default()
+        }
+        .check(&field(path, "playlist"), &self.playlist, errors);
+        if let Some(value) = &self.thumbnail {
+            StringRules {
+                format: Some(Format::Uri),
+                ..Default::default()
+            }
+            .check(&field(path, "thumbnail"), value, errors);
+        }
+        if let Some(value) = &self.alt {
+            StringRules {
+                max_length: Some(10000),
+                max_graphemes: Some(1000),
+                ..Default::default()
+            }
+            .check(&field(path, "alt"), value, errors);
+        }
+        if let Some(value) = &self.aspect_ratio {
+            value.validate_at(&field(path, "aspectRatio"), errors);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_samples() {
+        let samples = crate::test_vectors(include_str!(
+            "../../../../test-vectors/app/bsky/embed/video.json"
+        ));
+        crate::assert_round_trips::<Video>(&samples["main"]);
+        crate::assert_round_trips::<Caption>(&samples["caption"]);
+        crate::assert_round_trips::<View>(&samples["view"]);
+    }
+}
@@ -0,0 +1,80 @@
+// Generated by rsky-lexgen from lexicons/app/bsky/feed/describeFeedGenerator.json.
+// Do not edit by hand: update the lexicon and run `cargo run -p rsky-lexgen` instead.
+
+use crate::validation::{field, index, Format, StringRules, Validate, ValidationError};
+
+pub const NSID: &str = "app.bsky.feed.describeFeedGenerator";
+
+/// Get information about a feed generator, including policies and offered feed URIs. Does not
+/// require auth; implemented by Feed Generator services (not App View).
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DescribeFeedGeneratorOutput {
+    pub did: String,
+    pub feeds: Vec<Feed>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub links: Option<Links>,
+}
+
+impl Validate for DescribeFeedGeneratorOutput {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        StringRules {
+            format: Some(Format::Did),
+            ..Default::default()
+        }
+        .check(&field(path, "did"), &self.did, errors);
+        {
+            let path = &field(path, "feeds");
+            for (i, value) in self.feeds.iter().enumerate() {
+                value.validate_at(&index(path, i), errors);
+            }
+        }
+        if let Some(value) = &self.links {
+            value.validate_at(&field(path, "links"), errors);
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Feed {
+    pub uri: String,
+}
+
+impl Validate for Feed {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        StringRules {
+            format: Some(Format::AtUri),
+            ..Default::default()
+        }
+        .check(&field(path, "uri"), &self.uri, errors);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Links {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub privacy_policy: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub terms_of_service: Option<String>,
+}
+
+impl Validate for Links {
+    fn validate_at(&self, _path: &str, _errors: &mut Vec<ValidationError>) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_samples() {
+        let samples = crate::test_vectors(include_str!(
+            "../../../../test-vectors/app/bsky/feed/describeFeedGenerator.json"
+        ));
+        crate::assert_round_trips::<DescribeFeedGeneratorOutput>(&samples["output"]);
+        crate::assert_round_trips::<Feed>(&samples["feed"]);
+        crate::assert_round_trips::<Links>(&samples["links"]);
+    }
+}
@@ -0,0 +1,82 @@
+// Generated by rsky-lexgen from lexicons/app/bsky/feed/getFeedSkeleton.json.
+// Do not edit by hand: update the lexicon and run `cargo run -p rsky-lexgen` instead.
+
+use crate::app::bsky::feed::SkeletonFeedPost;
+use crate::validation::{
+    field, index, Format, IntegerRules, StringRules, Validate, ValidationError,
+};
+
+pub const NSID: &str = "app.bsky.feed.getFeedSkeleton";
+
+/// Get a skeleton of a feed provided by a feed generator. Auth is optional, depending on provider
+/// requirements, and provides the DID of the requester. Implemented by Feed Generator Service.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetFeedSkeletonParams {
+    /// Reference to feed generator record describing the specific feed being requested.
+    pub feed: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+}
+
+impl Validate for GetFeedSkeletonParams {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        StringRules {
+            format: Some(Format::AtUri),
+            ..Default::default()
+        }
+        .check(&field(path, "feed"), &self.feed, errors);
+        if let Some(value) = &self.limit {
+            IntegerRules {
+                minimum: Some(1),
+                maximum: Some(100),
+            }
+            .check(&field(path, "limit"), *value as i64, errors);
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetFeedSkeletonOutput {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    pub feed: Vec<SkeletonFeedPost>,
+    /// Unique identifier per request that may be passed back alongside interactions.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub req_id: Option<String>,
+}
+
+impl Validate for GetFeedSkeletonOutput {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        {
+            let path = &field(path, "feed");
+            for (i, value) in self.feed.iter().enumerate() {
+                value.validate_at(&index(path, i), errors);
+            }
+        }
+        if let Some(value) = &self.req_id {
+            StringRules {
+                max_length: Some(100),
+                ..Default::default()
+            }
+            .check(&field(path, "reqId"), value, errors);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_samples() {
+        let samples = crate::test_vectors(include_str!(
+            "../../../../test-vectors/app/bsky/feed/getFeedSkeleton.json"
+        ));
+        crate::assert_round_trips::<GetFeedSkeletonParams>(&samples["params"]);
+        crate::assert_round_trips::<GetFeedSkeletonOutput>(&samples["output"]);
+    }
+}
@@ -1,10 +1,39 @@
+// Generated by rsky-lexgen from lexicons/app/bsky/feed/like.json.
+// Do not edit by hand: update the lexicon and run `cargo run -p rsky-lexgen` instead.
+
 use crate::com::atproto::repo::StrongRef;
+use crate::validation::{field, Format, StringRules, Validate, ValidationError};
 
+/// Record declaring a 'like' of a piece of subject content.
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(tag = "$type")]
 #[serde(rename = "app.bsky.feed.like")]
 #[serde(rename_all = "camelCase")]
 pub struct Like {
-    pub created_at: String,
     pub subject: StrongRef,
+    pub created_at: String,
+}
+
+impl Validate for Like {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        self.subject.validate_at(&field(path, "subject"), errors);
+        StringRules {
+            format: Some(Format::Datetime),
+            ..Default::default()
+        }
+        .check(&field(path, "createdAt"), &self.created_at, errors);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_samples() {
+        let samples = crate::test_vectors(include_str!(
+            "../../../../test-vectors/app/bsky/feed/like.json"
+        ));
+        crate::assert_round_trips::<Like>(&samples["main"]);
+    }
 }
@@ -1,11 +1,22 @@
+pub mod describe_feed_generator;
+pub mod get_feed_skeleton;
 pub mod like;
+pub mod postgate;
+pub mod repost;
+pub mod send_interactions;
+pub mod threadgate;
+
+pub use repost::Repost;
 
 use super::actor::ProfileView;
 use crate::app::bsky::actor::{ProfileViewBasic, ViewerState};
 use crate::app::bsky::embed::{EmbedViews, Embeds};
+use crate::app::bsky::richtext::rich_text::{POST_MAX_GRAPHEMES, POST_MAX_LENGTH};
 use crate::app::bsky::richtext::Facet;
 use crate::com::atproto::label::{Label, SelfLabels};
 use crate::com::atproto::repo::StrongRef;
+use crate::open_union::OpenUnion;
+use crate::validation::{field, index, ArrayRules, Format, StringRules, Validate, ValidationError};
 use chrono::{DateTime, Utc};
 use serde_json::Value;
 
@@ -19,6 +30,7 @@ pub struct Post {
     /// The primary post content. Might be an empty string, if there are embeds.
     pub text: String,
     /// DEPRECATED: replaced by app.bsky.richtext.facet.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub entities: Option<Vec<EntityRef>>,
     /// Annotations of text (mentions, URLs, hashtags, .etc)
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -37,11 +49,82 @@ pub struct Post {
     pub tags: Option<Vec<String>>,
 }
 
+impl Validate for Post {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        StringRules {
+            max_length: Some(POST_MAX_LENGTH),
+            max_graphemes: Some(POST_MAX_GRAPHEMES),
+            ..Default::default()
+        }
+        .check(&field(path, "text"), &self.text, errors);
+        self.facets.validate_at(&field(path, "facets"), errors);
+        self.reply.validate_at(&field(path, "reply"), errors);
+        self.embed.validate_at(&field(path, "embed"), errors);
+        if let Some(langs) = &self.langs {
+            let path = &field(path, "langs");
+            ArrayRules {
+                max_length: Some(3),
+                ..Default::default()
+            }
+            .check(path, langs.len(), errors);
+            for (i, lang) in langs.iter().enumerate() {
+                StringRules {
+                    format: Some(Format::Language),
+                    ..Default::default()
+                }
+                .check(&index(path, i), lang, errors);
+            }
+        }
+        self.labels.validate_at(&field(path, "labels"), errors);
+        if let Some(tags) = &self.tags {
+            let path = &field(path, "tags");
+            ArrayRules {
+                max_length: Some(8),
+                ..Default::default()
+            }
+            .check(path, tags.len(), errors);
+            for (i, tag) in tags.iter().enumerate() {
+                StringRules {
+                    max_length: Some(640),
+                    max_graphemes: Some(64),
+                    ..Default::default()
+                }
+                .check(&index(path, i), tag, errors);
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(tag = "$type")]
 pub enum PostLabels {
     #[serde(rename = "com.atproto.label.defs#selfLabels")]
     SelfLabels(SelfLabels),
+    /// A `$type` this union doesn't know, kept as-is so it round-trips unchanged. Known types
+    /// that don't parse are errors rather than landing here.
+    #[serde(untagged)]
+    Unknown {
+        #[serde(
+            rename = "$type",
+            deserialize_with = "crate::open_union::unknown_type::<PostLabels, _>"
+        )]
+        r#type: String,
+        #[serde(flatten)]
+        data: Value,
+    },
+}
+
+impl OpenUnion for PostLabels {
+    const KNOWN_TYPES: &'static [&'static str] = &["com.atproto.label.defs#selfLabels"];
+}
+
+impl Validate for PostLabels {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        match self {
+            PostLabels::SelfLabels(labels) => labels.validate_at(path, errors),
+            PostLabels::Unknown { .. } => {}
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -79,6 +162,129 @@ pub struct FeedViewPost {
     pub feed_context: Option<String>,
 }
 
+/// A post in a feed generator's skeleton, which the AppView hydrates into a `FeedViewPost`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkeletonFeedPost {
+    pub post: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<SkeletonReason>,
+    /// Context that will be passed through to client and may be passed to feed generator back
+    /// alongside interactions.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feed_context: Option<String>,
+}
+
+impl Validate for SkeletonFeedPost {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        StringRules {
+            format: Some(Format::AtUri),
+            ..Default::default()
+        }
+        .check(&field(path, "post"), &self.post, errors);
+        self.reason.validate_at(&field(path, "reason"), errors);
+        if let Some(feed_context) = &self.feed_context {
+            StringRules {
+                max_length: Some(2000),
+                ..Default::default()
+            }
+            .check(&field(path, "feedContext"), feed_context, errors);
+        }
+    }
+}
+
+/// Why a post is in a feed skeleton, when it isn't simply there on its own merits.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "$type")]
+pub enum SkeletonReason {
+    #[serde(rename = "app.bsky.feed.defs#skeletonReasonRepost")]
+    SkeletonReasonRepost(SkeletonReasonRepost),
+    #[serde(rename = "app.bsky.feed.defs#skeletonReasonPin")]
+    SkeletonReasonPin,
+    /// A `$type` this union doesn't know, kept as-is so it round-trips unchanged. Known types
+    /// that don't parse are errors rather than landing here.
+    #[serde(untagged)]
+    Unknown {
+        #[serde(
+            rename = "$type",
+            deserialize_with = "crate::open_union::unknown_type::<SkeletonReason, _>"
+        )]
+        r#type: String,
+        #[serde(flatten)]
+        data: Value,
+    },
+}
+
+impl OpenUnion for SkeletonReason {
+    const KNOWN_TYPES: &'static [&'static str] = &[
+        "app.bsky.feed.defs#skeletonReasonRepost",
+        "app.bsky.feed.defs#skeletonReasonPin",
+    ];
+}
+
+impl Validate for SkeletonReason {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        match self {
+            SkeletonReason::SkeletonReasonRepost(reason) => StringRules {
+                format: Some(Format::AtUri),
+                ..Default::default()
+            }
+            .check(&field(path, "repost"), &reason.repost, errors),
+            SkeletonReason::SkeletonReasonPin | SkeletonReason::Unknown { .. } => {}
+        }
+    }
+}
+
+/// The post is in the feed because the account behind `repost` reposted it.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct SkeletonReasonRepost {
+    pub repost: String,
+}
+
+/// Something a viewer did with a feed item, sent back to the feed generator that served it.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Interaction {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item: Option<String>,
+    /// eg `app.bsky.feed.defs#requestLess`, `#clickthroughItem` or `#interactionLike`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event: Option<String>,
+    /// Context on a feed item that was originally supplied by the feed generator on
+    /// getFeedSkeleton.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feed_context: Option<String>,
+    /// Unique identifier per request that may be passed back alongside interactions.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub req_id: Option<String>,
+}
+
+impl Validate for Interaction {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        if let Some(item) = &self.item {
+            StringRules {
+                format: Some(Format::AtUri),
+                ..Default::default()
+            }
+            .check(&field(path, "item"), item, errors);
+        }
+        if let Some(feed_context) = &self.feed_context {
+            StringRules {
+                max_length: Some(2000),
+                ..Default::default()
+            }
+            .check(&field(path, "feedContext"), feed_context, errors);
+        }
+        if let Some(req_id) = &self.req_id {
+            StringRules {
+                max_length: Some(100),
+                ..Default::default()
+            }
+            .check(&field(path, "reqId"), req_id, errors);
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct AuthorFeed {
     pub cursor: Option<String>,
@@ -94,15 +300,6 @@ pub struct GetLikesLike {
     pub actor: ProfileView,
 }
 
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
-#[serde(tag = "$type")]
-#[serde(rename = "app.bsky.feed.repost")]
-#[serde(rename_all = "camelCase")]
-pub struct Repost {
-    pub created_at: String,
-    pub subject: StrongRef,
-}
-
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ReplyRefView {
@@ -118,22 +315,51 @@ pub struct ReplyRef {
     pub parent: StrongRef,
 }
 
+impl Validate for ReplyRef {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        self.root.validate_at(&field(path, "root"), errors);
+        self.parent.validate_at(&field(path, "parent"), errors);
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "$type")]
 pub enum ReplyRefUnion {
-    #[serde(rename = "#postView")]
+    #[serde(rename = "app.bsky.feed.defs#postView")]
     PostView(Box<PostView>),
-    #[serde(rename = "#notFoundPost")]
+    #[serde(rename = "app.bsky.feed.defs#notFoundPost")]
     NotFoundPost(NotFoundPost),
-    #[serde(rename = "#blockedPost")]
+    #[serde(rename = "app.bsky.feed.defs#blockedPost")]
     BlockedPost(Box<BlockedPost>),
+    /// A `$type` this union doesn't know, kept as-is so it round-trips unchanged. Known types
+    /// that don't parse are errors rather than landing here.
+    #[serde(untagged)]
+    Unknown {
+        #[serde(
+            rename = "$type",
+            deserialize_with = "crate::open_union::unknown_type::<ReplyRefUnion, _>"
+        )]
+        r#type: String,
+        #[serde(flatten)]
+        data: Value,
+    },
+}
+
+impl OpenUnion for ReplyRefUnion {
+    const KNOWN_TYPES: &'static [&'static str] = &[
+        "app.bsky.feed.defs#postView",
+        "app.bsky.feed.defs#notFoundPost",
+        "app.bsky.feed.defs#blockedPost",
+    ];
 }
 
 impl ReplyRefUnion {
-    pub fn uri(&self) -> &str {
+    pub fn uri(&self) -> Option<&str> {
         match self {
-            ReplyRefUnion::PostView(post) => post.uri.as_str(),
-            ReplyRefUnion::NotFoundPost(post) => post.uri.as_str(),
-            ReplyRefUnion::BlockedPost(post) => post.uri.as_str(),
+            ReplyRefUnion::PostView(post) => Some(post.uri.as_str()),
+            ReplyRefUnion::NotFoundPost(post) => Some(post.uri.as_str()),
+            ReplyRefUnion::BlockedPost(post) => Some(post.uri.as_str()),
+            ReplyRefUnion::Unknown { data, .. } => data.get("uri").and_then(Value::as_str),
         }
     }
 }
@@ -227,11 +453,31 @@ pub struct GeneratorViewState {
 #[serde(tag = "$type")]
 pub enum ThreadViewPostEnum {
     #[serde(rename = "app.bsky.feed.defs#threadViewPost")]
-    ThreadViewPost(ThreadViewPost),
+    ThreadViewPost(Box<ThreadViewPost>),
     #[serde(rename = "app.bsky.feed.defs#notFoundPost")]
     NotFoundPost(NotFoundPost),
     #[serde(rename = "app.bsky.feed.defs#blockedPost")]
     BlockedPost(Box<BlockedPost>),
+    /// A `$type` this union doesn't know, kept as-is so it round-trips unchanged. Known types
+    /// that don't parse are errors rather than landing here.
+    #[serde(untagged)]
+    Unknown {
+        #[serde(
+            rename = "$type",
+            deserialize_with = "crate::open_union::unknown_type::<ThreadViewPostEnum, _>"
+        )]
+        r#type: String,
+        #[serde(flatten)]
+        data: Value,
+    },
+}
+
+impl OpenUnion for ThreadViewPostEnum {
+    const KNOWN_TYPES: &'static [&'static str] = &[
+        "app.bsky.feed.defs#threadViewPost",
+        "app.bsky.feed.defs#notFoundPost",
+        "app.bsky.feed.defs#blockedPost",
+    ];
 }
 
 ///api.bsky.feed.getPostThread
@@ -244,3 +490,128 @@ pub struct GetPostThread {
 pub struct GetPostThreadOutput {
     pub thread: ThreadViewPostEnum,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::bsky::richtext::Features;
+    use serde_json::json;
+
+    #[test]
+    fn keeps_unknown_union_members() {
+        let json = json!({
+            "$type": "app.bsky.feed.post",
+            "createdAt": "2024-11-05T12:00:00.000Z",
+            "text": "$RSKY to the moon",
+            "facets": [{
+                "index": { "byteStart": 0, "byteEnd": 5 },
+                "features": [{ "$type": "com.example.richtext.facet#cashtag", "ticker": "RSKY" }]
+            }],
+            "embed": {
+                "$type": "com.example.embed.poll",
+                "options": ["yes", "no"],
+                "closesAt": "2024-11-06T12:00:00.000Z"
+            }
+        });
+        let post: Post = serde_json::from_value(json.clone()).unwrap();
+        let Some(Embeds::Unknown { r#type, data }) = &post.embed else {
+            panic!("expected an unknown embed, got {:?}", post.embed);
+        };
+        assert_eq!(r#type, "com.example.embed.poll");
+        assert_eq!(data["options"], json!(["yes", "no"]));
+        assert!(matches!(
+            &post.facets.as_ref().unwrap()[0].features[0],
+            Features::Unknown { r#type, .. } if r#type == "com.example.richtext.facet#cashtag"
+        ));
+        assert!(post.validate().is_ok());
+        assert_eq!(serde_json::to_value(&post.embed).unwrap(), json["embed"]);
+        assert_eq!(serde_json::to_value(&post.facets).unwrap(), json["facets"]);
+    }
+
+    #[test]
+    fn rejects_known_union_members_that_dont_parse() {
+        let post = serde_json::from_value::<Post>(json!({
+            "$type": "app.bsky.feed.post",
+            "createdAt": "2024-11-05T12:00:00.000Z",
+            "text": "look",
+            "embed": { "$type": "app.bsky.embed.images", "pictures": [] }
+        }));
+        assert!(post.is_err());
+    }
+
+    #[test]
+    fn validates_post_constraints() {
+        let post: Post = serde_json::from_value(json!({
+            "$type": "app.bsky.feed.post",
+            "createdAt": "2024-11-05T12:00:00.000Z",
+            "text": "hello",
+            "langs": ["en", "not a language"],
+            "reply": {
+                "root": { "uri": "at://did:plc:abc/app.bsky.feed.post/3k", "cid": "nope" },
+                "parent": { "uri": "at://did:plc:abc/app.bsky.feed.post/3k", "cid": "nope" }
+            },
+            "tags": ["a".repeat(65)]
+        }))
+        .unwrap();
+        let errors = post.validate().unwrap_err().0;
+        let paths: Vec<&str> = errors.iter().map(|error| error.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            ["reply.root.cid", "reply.parent.cid", "langs[1]", "tags[0]"]
+        );
+
+        let post = Post {
+            text: "🇺🇦".repeat(301),
+            langs: None,
+            reply: None,
+            tags: None,
+            ..post
+        };
+        assert_eq!(
+            post.validate().unwrap_err().to_string(),
+            "text: must be at most 300 graphemes long, is 301"
+        );
+    }
+
+    #[test]
+    fn parses_skeleton_reasons() {
+        let feed: Vec<SkeletonFeedPost> = serde_json::from_value(json!([
+            {
+                "post": "at://did:plc:ewvi7nxzyoun6zhxrhs64oiz/app.bsky.feed.post/3l6oveex3ii2l",
+                "reason": {
+                    "$type": "app.bsky.feed.defs#skeletonReasonRepost",
+                    "repost": "at://did:plc:6wthaiuqiys3y7eztkpsdam2/app.bsky.feed.repost/3latjd3mzpk2w"
+                }
+            },
+            {
+                "post": "at://did:plc:cimwguwdlh2i2mebdqczgcyl/app.bsky.feed.post/3l4pi6irzsg2m",
+                "reason": { "$type": "app.bsky.feed.defs#skeletonReasonPin" }
+            },
+            {
+                "post": "not an at-uri",
+                "reason": { "$type": "app.bsky.feed.defs#skeletonReasonPin" }
+            }
+        ]))
+        .unwrap();
+        let reasons: Vec<_> = feed
+            .iter()
+            .map(|post| post.reason.as_ref().unwrap())
+            .collect();
+        assert!(matches!(
+            reasons[0],
+            SkeletonReason::SkeletonReasonRepost(_)
+        ));
+        assert_eq!(reasons[1], &SkeletonReason::SkeletonReasonPin);
+        // A known reason missing its fields is an error rather than an unknown reason
+        assert!(serde_json::from_value::<SkeletonReason>(
+            json!({ "$type": "app.bsky.feed.defs#skeletonReasonRepost" })
+        )
+        .is_err());
+        let unknown: SkeletonReason =
+            serde_json::from_value(json!({ "$type": "com.example.feed#skeletonReasonQuote" }))
+                .unwrap();
+        assert!(matches!(unknown, SkeletonReason::Unknown { .. }));
+        assert!(feed[..2].iter().all(|post| post.validate().is_ok()));
+        assert_eq!(feed[2].validate().unwrap_err().0[0].path, "post");
+    }
+}
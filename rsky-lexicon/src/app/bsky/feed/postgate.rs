@@ -0,0 +1,105 @@
+// Generated by rsky-lexgen from lexicons/app/bsky/feed/postgate.json.
+// Do not edit by hand: update the lexicon and run `cargo run -p rsky-lexgen` instead.
+
+use crate::validation::{field, index, ArrayRules, Format, StringRules, Validate, ValidationError};
+
+/// Record defining interaction rules for a post. The record key (rkey) of the postgate record must
+/// match the record key of the post, and that record must be in the same repository.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "$type")]
+#[serde(rename = "app.bsky.feed.postgate")]
+#[serde(rename_all = "camelCase")]
+pub struct Postgate {
+    pub created_at: String,
+    /// Reference (AT-URI) to the post record.
+    pub post: String,
+    /// List of AT-URIs embedding this post that the author has detached from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detached_embedding_uris: Option<Vec<String>>,
+    /// List of rules defining who can embed this post. If value is an empty array or is undefined,
+    /// no particular rules apply and anyone can embed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub embedding_rules: Option<Vec<PostgateEmbeddingRule>>,
+}
+
+impl Validate for Postgate {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        StringRules {
+            format: Some(Format::Datetime),
+            ..Default::default()
+        }
+        .check(&field(path, "createdAt"), &self.created_at, errors);
+        StringRules {
+            format: Some(Format::AtUri),
+            ..Default::default()
+        }
+        .check(&field(path, "post"), &self.post, errors);
+        if let Some(value) = &self.detached_embedding_uris {
+            let path = &field(path, "detachedEmbeddingUris");
+            ArrayRules {
+                max_length: Some(50),
+                ..Default::default()
+            }
+            .check(path, value.len(), errors);
+            for (i, value) in value.iter().enumerate() {
+                StringRules {
+                    format: Some(Format::AtUri),
+                    ..Default::default()
+                }
+                .check(&index(path, i), value, errors);
+            }
+        }
+        if let Some(value) = &self.embedding_rules {
+            let path = &field(path, "embeddingRules");
+            ArrayRules {
+                max_length: Some(5),
+                ..Default::default()
+            }
+            .check(path, value.len(), errors);
+            for (i, value) in value.iter().enumerate() {
+                value.validate_at(&index(path, i), errors);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "$type")]
+pub enum PostgateEmbeddingRule {
+    /// Disables the ability to embed this post.
+    #[serde(rename = "app.bsky.feed.postgate#disableRule")]
+    DisableRule,
+    /// A `$type` this union doesn't know, kept as-is so it round-trips unchanged. Known types
+    /// that don't parse are errors rather than landing here.
+    #[serde(untagged)]
+    Unknown {
+        #[serde(
+            rename = "$type",
+            deserialize_with = "crate::open_union::unknown_type::<PostgateEmbeddingRule, _>"
+        )]
+        r#type: String,
+        #[serde(flatten)]
+        data: serde_json::Value,
+    },
+}
+
+impl crate::open_union::OpenUnion for PostgateEmbeddingRule {
+    const KNOWN_TYPES: &'static [&'static str] = &["app.bsky.feed.postgate#disableRule"];
+}
+
+impl Validate for PostgateEmbeddingRule {
+    fn validate_at(&self, _path: &str, _errors: &mut Vec<ValidationError>) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_samples() {
+        let samples = crate::test_vectors(include_str!(
+            "../../../../test-vectors/app/bsky/feed/postgate.json"
+        ));
+        crate::assert_round_trips::<Postgate>(&samples["main"]);
+    }
+}
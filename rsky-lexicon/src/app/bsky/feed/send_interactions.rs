@@ -0,0 +1,47 @@
+// Generated by rsky-lexgen from lexicons/app/bsky/feed/sendInteractions.json.
+// Do not edit by hand: update the lexicon and run `cargo run -p rsky-lexgen` instead.
+
+use crate::app::bsky::feed::Interaction;
+use crate::validation::{field, index, Validate, ValidationError};
+
+pub const NSID: &str = "app.bsky.feed.sendInteractions";
+
+/// Send information about interactions with feed items back to the feed generator that served them.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SendInteractionsInput {
+    pub interactions: Vec<Interaction>,
+}
+
+impl Validate for SendInteractionsInput {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        {
+            let path = &field(path, "interactions");
+            for (i, value) in self.interactions.iter().enumerate() {
+                value.validate_at(&index(path, i), errors);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SendInteractionsOutput {}
+
+impl Validate for SendInteractionsOutput {
+    fn validate_at(&self, _path: &str, _errors: &mut Vec<ValidationError>) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_samples() {
+        let samples = crate::test_vectors(include_str!(
+            "../../../../test-vectors/app/bsky/feed/sendInteractions.json"
+        ));
+        crate::assert_round_trips::<SendInteractionsInput>(&samples["input"]);
+        crate::assert_round_trips::<SendInteractionsOutput>(&samples["output"]);
+    }
+}
@@ -0,0 +1,134 @@
+// Generated by rsky-lexgen from lexicons/app/bsky/feed/threadgate.json.
+// Do not edit by hand: update the lexicon and run `cargo run -p rsky-lexgen` instead.
+
+use crate::validation::{field, index, ArrayRules, Format, StringRules, Validate, ValidationError};
+
+/// Record defining interaction gating rules for a thread (aka, reply controls). The record key for
+/// the thread gate record must match the record key for the thread root post, and that record must
+/// be in the same repository.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "$type")]
+#[serde(rename = "app.bsky.feed.threadgate")]
+#[serde(rename_all = "camelCase")]
+pub struct Threadgate {
+    /// Reference (AT-URI) to the post record.
+    pub post: String,
+    /// List of rules defining who can reply to this post. If value is an empty array, no one can
+    /// reply. If value is undefined, anyone can reply.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow: Option<Vec<ThreadgateRule>>,
+    pub created_at: String,
+    /// List of hidden reply URIs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hidden_replies: Option<Vec<String>>,
+}
+
+impl Validate for Threadgate {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        StringRules {
+            format: Some(Format::AtUri),
+            ..Default::default()
+        }
+        .check(&field(path, "post"), &self.post, errors);
+        if let Some(value) = &self.allow {
+            let path = &field(path, "allow");
+            ArrayRules {
+                max_length: Some(5),
+                ..Default::default()
+            }
+            .check(path, value.len(), errors);
+            for (i, value) in value.iter().enumerate() {
+                value.validate_at(&index(path, i), errors);
+            }
+        }
+        StringRules {
+            format: Some(Format::Datetime),
+            ..Default::default()
+        }
+        .check(&field(path, "createdAt"), &self.created_at, errors);
+        if let Some(value) = &self.hidden_replies {
+            let path = &field(path, "hiddenReplies");
+            ArrayRules {
+                max_length: Some(50),
+                ..Default::default()
+            }
+            .check(path, value.len(), errors);
+            for (i, value) in value.iter().enumerate() {
+                StringRules {
+                    format: Some(Format::AtUri),
+                    ..Default::default()
+                }
+                .check(&index(path, i), value, errors);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "$type")]
+pub enum ThreadgateRule {
+    /// Allow replies from actors mentioned in your post.
+    #[serde(rename = "app.bsky.feed.threadgate#mentionRule")]
+    MentionRule,
+    /// Allow replies from actors who follow you.
+    #[serde(rename = "app.bsky.feed.threadgate#followerRule")]
+    FollowerRule,
+    /// Allow replies from actors you follow.
+    #[serde(rename = "app.bsky.feed.threadgate#followingRule")]
+    FollowingRule,
+    /// Allow replies from actors on a list.
+    #[serde(rename = "app.bsky.feed.threadgate#listRule")]
+    ListRule { list: String },
+    /// A `$type` this union doesn't know, kept as-is so it round-trips unchanged. Known types
+    /// that don't parse are errors rather than landing here.
+    #[serde(untagged)]
+    Unknown {
+        #[serde(
+            rename = "$type",
+            deserialize_with = "crate::open_union::unknown_type::<ThreadgateRule, _>"
+        )]
+        r#type: String,
+        #[serde(flatten)]
+        data: serde_json::Value,
+    },
+}
+
+impl crate::open_union::OpenUnion for ThreadgateRule {
+    const KNOWN_TYPES: &'static [&'static str] = &[
+        "app.bsky.feed.threadgate#mentionRule",
+        "app.bsky.feed.threadgate#followerRule",
+        "app.bsky.feed.threadgate#followingRule",
+        "app.bsky.feed.threadgate#listRule",
+    ];
+}
+
+impl Validate for ThreadgateRule {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        match self {
+            ThreadgateRule::MentionRule => {}
+            ThreadgateRule::FollowerRule => {}
+            ThreadgateRule::FollowingRule => {}
+            ThreadgateRule::ListRule { list } => {
+                StringRules {
+                    format: Some(Format::AtUri),
+                    ..Default::default()
+                }
+                .check(&field(path, "list"), list, errors);
+            }
+            ThreadgateRule::Unknown { .. } => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_samples() {
+        let samples = crate::test_vectors(include_str!(
+            "../../../../test-vectors/app/bsky/feed/threadgate.json"
+        ));
+        crate::assert_round_trips::<Threadgate>(&samples["main"]);
+    }
+}
@@ -0,0 +1,44 @@
+// Generated by rsky-lexgen from lexicons/app/bsky/graph/block.json.
+// Do not edit by hand: update the lexicon and run `cargo run -p rsky-lexgen` instead.
+
+use crate::validation::{field, Format, StringRules, Validate, ValidationError};
+
+/// Record declaring a 'block' relationship against another account. NOTE: blocks are public in
+/// Bluesky; see blog posts for details.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "$type")]
+#[serde(rename = "app.bsky.graph.block")]
+#[serde(rename_all = "camelCase")]
+pub struct Block {
+    /// DID of the account to be blocked.
+    pub subject: String,
+    pub created_at: String,
+}
+
+impl Validate for Block {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        StringRules {
+            format: Some(Format::Did),
+            ..Default::default()
+        }
+        .check(&field(path, "subject"), &self.subject, errors);
+        StringRules {
+            format: Some(Format::Datetime),
+            ..Default::default()
+        }
+        .check(&field(path, "createdAt"), &self.created_at, errors);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_samples() {
+        let samples = crate::test_vectors(include_str!(
+            "../../../../test-vectors/app/bsky/graph/block.json"
+        ));
+        crate::assert_round_trips::<Block>(&samples["main"]);
+    }
+}
@@ -1,10 +1,43 @@
-/// Record declaring a social 'follow' relationship of another account.
-/// Duplicate follows will be ignored by the AppView.
+// Generated by rsky-lexgen from lexicons/app/bsky/graph/follow.json.
+// Do not edit by hand: update the lexicon and run `cargo run -p rsky-lexgen` instead.
+
+use crate::validation::{field, Format, StringRules, Validate, ValidationError};
+
+/// Record declaring a social 'follow' relationship of another account. Duplicate follows will be
+/// ignored by the AppView.
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(tag = "$type")]
 #[serde(rename = "app.bsky.graph.follow")]
 #[serde(rename_all = "camelCase")]
 pub struct Follow {
-    pub created_at: String,
     pub subject: String,
+    pub created_at: String,
+}
+
+impl Validate for Follow {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        StringRules {
+            format: Some(Format::Did),
+            ..Default::default()
+        }
+        .check(&field(path, "subject"), &self.subject, errors);
+        StringRules {
+            format: Some(Format::Datetime),
+            ..Default::default()
+        }
+        .check(&field(path, "createdAt"), &self.created_at, errors);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_samples() {
+        let samples = crate::test_vectors(include_str!(
+            "../../../../test-vectors/app/bsky/graph/follow.json"
+        ));
+        crate::assert_round_trips::<Follow>(&samples["main"]);
+    }
 }
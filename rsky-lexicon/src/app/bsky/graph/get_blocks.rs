@@ -0,0 +1,62 @@
+// Generated by rsky-lexgen from lexicons/app/bsky/graph/getBlocks.json.
+// Do not edit by hand: update the lexicon and run `cargo run -p rsky-lexgen` instead.
+
+use crate::app::bsky::actor::ProfileView;
+use crate::validation::{field, index, IntegerRules, Validate, ValidationError};
+
+pub const NSID: &str = "app.bsky.graph.getBlocks";
+
+/// Enumerates which accounts the requesting account is currently blocking. Requires auth.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetBlocksParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+}
+
+impl Validate for GetBlocksParams {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        if let Some(value) = &self.limit {
+            IntegerRules {
+                minimum: Some(1),
+                maximum: Some(100),
+            }
+            .check(&field(path, "limit"), *value as i64, errors);
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetBlocksOutput {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    pub blocks: Vec<ProfileView>,
+}
+
+impl Validate for GetBlocksOutput {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        {
+            let path = &field(path, "blocks");
+            for (i, value) in self.blocks.iter().enumerate() {
+                value.validate_at(&index(path, i), errors);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_samples() {
+        let samples = crate::test_vectors(include_str!(
+            "../../../../test-vectors/app/bsky/graph/getBlocks.json"
+        ));
+        crate::assert_round_trips::<GetBlocksParams>(&samples["params"]);
+        crate::assert_round_trips::<GetBlocksOutput>(&samples["output"]);
+    }
+}
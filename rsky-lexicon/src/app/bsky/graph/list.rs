@@ -0,0 +1,116 @@
+// Generated by rsky-lexgen from lexicons/app/bsky/graph/list.json.
+// Do not edit by hand: update the lexicon and run `cargo run -p rsky-lexgen` instead.
+
+use crate::app::bsky::graph::ListPurpose;
+use crate::app::bsky::richtext::Facet;
+use crate::com::atproto::label::SelfLabels;
+use crate::com::atproto::repo::Blob;
+use crate::validation::{field, index, BlobRules, Format, StringRules, Validate, ValidationError};
+
+/// Record representing a list of accounts (actors). Scope includes both moderation-oriented lists
+/// and curration-oriented lists.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "$type")]
+#[serde(rename = "app.bsky.graph.list")]
+#[serde(rename_all = "camelCase")]
+pub struct List {
+    /// Defines the purpose of the list (aka, moderation-oriented or curration-oriented)
+    pub purpose: ListPurpose,
+    /// Display name for list; can not be empty.
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description_facets: Option<Vec<Facet>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avatar: Option<Blob>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub labels: Option<ListLabels>,
+    pub created_at: String,
+}
+
+impl Validate for List {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        self.purpose.validate_at(&field(path, "purpose"), errors);
+        StringRules {
+            min_length: Some(1),
+            max_length: Some(64),
+            ..Default::default()
+        }
+        .check(&field(path, "name"), &self.name, errors);
+        if let Some(value) = &self.description {
+            StringRules {
+                max_length: Some(3000),
+                max_graphemes: Some(300),
+                ..Default::default()
+            }
+            .check(&field(path, "description"), value, errors);
+        }
+        if let Some(value) = &self.description_facets {
+            let path = &field(path, "descriptionFacets");
+            for (i, value) in value.iter().enumerate() {
+                value.validate_at(&index(path, i), errors);
+            }
+        }
+        if let Some(value) = &self.avatar {
+            BlobRules {
+                accept: &["image/png", "image/jpeg"],
+                max_size: Some(1000000),
+            }
+            .check(&field(path, "avatar"), value, errors);
+        }
+        if let Some(value) = &self.labels {
+            value.validate_at(&field(path, "labels"), errors);
+        }
+        StringRules {
+            format: Some(Format::Datetime),
+            ..Default::default()
+        }
+        .check(&field(path, "createdAt"), &self.created_at, errors);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "$type")]
+pub enum ListLabels {
+    #[serde(rename = "com.atproto.label.defs#selfLabels")]
+    SelfLabels(SelfLabels),
+    /// A `$type` this union doesn't know, kept as-is so it round-trips unchanged. Known types
+    /// that don't parse are errors rather than landing here.
+    #[serde(untagged)]
+    Unknown {
+        #[serde(
+            rename = "$type",
+            deserialize_with = "crate::open_union::unknown_type::<ListLabels, _>"
+        )]
+        r#type: String,
+        #[serde(flatten)]
+        data: serde_json::Value,
+    },
+}
+
+impl crate::open_union::OpenUnion for ListLabels {
+    const KNOWN_TYPES: &'static [&'static str] = &["com.atproto.label.defs#selfLabels"];
+}
+
+impl Validate for ListLabels {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        match self {
+            ListLabels::SelfLabels(value) => value.validate_at(path, errors),
+            ListLabels::Unknown { .. } => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_samples() {
+        let samples = crate::test_vectors(include_str!(
+            "../../../../test-vectors/app/bsky/graph/list.json"
+        ));
+        crate::assert_round_trips::<List>(&samples["main"]);
+    }
+}
@@ -0,0 +1,51 @@
+// Generated by rsky-lexgen from lexicons/app/bsky/graph/listitem.json.
+// Do not edit by hand: update the lexicon and run `cargo run -p rsky-lexgen` instead.
+
+use crate::validation::{field, Format, StringRules, Validate, ValidationError};
+
+/// Record representing an account's inclusion on a specific list. The AppView will ignore duplicate
+/// listitem records.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "$type")]
+#[serde(rename = "app.bsky.graph.listitem")]
+#[serde(rename_all = "camelCase")]
+pub struct ListItem {
+    /// The account which is included on the list.
+    pub subject: String,
+    /// Reference (AT-URI) to the list record (app.bsky.graph.list).
+    pub list: String,
+    pub created_at: String,
+}
+
+impl Validate for ListItem {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        StringRules {
+            format: Some(Format::Did),
+            ..Default::default()
+        }
+        .check(&field(path, "subject"), &self.subject, errors);
+        StringRules {
+            format: Some(Format::AtUri),
+            ..Default::default()
+        }
+        .check(&field(path, "list"), &self.list, errors);
+        StringRules {
+            format: Some(Format::Datetime),
+            ..Default::default()
+        }
+        .check(&field(path, "createdAt"), &self.created_at, errors);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_samples() {
+        let samples = crate::test_vectors(include_str!(
+            "../../../../test-vectors/app/bsky/graph/listitem.json"
+        ));
+        crate::assert_round_trips::<ListItem>(&samples["main"]);
+    }
+}
@@ -1,8 +1,14 @@
+pub mod block;
 pub mod follow;
+pub mod get_blocks;
+pub mod list;
+pub mod listitem;
+pub mod mute_actor;
 
 use crate::app::bsky::actor::{ProfileView, ProfileViewBasic};
 use crate::app::bsky::richtext::Facet;
 use crate::com::atproto::label::Label;
+use crate::validation::{Validate, ValidationError};
 use serde_json::Value;
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -67,6 +73,10 @@ pub enum ListPurpose {
     ReferenceList,
 }
 
+impl Validate for ListPurpose {
+    fn validate_at(&self, _path: &str, _errors: &mut Vec<ValidationError>) {}
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct ListViewerState {
     pub muted: Option<bool>,
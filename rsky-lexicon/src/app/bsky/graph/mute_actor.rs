@@ -0,0 +1,37 @@
+// Generated by rsky-lexgen from lexicons/app/bsky/graph/muteActor.json.
+// Do not edit by hand: update the lexicon and run `cargo run -p rsky-lexgen` instead.
+
+use crate::validation::{field, Format, StringRules, Validate, ValidationError};
+
+pub const NSID: &str = "app.bsky.graph.muteActor";
+
+/// Creates a mute relationship for the specified account. Mutes are private in Bluesky. Requires
+/// auth.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MuteActorInput {
+    pub actor: String,
+}
+
+impl Validate for MuteActorInput {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        StringRules {
+            format: Some(Format::AtIdentifier),
+            ..Default::default()
+        }
+        .check(&field(path, "actor"), &self.actor, errors);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_samples() {
+        let samples = crate::test_vectors(include_str!(
+            "../../../../test-vectors/app/bsky/graph/muteActor.json"
+        ));
+        crate::assert_round_trips::<MuteActorInput>(&samples["input"]);
+    }
+}
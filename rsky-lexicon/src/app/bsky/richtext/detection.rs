@@ -0,0 +1,191 @@
+//! Finds mentions, links and hashtags in plain text, following the rules the Bluesky app uses
+//! when it builds facets for a new post. Every match comes with the UTF-8 byte range it covers.
+use crate::app::bsky::richtext::ByteSlice;
+use lazy_static::lazy_static;
+use regex::Regex;
+use rsky_syntax::handle::Handle;
+use std::collections::HashSet;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Longest hashtag the `app.bsky.richtext.facet#tag` lexicon allows, in graphemes
+pub const TAG_MAX_GRAPHEMES: usize = 64;
+
+lazy_static! {
+    static ref MENTION_RE: Regex = Regex::new(r"(?:^|\s|\()(@)([a-zA-Z0-9.-]+)\b").unwrap();
+    static ref URL_RE: Regex = Regex::new(
+        r"(?i)(?:^|\s|\()((?:https?://\S+)|(?P<domain>[a-z][a-z0-9]*(?:\.[a-z0-9]+)+)\S*)"
+    )
+    .unwrap();
+    static ref TAG_RE: Regex = Regex::new(
+        r"(?:^|\s)([#＃])([^\s\u{00AD}\u{2060}\u{200A}\u{200B}\u{200C}\u{200D}\u{20E2}]+)"
+    )
+    .unwrap();
+    static ref TRAILING_PUNCTUATION_RE: Regex = Regex::new(r"\p{P}+$").unwrap();
+    static ref TAG_TEXT_RE: Regex = Regex::new(r"[^0-9\p{P}]").unwrap();
+    static ref TLDS: HashSet<&'static str> = include_str!("tlds.txt")
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .collect();
+}
+
+/// `@handle` mentions. The range covers the `@` and the handle; text that isn't a valid handle,
+/// or uses a TLD that can't be registered, isn't a mention.
+pub fn detect_mentions(text: &str) -> Vec<(ByteSlice, Handle)> {
+    MENTION_RE
+        .captures_iter(text)
+        .filter_map(|captures| {
+            let (at, matched) = (captures.get(1)?, captures.get(2)?);
+            let handle = Handle::new(matched.as_str().to_string()).ok()?;
+            if handle.has_disallowed_tld() {
+                return None;
+            }
+            Some((byte_slice(at.start(), matched.end()), handle))
+        })
+        .collect()
+}
+
+/// `http(s)://` URLs, and bare domains such as `example.com/page`, which get an `https://`
+/// prefix. Trailing sentence punctuation, and a closing `)` without an opening one, are left out.
+/// Bare domains must end in a registered TLD, so file names like `notes.txt` aren't links.
+pub fn detect_links(text: &str) -> Vec<(ByteSlice, String)> {
+    URL_RE
+        .captures_iter(text)
+        .filter_map(|captures| {
+            let link = captures.get(1)?;
+            let mut uri = link.as_str();
+            if let Some(domain) = captures.name("domain") {
+                if !is_linkable_domain(domain.as_str()) {
+                    return None;
+                }
+            }
+            if uri.ends_with(['.', ',', ';', ':', '!', '?']) {
+                uri = &uri[..uri.len() - 1];
+            }
+            if uri.ends_with(')') && !uri.contains('(') {
+                uri = &uri[..uri.len() - 1];
+            }
+            let index = byte_slice(link.start(), link.start() + uri.len());
+            match captures.name("domain") {
+                Some(_) => Some((index, format!("https://{uri}"))),
+                None => Some((index, uri.to_string())),
+            }
+        })
+        .collect()
+}
+
+/// `#hashtag`s, returned without the `#`. The range covers the `#` and the tag. Trailing
+/// punctuation is left out, and tags that are only digits or longer than
+/// [`TAG_MAX_GRAPHEMES`] are skipped.
+pub fn detect_tags(text: &str) -> Vec<(ByteSlice, String)> {
+    TAG_RE
+        .captures_iter(text)
+        .filter_map(|captures| {
+            let (hash, tag) = (captures.get(1)?, captures.get(2)?);
+            // A keycap emoji such as #️⃣, not a tag
+            if tag.as_str().starts_with('\u{FE0F}') {
+                return None;
+            }
+            let tag = TRAILING_PUNCTUATION_RE.replace(tag.as_str(), "");
+            if !TAG_TEXT_RE.is_match(&tag) || tag.graphemes(true).count() > TAG_MAX_GRAPHEMES {
+                return None;
+            }
+            Some((
+                byte_slice(hash.start(), hash.end() + tag.len()),
+                tag.into_owned(),
+            ))
+        })
+        .collect()
+}
+
+fn byte_slice(byte_start: usize, byte_end: usize) -> ByteSlice {
+    ByteSlice {
+        byte_start,
+        byte_end,
+    }
+}
+
+fn is_linkable_domain(domain: &str) -> bool {
+    let tld = domain.rsplit('.').next().unwrap_or_default();
+    TLDS.contains(tld.to_ascii_lowercase().as_str())
+        && Handle::new(domain.to_string()).is_ok_and(|domain| !domain.has_disallowed_tld())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spans<T: ToString>(text: &str, detected: Vec<(ByteSlice, T)>) -> Vec<(&str, String)> {
+        detected
+            .into_iter()
+            .map(|(index, value)| (&text[index.byte_start..index.byte_end], value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn detects_mentions() {
+        let text =
+            "hi @Alice.test and (@bob.example.com). not @bad or email@carol.test @dave.local";
+        assert_eq!(
+            spans(text, detect_mentions(text)),
+            vec![
+                ("@Alice.test", "alice.test".to_string()),
+                ("@bob.example.com", "bob.example.com".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn detects_links() {
+        let text = "see https://example.com/a?b=c, example.org/wiki_(x) and (blacksky.app) \
+                    but not e.g. or 1.5 or notes.local";
+        assert_eq!(
+            spans(text, detect_links(text)),
+            vec![
+                (
+                    "https://example.com/a?b=c",
+                    "https://example.com/a?b=c".to_string()
+                ),
+                (
+                    "example.org/wiki_(x)",
+                    "https://example.org/wiki_(x)".to_string()
+                ),
+                ("blacksky.app", "https://blacksky.app".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_unregistered_tlds() {
+        let text = "open notes.txt, photo.JPEG or backup.tar.gz, not Example.COM.";
+        assert_eq!(
+            spans(text, detect_links(text)),
+            vec![("Example.COM", "https://Example.COM".to_string())]
+        );
+        assert!(detect_links("config.yaml README.md.bak v1.2.3").is_empty());
+    }
+
+    #[test]
+    fn detects_tags() {
+        let long = "a".repeat(TAG_MAX_GRAPHEMES + 1);
+        let text = format!("#rust, ＃日本語! #2024 #️⃣ a#b ##double #{long} #v2.");
+        assert_eq!(
+            spans(&text, detect_tags(&text)),
+            vec![
+                ("#rust", "rust".to_string()),
+                ("＃日本語", "日本語".to_string()),
+                ("##double", "#double".to_string()),
+                ("#v2", "v2".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn offsets_count_utf8_bytes() {
+        let text = "✨ café 👩‍👩‍👧 @alice.test #fête";
+        let (index, _) = &detect_mentions(text)[0];
+        assert_eq!((index.byte_start, index.byte_end), (29, 40));
+        let (index, tag) = &detect_tags(text)[0];
+        assert_eq!(&text[index.byte_start..index.byte_end], "#fête");
+        assert_eq!(tag, "fête");
+    }
+}
@@ -1,9 +1,29 @@
+pub mod detection;
+pub mod rich_text;
+
+use crate::open_union::OpenUnion;
+use crate::validation::{field, Format, StringRules, Validate, ValidationError};
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct Facet {
     pub index: ByteSlice,
     pub features: Vec<Features>,
 }
 
+impl Facet {
+    /// The part of `text` this facet covers, or `None` if its indices don't fall on character
+    /// boundaries within `text`.
+    pub fn text<'a>(&self, text: &'a str) -> Option<&'a str> {
+        text.get(self.index.byte_start..self.index.byte_end)
+    }
+}
+
+impl Validate for Facet {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        self.features.validate_at(&field(path, "features"), errors);
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(tag = "$type")]
 pub enum Features {
@@ -13,6 +33,50 @@ pub enum Features {
     Link(Link),
     #[serde(rename = "app.bsky.richtext.facet#tag")]
     Tag(Tag),
+    /// A `$type` this union doesn't know, kept as-is so it round-trips unchanged. Known types
+    /// that don't parse are errors rather than landing here.
+    #[serde(untagged)]
+    Unknown {
+        #[serde(
+            rename = "$type",
+            deserialize_with = "crate::open_union::unknown_type::<Features, _>"
+        )]
+        r#type: String,
+        #[serde(flatten)]
+        data: serde_json::Value,
+    },
+}
+
+impl OpenUnion for Features {
+    const KNOWN_TYPES: &'static [&'static str] = &[
+        "app.bsky.richtext.facet#mention",
+        "app.bsky.richtext.facet#link",
+        "app.bsky.richtext.facet#tag",
+    ];
+}
+
+impl Validate for Features {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        match self {
+            Features::Mention(mention) => StringRules {
+                format: Some(Format::Did),
+                ..Default::default()
+            }
+            .check(&field(path, "did"), &mention.did, errors),
+            Features::Link(link) => StringRules {
+                format: Some(Format::Uri),
+                ..Default::default()
+            }
+            .check(&field(path, "uri"), &link.uri, errors),
+            Features::Tag(tag) => StringRules {
+                max_length: Some(640),
+                max_graphemes: Some(64),
+                ..Default::default()
+            }
+            .check(&field(path, "tag"), &tag.tag, errors),
+            Features::Unknown { .. } => {}
+        }
+    }
 }
 
 /// Facet feature for mention of another account. The text is usually a handle, including a '@'
@@ -0,0 +1,164 @@
+use crate::app::bsky::richtext::detection::{detect_links, detect_mentions, detect_tags};
+use crate::app::bsky::richtext::{Facet, Features, Link, Mention, Tag};
+use anyhow::Result;
+use rsky_syntax::handle::ResolveHandle;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Longest `app.bsky.feed.post` text, in UTF-8 bytes
+pub const POST_MAX_LENGTH: usize = 3000;
+/// Longest `app.bsky.feed.post` text, in graphemes
+pub const POST_MAX_GRAPHEMES: usize = 300;
+
+/// Post text together with its facets, eg for composing a post or reading one back.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RichText {
+    pub text: String,
+    pub facets: Vec<Facet>,
+}
+
+impl RichText {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            facets: Vec::new(),
+        }
+    }
+
+    /// Length in UTF-8 bytes, the unit facet indices use.
+    pub fn len(&self) -> usize {
+        self.text.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Length in user-perceived characters, the unit post limits are mostly counted in.
+    pub fn grapheme_len(&self) -> usize {
+        self.text.graphemes(true).count()
+    }
+
+    /// Whether the text is short enough to post.
+    pub fn fits_post(&self) -> bool {
+        self.len() <= POST_MAX_LENGTH && self.grapheme_len() <= POST_MAX_GRAPHEMES
+    }
+
+    /// Replaces the facets with those detected in the text. Mentions are looked up with
+    /// `resolver` and dropped if their handle doesn't resolve.
+    pub async fn detect_facets(&mut self, resolver: &dyn ResolveHandle) -> Result<()> {
+        let mut facets = Vec::new();
+        for (index, handle) in detect_mentions(&self.text) {
+            if let Some(did) = resolver.resolve_handle(&handle).await? {
+                facets.push(Facet {
+                    index,
+                    features: vec![Features::Mention(Mention {
+                        did: did.to_string(),
+                    })],
+                });
+            }
+        }
+        self.detect_facets_without_resolution();
+        self.facets.extend(facets);
+        self.facets.sort_by_key(|facet| facet.index.byte_start);
+        Ok(())
+    }
+
+    /// Replaces the facets with the links and tags detected in the text. Mentions need a DID,
+    /// so they are left out; see [`RichText::detect_facets`].
+    pub fn detect_facets_without_resolution(&mut self) {
+        let links = detect_links(&self.text)
+            .into_iter()
+            .map(|(index, uri)| Facet {
+                index,
+                features: vec![Features::Link(Link { uri })],
+            });
+        let tags = detect_tags(&self.text)
+            .into_iter()
+            .map(|(index, tag)| Facet {
+                index,
+                features: vec![Features::Tag(Tag { tag })],
+            });
+        self.facets = links.chain(tags).collect();
+        self.facets.sort_by_key(|facet| facet.index.byte_start);
+    }
+
+    /// Each facet with the part of the text it covers. Facets whose indices don't fall on
+    /// character boundaries within the text are skipped.
+    pub fn facet_texts(&self) -> impl Iterator<Item = (&Facet, &str)> {
+        self.facets
+            .iter()
+            .filter_map(|facet| Some((facet, facet.text(&self.text)?)))
+    }
+
+    /// Every hashtag in the facets, without the `#`.
+    pub fn tags(&self) -> impl Iterator<Item = &str> {
+        self.features().filter_map(|feature| match feature {
+            Features::Tag(tag) => Some(tag.tag.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Every link in the facets.
+    pub fn links(&self) -> impl Iterator<Item = &str> {
+        self.features().filter_map(|feature| match feature {
+            Features::Link(link) => Some(link.uri.as_str()),
+            _ => None,
+        })
+    }
+
+    fn features(&self) -> impl Iterator<Item = &Features> {
+        self.facets.iter().flat_map(|facet| &facet.features)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use rsky_syntax::did::Did;
+    use rsky_syntax::handle::Handle;
+
+    struct StaticResolver;
+
+    #[async_trait]
+    impl ResolveHandle for StaticResolver {
+        async fn resolve_handle(&self, handle: &Handle) -> Result<Option<Did>> {
+            match handle.as_str() {
+                "alice.test" => Ok(Some("did:plc:6wthaiuqiys3y7eztkpsdam2".parse()?)),
+                _ => Ok(None),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn detects_and_resolves_facets() {
+        let mut rich_text =
+            RichText::new("gm @alice.test and @ghost.test 🌅 #sunrise via blacksky.app");
+        rich_text.detect_facets(&StaticResolver).await.unwrap();
+        let texts: Vec<&str> = rich_text.facet_texts().map(|(_, text)| text).collect();
+        assert_eq!(texts, vec!["@alice.test", "#sunrise", "blacksky.app"]);
+        assert_eq!(
+            rich_text.facets[0].features,
+            vec![Features::Mention(Mention {
+                did: "did:plc:6wthaiuqiys3y7eztkpsdam2".to_string()
+            })]
+        );
+        assert_eq!(rich_text.tags().collect::<Vec<_>>(), vec!["sunrise"]);
+        assert_eq!(
+            rich_text.links().collect::<Vec<_>>(),
+            vec!["https://blacksky.app"]
+        );
+    }
+
+    #[test]
+    fn counts_graphemes_against_post_limits() {
+        let flags = RichText::new("🏳️‍🌈".repeat(POST_MAX_GRAPHEMES));
+        assert_eq!(flags.grapheme_len(), POST_MAX_GRAPHEMES);
+        assert_eq!(flags.len(), 14 * POST_MAX_GRAPHEMES);
+        assert!(flags.len() > POST_MAX_LENGTH && !flags.fits_post());
+
+        let text = RichText::new("é".repeat(POST_MAX_GRAPHEMES));
+        assert!(text.fits_post());
+        assert!(!RichText::new("é".repeat(POST_MAX_GRAPHEMES + 1)).fits_post());
+    }
+}
@@ -1,6 +1,7 @@
 use crate::app::bsky::embed::record::{Record, View as EmbedRecordView};
 use crate::app::bsky::richtext::Facet;
 use crate::chat::bsky::actor::ProfileViewBasic;
+use crate::open_union::OpenUnion;
 use chrono::{DateTime, Utc};
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -60,6 +61,24 @@ pub struct ConvoView {
 pub enum MessageViewEnum {
     MessageView(Box<MessageView>),
     DeletedMessageView(DeletedMessageView),
+    /// A `$type` this union doesn't know, kept as-is so it round-trips unchanged. Known types
+    /// that don't parse are errors rather than landing here.
+    Unknown {
+        #[serde(
+            rename = "$type",
+            deserialize_with = "crate::open_union::unknown_type::<MessageViewEnum, _>"
+        )]
+        r#type: String,
+        #[serde(flatten)]
+        data: serde_json::Value,
+    },
+}
+
+impl OpenUnion for MessageViewEnum {
+    const KNOWN_TYPES: &'static [&'static str] = &[
+        "chat.bsky.convo.defs#messageView",
+        "chat.bsky.convo.defs#deletedMessageView",
+    ];
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -113,6 +132,26 @@ pub enum LogEnum {
     LogLeaveConvo(LogLeaveConvo),
     LogCreateMessage(LogCreateMessage),
     LogDeleteMessage(LogDeleteMessage),
+    /// A `$type` this union doesn't know, kept as-is so it round-trips unchanged. Known types
+    /// that don't parse are errors rather than landing here.
+    Unknown {
+        #[serde(
+            rename = "$type",
+            deserialize_with = "crate::open_union::unknown_type::<LogEnum, _>"
+        )]
+        r#type: String,
+        #[serde(flatten)]
+        data: serde_json::Value,
+    },
+}
+
+impl OpenUnion for LogEnum {
+    const KNOWN_TYPES: &'static [&'static str] = &[
+        "chat.bsky.convo.defs#logBeginConvo",
+        "chat.bsky.convo.defs#logLeaveConvo",
+        "chat.bsky.convo.defs#logCreateMessage",
+        "chat.bsky.convo.defs#logDeleteMessage",
+    ];
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
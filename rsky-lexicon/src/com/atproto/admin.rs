@@ -1,5 +1,6 @@
 use crate::com::atproto::repo::StrongRef;
 use crate::com::atproto::server::InviteCode;
+use crate::open_union::OpenUnion;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
@@ -133,6 +134,26 @@ pub enum Subject {
     StrongRef(StrongRef),
     #[serde(rename = "com.atproto.admin.defs#repoBlobRef")]
     RepoBlobRef(RepoBlobRef),
+    /// A `$type` this union doesn't know, kept as-is so it round-trips unchanged. Known types
+    /// that don't parse are errors rather than landing here.
+    #[serde(untagged)]
+    Unknown {
+        #[serde(
+            rename = "$type",
+            deserialize_with = "crate::open_union::unknown_type::<Subject, _>"
+        )]
+        r#type: String,
+        #[serde(flatten)]
+        data: Value,
+    },
+}
+
+impl OpenUnion for Subject {
+    const KNOWN_TYPES: &'static [&'static str] = &[
+        "com.atproto.admin.defs#repoRef",
+        "com.atproto.repo.strongRef",
+        "com.atproto.admin.defs#repoBlobRef",
+    ];
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
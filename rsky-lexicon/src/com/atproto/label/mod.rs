@@ -1,25 +1,49 @@
+use crate::validation::{field, ArrayRules, StringRules, Validate, ValidationError};
+use base64::alphabet::STANDARD;
+use base64::engine::general_purpose::{GeneralPurpose, GeneralPurposeConfig};
+use base64::engine::DecodePaddingMode;
+use base64::Engine as _;
 use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// atproto encodes `bytes` in JSON as unpadded standard base64, but tolerates padding on input.
+const BYTES_ENGINE: GeneralPurpose = GeneralPurpose::new(
+    &STANDARD,
+    GeneralPurposeConfig::new()
+        .with_encode_padding(false)
+        .with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
 
 /// Metadata tag on an atproto resource (eg, repo or record).
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct Label {
     /// The AT Protocol version of the label object.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub ver: Option<u8>,
     /// DID of the actor who created this label.
     pub src: String,
     /// AT URI of the record, repository (account), or other resource that this label applies to.
     pub uri: String,
     /// Optionally, CID specifying the specific version of 'uri' resource this label applies to.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub cid: Option<String>,
     /// The short string name of the value or type of this label.
     pub val: String,
     /// If true, this is a negation label, overwriting a previous label.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub neg: Option<bool>,
     /// Timestamp when this label was created.
     pub cts: DateTime<Utc>,
     /// Timestamp at which this label expires (no longer applies).
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub exp: Option<DateTime<Utc>>,
     /// Signature of dag-cbor encoded label.
+    #[serde(
+        default,
+        serialize_with = "serialize_option_bytes",
+        deserialize_with = "deserialize_option_bytes",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub sig: Option<Vec<u8>>,
 }
 
@@ -29,6 +53,18 @@ pub struct SelfLabels {
     pub values: Vec<SelfLabel>,
 }
 
+impl Validate for SelfLabels {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        let path = &field(path, "values");
+        ArrayRules {
+            max_length: Some(10),
+            ..Default::default()
+        }
+        .check(path, self.values.len(), errors);
+        self.values.validate_at(path, errors);
+    }
+}
+
 /// Metadata tag on an atproto record, published by the author within the record.
 /// Note that schemas should use #selfLabels, not #selfLabel.
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -36,3 +72,78 @@ pub struct SelfLabel {
     /// The short string name of the value or type of this label.
     pub val: String,
 }
+
+impl Validate for SelfLabel {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        StringRules {
+            max_length: Some(128),
+            ..Default::default()
+        }
+        .check(&field(path, "val"), &self.val, errors);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct QueryLabelsOutput {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    pub labels: Vec<Label>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct SubscribeLabelsLabels {
+    pub seq: i64,
+    pub labels: Vec<Label>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct SubscribeLabelsInfo {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// Messages emitted on a `com.atproto.label.subscribeLabels` stream.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SubscribeLabels {
+    Labels(SubscribeLabelsLabels),
+    Info(SubscribeLabelsInfo),
+}
+
+/// JSON form of an atproto `bytes` value.
+#[derive(Deserialize, Serialize)]
+struct JsonBytes {
+    #[serde(rename = "$bytes")]
+    bytes: String,
+}
+
+/// Writes `bytes` as a CBOR byte string, or as a `{"$bytes": "<base64>"}` object for JSON.
+pub fn serialize_option_bytes<S>(value: &Option<Vec<u8>>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match value {
+        None => serializer.serialize_none(),
+        Some(bytes) if serializer.is_human_readable() => serializer.serialize_some(&JsonBytes {
+            bytes: BYTES_ENGINE.encode(bytes),
+        }),
+        Some(bytes) => serializer.serialize_some(&serde_bytes::Bytes::new(bytes)),
+    }
+}
+
+pub fn deserialize_option_bytes<'de, D>(deserializer: D) -> Result<Option<Vec<u8>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    if deserializer.is_human_readable() {
+        match Option::<JsonBytes>::deserialize(deserializer)? {
+            None => Ok(None),
+            Some(json) => Ok(Some(BYTES_ENGINE.decode(json.bytes).map_err(|e| {
+                serde::de::Error::custom(format!("Failed to deserialize bytes: {}", e))
+            })?)),
+        }
+    } else {
+        let buf = Option::<serde_bytes::ByteBuf>::deserialize(deserializer)?;
+        Ok(buf.map(|buf| buf.into_vec()))
+    }
+}
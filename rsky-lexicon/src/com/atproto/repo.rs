@@ -1,4 +1,5 @@
 use crate::com::atproto::sync::{default_resource, deserialize_option_cid_v1};
+use crate::validation::{field, Format, StringRules, Validate, ValidationError};
 use lexicon_cid::Cid;
 use serde_json::Value;
 
@@ -8,6 +9,21 @@ pub struct StrongRef {
     pub cid: String,
 }
 
+impl Validate for StrongRef {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        StringRules {
+            format: Some(Format::AtUri),
+            ..Default::default()
+        }
+        .check(&field(path, "uri"), &self.uri, errors);
+        StringRules {
+            format: Some(Format::Cid),
+            ..Default::default()
+        }
+        .check(&field(path, "cid"), &self.cid, errors);
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct Record {
     pub uri: String,
@@ -28,16 +44,13 @@ pub struct Blob {
         skip_serializing_if = "Option::is_none"
     )]
     pub r#type: Option<String>,
-    // #[serde(
-    //     skip_serializing_if = "Option::is_none",
-    //     default = "default_resource",
-    //     deserialize_with = "deserialize_option_cid_v1"
-    // )]
-    // pub r#ref: Option<Cid>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub r#ref: Option<Link>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cid: Option<String>,
     #[serde(rename(deserialize = "mimeType", serialize = "mimeType"))]
     pub mime_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub size: Option<i64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub original: Option<OriginalBlob>,
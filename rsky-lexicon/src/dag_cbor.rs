@@ -0,0 +1,170 @@
+//! DAG-CBOR encoding for lexicon types, as stored in repos and sent over the firehose.
+//!
+//! Values go through the atproto JSON data model, which every type here already serializes to:
+//! `{"$link": "<cid>"}` objects such as a blob's `ref` become tag 42 CIDs, `{"$bytes": "<base64>"}`
+//! objects such as a label's `sig` become raw byte strings, and map keys are written in canonical
+//! order, so a record encodes to the same bytes, and CID, its author committed. Decoding reverses
+//! each step. Unknown union members keep their links and bytes too.
+//!
+//! Fields typed as `DateTime<Utc>` re-serialize in chrono's RFC 3339 form, so a record whose
+//! timestamps were written differently (eg `.000Z`) decodes fine but hashes to a different CID.
+use anyhow::Result;
+use lexicon_cid::Cid;
+use rsky_crypto::dag_cbor;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Encodes `value` as canonical DAG-CBOR.
+pub fn to_vec<T: Serialize>(value: &T) -> Result<Vec<u8>> {
+    dag_cbor::encode(value)
+}
+
+/// Decodes a DAG-CBOR block, eg a record from a CAR file, into `T`.
+pub fn from_slice<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+    Ok(serde_json::from_value(dag_cbor::decode_json(bytes)?)?)
+}
+
+/// The CIDv1 a repo commits `record` under.
+pub fn cid_for_record<T: Serialize>(record: &T) -> Result<Cid> {
+    dag_cbor::cid_for_record(record)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::bsky::embed::Embeds;
+    use crate::app::bsky::feed::like::Like;
+    use crate::app::bsky::feed::Post;
+    use crate::com::atproto::label::Label;
+    use serde_cbor::Value as CborValue;
+    use serde_json::json;
+    use std::fmt::Debug;
+
+    /// Asserts `record` hashes to `cid` and decodes back to itself.
+    fn assert_golden<T>(record: &T, cid: &str)
+    where
+        T: Serialize + DeserializeOwned + PartialEq + Debug,
+    {
+        let bytes = to_vec(record).unwrap();
+        assert_eq!(cid_for_record(record).unwrap().to_string(), cid);
+        assert_eq!(from_slice::<T>(&bytes).unwrap(), *record);
+    }
+
+    /// Every CBOR value nested in `value`, including itself.
+    fn flatten(value: &CborValue) -> Vec<&CborValue> {
+        let mut values = vec![value];
+        match value {
+            CborValue::Array(items) => values.extend(items.iter().flat_map(flatten)),
+            CborValue::Map(map) => values.extend(map.values().flat_map(flatten)),
+            CborValue::Tag(_, inner) => values.extend(flatten(inner)),
+            _ => (),
+        }
+        values
+    }
+
+    fn links(bytes: &[u8]) -> usize {
+        let value: CborValue = serde_cbor::from_slice(bytes).unwrap();
+        flatten(&value)
+            .into_iter()
+            .filter(|value| matches!(value, CborValue::Tag(42, _)))
+            .count()
+    }
+
+    #[test]
+    fn encodes_like_to_its_committed_cid() {
+        // As received from Jetstream, with the CID its repo committed it under
+        let like: Like = serde_json::from_value(json!({
+            "$type": "app.bsky.feed.like",
+            "createdAt": "2024-11-13T23:19:36.449Z",
+            "subject": {
+                "cid": "bafyreigw5ufnkavdzcczl2dusa3bcnkckhi4tscp6qsrsmg76s3ckseney",
+                "uri": "at://did:plc:6wthaiuqiys3y7eztkpsdam2/app.bsky.feed.post/3latjcehsho2n"
+            }
+        }))
+        .unwrap();
+        assert_golden(
+            &like,
+            "bafyreifsdaip3s5nm3hcz4fbgkxodnils75oi3rmqhipwtom34rxw4vwdi",
+        );
+    }
+
+    #[test]
+    fn encodes_blob_refs_as_links() {
+        // CID cross-checked against a separate DAG-CBOR encoder
+        let post: Post = serde_json::from_value(json!({
+            "$type": "app.bsky.feed.post",
+            "createdAt": "2024-11-14T09:02:11.512Z",
+            "text": "sunset from the pier",
+            "langs": ["en"],
+            "embed": {
+                "$type": "app.bsky.embed.images",
+                "images": [{
+                    "alt": "orange sky over the water",
+                    "aspectRatio": { "width": 2000, "height": 1500 },
+                    "image": {
+                        "$type": "blob",
+                        "ref": {
+                            "$link": "bafkreifzjut3te2nhyekklss27nh3k72ysco7y32koao5eei66wof36n5e"
+                        },
+                        "mimeType": "image/jpeg",
+                        "size": 482113
+                    }
+                }]
+            }
+        }))
+        .unwrap();
+        assert_golden(
+            &post,
+            "bafyreidrmeidub34yqaarbi3dnhnwturrf27ao6fzm2o3lubblx22ke42e",
+        );
+        assert_eq!(links(&to_vec(&post).unwrap()), 1);
+    }
+
+    #[test]
+    fn keeps_links_in_unknown_union_members() {
+        let post: Post = serde_json::from_value(json!({
+            "$type": "app.bsky.feed.post",
+            "createdAt": "2024-11-14T09:02:11.512Z",
+            "text": "",
+            "embed": {
+                "$type": "com.example.embed.audio",
+                "audio": {
+                    "$type": "blob",
+                    "ref": {
+                        "$link": "bafkreifzjut3te2nhyekklss27nh3k72ysco7y32koao5eei66wof36n5e"
+                    },
+                    "mimeType": "audio/ogg",
+                    "size": 11
+                }
+            }
+        }))
+        .unwrap();
+        let bytes = to_vec(&post).unwrap();
+        assert_eq!(links(&bytes), 1);
+        let decoded: Post = from_slice(&bytes).unwrap();
+        assert!(matches!(decoded.embed, Some(Embeds::Unknown { .. })));
+        assert_eq!(decoded, post);
+    }
+
+    #[test]
+    fn encodes_label_signatures_as_bytes() {
+        let label: Label = serde_json::from_value(json!({
+            "ver": 1,
+            "src": "did:plc:ar7c4by46qjdydhdevvrndac",
+            "uri": "at://did:plc:6wthaiuqiys3y7eztkpsdam2/app.bsky.feed.post/3latjcehsho2n",
+            "val": "no-alt-text",
+            "cts": "2024-11-14T09:02:11.512Z",
+            "sig": { "$bytes": "AQIDBA" }
+        }))
+        .unwrap();
+        let bytes = to_vec(&label).unwrap();
+        let CborValue::Map(map) = serde_cbor::from_slice(&bytes).unwrap() else {
+            panic!("labels encode as maps");
+        };
+        assert_eq!(
+            map[&CborValue::Text("sig".to_string())],
+            CborValue::Bytes(vec![1, 2, 3, 4])
+        );
+        assert_eq!(from_slice::<Label>(&bytes).unwrap(), label);
+    }
+}
@@ -7,3 +7,26 @@ extern crate serde_json;
 pub mod app;
 pub mod chat;
 pub mod com;
+#[cfg(feature = "dag-cbor")]
+pub mod dag_cbor;
+pub mod open_union;
+pub mod validation;
+
+/// Parses a test-vector file of sample values keyed by lexicon def name.
+#[cfg(test)]
+pub(crate) fn test_vectors(json: &str) -> serde_json::Value {
+    serde_json::from_str(json).expect("malformed test vectors")
+}
+
+/// Asserts that every sample deserializes into `T` and serializes back to the same JSON.
+#[cfg(test)]
+pub(crate) fn assert_round_trips<T>(samples: &serde_json::Value)
+where
+    T: serde::de::DeserializeOwned + serde::Serialize + std::fmt::Debug,
+{
+    for sample in samples.as_array().expect("samples should be an array") {
+        let value: T = serde_json::from_value(sample.clone())
+            .unwrap_or_else(|error| panic!("failed to deserialize {sample}: {error}"));
+        assert_eq!(serde_json::to_value(&value).unwrap(), *sample, "{value:?}");
+    }
+}
@@ -0,0 +1,26 @@
+use serde::de::Error;
+use serde::{Deserialize, Deserializer};
+
+/// A union that keeps members it doesn't model in an `Unknown { r#type, data }` variant.
+pub trait OpenUnion {
+    /// Every `$type` the union has a variant for.
+    const KNOWN_TYPES: &'static [&'static str];
+}
+
+/// Deserializes the `$type` of an `Unknown` variant, rejecting the types `U` knows. Serde tries
+/// `Unknown` when a member fails to parse, so without this a known `$type` with bad fields would
+/// be silently kept as unknown rather than reported.
+pub fn unknown_type<'de, U, D>(deserializer: D) -> Result<String, D::Error>
+where
+    U: OpenUnion,
+    D: Deserializer<'de>,
+{
+    let r#type = String::deserialize(deserializer)?;
+    match U::KNOWN_TYPES.contains(&r#type.as_str()) {
+        true => Err(D::Error::custom(format!(
+            "`{type}` doesn't match its lexicon",
+            type = r#type
+        ))),
+        false => Ok(r#type),
+    }
+}
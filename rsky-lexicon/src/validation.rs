@@ -0,0 +1,450 @@
+use crate::com::atproto::repo::Blob;
+use rsky_syntax::aturi::AtUri;
+use rsky_syntax::{cid, datetime, did, handle, language, nsid, record_key, tid, AtIdentifier};
+use std::fmt;
+use thiserror::Error;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Checks a value against the constraints its lexicon puts on it beyond what the Rust type
+/// already guarantees: string lengths and formats, array lengths, integer ranges and blob limits.
+pub trait Validate {
+    /// Reports every constraint `self` breaks, with paths relative to `path`.
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>);
+
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = Vec::new();
+        self.validate_at("", &mut errors);
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(ValidationErrors(errors)),
+        }
+    }
+}
+
+impl<T: Validate> Validate for Option<T> {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        if let Some(value) = self {
+            value.validate_at(path, errors);
+        }
+    }
+}
+
+impl<T: Validate> Validate for Vec<T> {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        for (i, value) in self.iter().enumerate() {
+            value.validate_at(&index(path, i), errors);
+        }
+    }
+}
+
+impl<T: Validate> Validate for Box<T> {
+    fn validate_at(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        self.as_ref().validate_at(path, errors);
+    }
+}
+
+/// A broken constraint, eg `embed.images[2].alt: must be at most 10000 bytes long, is 10240`.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("{path}: {kind}")]
+pub struct ValidationError {
+    /// Where the value sits in the record, using the lexicon's field names.
+    pub path: String,
+    pub kind: ValidationErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ValidationErrorKind {
+    #[error("must be at least {min} bytes long, is {actual}")]
+    TooShort { min: usize, actual: usize },
+    #[error("must be at most {max} bytes long, is {actual}")]
+    TooLong { max: usize, actual: usize },
+    #[error("must be at least {min} graphemes long, is {actual}")]
+    TooFewGraphemes { min: usize, actual: usize },
+    #[error("must be at most {max} graphemes long, is {actual}")]
+    TooManyGraphemes { max: usize, actual: usize },
+    #[error("must have at least {min} items, has {actual}")]
+    TooFewItems { min: usize, actual: usize },
+    #[error("must have at most {max} items, has {actual}")]
+    TooManyItems { max: usize, actual: usize },
+    #[error("must be at least {min}, is {actual}")]
+    TooSmall { min: i64, actual: i64 },
+    #[error("must be at most {max}, is {actual}")]
+    TooLarge { max: i64, actual: i64 },
+    #[error("is not a valid {0}")]
+    InvalidFormat(Format),
+    #[error("blob must be at most {max} bytes, is {actual}")]
+    BlobTooLarge { max: i64, actual: i64 },
+    #[error("blob of type {mime_type:?} is not one of {accept:?}")]
+    BlobType {
+        mime_type: String,
+        accept: Vec<String>,
+    },
+}
+
+/// Every constraint a value broke.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub struct ValidationErrors(pub Vec<ValidationError>);
+
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, error) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str("; ")?;
+            }
+            write!(f, "{error}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The lexicon string formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    AtIdentifier,
+    AtUri,
+    Cid,
+    Datetime,
+    Did,
+    Handle,
+    Language,
+    Nsid,
+    RecordKey,
+    Tid,
+    Uri,
+}
+
+impl Format {
+    pub fn is_valid(&self, value: &str) -> bool {
+        match self {
+            Format::AtIdentifier => AtIdentifier::new(value.to_string()).is_ok(),
+            Format::AtUri => AtUri::new(value.to_string(), None).is_ok(),
+            Format::Cid => cid::ensure_valid_cid(value).is_ok(),
+            Format::Datetime => datetime::is_valid_datetime(value),
+            Format::Did => did::ensure_valid_did(value).is_ok(),
+            Format::Handle => handle::ensure_valid_handle(value).is_ok(),
+            Format::Language => language::is_valid_language(value),
+            Format::Nsid => nsid::ensure_valid_nsid(value).is_ok(),
+            Format::RecordKey => record_key::ensure_valid_record_key(value).is_ok(),
+            Format::Tid => tid::ensure_valid_tid(value).is_ok(),
+            Format::Uri => is_valid_uri(value),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Format::AtIdentifier => "at-identifier",
+            Format::AtUri => "at-uri",
+            Format::Cid => "cid",
+            Format::Datetime => "datetime",
+            Format::Did => "did",
+            Format::Handle => "handle",
+            Format::Language => "language",
+            Format::Nsid => "nsid",
+            Format::RecordKey => "record-key",
+            Format::Tid => "tid",
+            Format::Uri => "uri",
+        })
+    }
+}
+
+/// A scheme followed by something, which is all the lexicon `uri` format asks for.
+fn is_valid_uri(value: &str) -> bool {
+    let Some((scheme, rest)) = value.split_once(':') else {
+        return false;
+    };
+    let mut scheme_chars = scheme.chars();
+    scheme_chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && scheme_chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        && !rest.is_empty()
+        && !value.chars().any(char::is_whitespace)
+        && value.len() <= 8192
+}
+
+/// `path.name`, or just `name` at the root.
+pub fn field(path: &str, name: &str) -> String {
+    match path.is_empty() {
+        true => name.to_string(),
+        false => format!("{path}.{name}"),
+    }
+}
+
+/// `path[i]`.
+pub fn index(path: &str, i: usize) -> String {
+    format!("{path}[{i}]")
+}
+
+fn push(errors: &mut Vec<ValidationError>, path: &str, kind: ValidationErrorKind) {
+    errors.push(ValidationError {
+        path: path.to_string(),
+        kind,
+    });
+}
+
+/// Constraints on a `string`. Lengths are in UTF-8 bytes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StringRules {
+    pub format: Option<Format>,
+    pub min_length: Option<usize>,
+    pub max_length: Option<usize>,
+    pub min_graphemes: Option<usize>,
+    pub max_graphemes: Option<usize>,
+}
+
+impl StringRules {
+    pub fn check(&self, path: &str, value: &str, errors: &mut Vec<ValidationError>) {
+        let length = value.len();
+        if let Some(min) = self.min_length.filter(|min| length < *min) {
+            push(
+                errors,
+                path,
+                ValidationErrorKind::TooShort {
+                    min,
+                    actual: length,
+                },
+            );
+        }
+        if let Some(max) = self.max_length.filter(|max| length > *max) {
+            push(
+                errors,
+                path,
+                ValidationErrorKind::TooLong {
+                    max,
+                    actual: length,
+                },
+            );
+        }
+        if self.min_graphemes.is_some() || self.max_graphemes.is_some() {
+            let graphemes = value.graphemes(true).count();
+            if let Some(min) = self.min_graphemes.filter(|min| graphemes < *min) {
+                let kind = ValidationErrorKind::TooFewGraphemes {
+                    min,
+                    actual: graphemes,
+                };
+                push(errors, path, kind);
+            }
+            if let Some(max) = self.max_graphemes.filter(|max| graphemes > *max) {
+                let kind = ValidationErrorKind::TooManyGraphemes {
+                    max,
+                    actual: graphemes,
+                };
+                push(errors, path, kind);
+            }
+        }
+        if let Some(format) = self.format.filter(|format| !format.is_valid(value)) {
+            push(errors, path, ValidationErrorKind::InvalidFormat(format));
+        }
+    }
+}
+
+/// Constraints on the number of items in an `array`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ArrayRules {
+    pub min_length: Option<usize>,
+    pub max_length: Option<usize>,
+}
+
+impl ArrayRules {
+    pub fn check(&self, path: &str, length: usize, errors: &mut Vec<ValidationError>) {
+        if let Some(min) = self.min_length.filter(|min| length < *min) {
+            push(
+                errors,
+                path,
+                ValidationErrorKind::TooFewItems {
+                    min,
+                    actual: length,
+                },
+            );
+        }
+        if let Some(max) = self.max_length.filter(|max| length > *max) {
+            push(
+                errors,
+                path,
+                ValidationErrorKind::TooManyItems {
+                    max,
+                    actual: length,
+                },
+            );
+        }
+    }
+}
+
+/// Constraints on an `integer`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IntegerRules {
+    pub minimum: Option<i64>,
+    pub maximum: Option<i64>,
+}
+
+impl IntegerRules {
+    pub fn check(&self, path: &str, value: i64, errors: &mut Vec<ValidationError>) {
+        if let Some(min) = self.minimum.filter(|min| value < *min) {
+            push(
+                errors,
+                path,
+                ValidationErrorKind::TooSmall { min, actual: value },
+            );
+        }
+        if let Some(max) = self.maximum.filter(|max| value > *max) {
+            push(
+                errors,
+                path,
+                ValidationErrorKind::TooLarge { max, actual: value },
+            );
+        }
+    }
+}
+
+/// Constraints on a `blob`. `accept` holds mime types, which may end in a `/*` wildcard.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BlobRules {
+    pub accept: &'static [&'static str],
+    pub max_size: Option<i64>,
+}
+
+impl BlobRules {
+    pub fn check(&self, path: &str, blob: &Blob, errors: &mut Vec<ValidationError>) {
+        if let (Some(max), Some(size)) = (self.max_size, blob.size) {
+            if size > max {
+                push(
+                    errors,
+                    path,
+                    ValidationErrorKind::BlobTooLarge { max, actual: size },
+                );
+            }
+        }
+        let accepted = self.accept.is_empty()
+            || self
+                .accept
+                .iter()
+                .any(|accept| match accept.strip_suffix("/*") {
+                    Some(prefix) => blob
+                        .mime_type
+                        .split_once('/')
+                        .is_some_and(|(kind, _)| kind == prefix),
+                    None => *accept == "*/*" || *accept == blob.mime_type,
+                });
+        if !accepted {
+            let kind = ValidationErrorKind::BlobType {
+                mime_type: blob.mime_type.clone(),
+                accept: self
+                    .accept
+                    .iter()
+                    .map(|accept| accept.to_string())
+                    .collect(),
+            };
+            push(errors, path, kind);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blob(mime_type: &str, size: i64) -> Blob {
+        Blob {
+            r#type: Some("blob".to_string()),
+            r#ref: None,
+            cid: None,
+            mime_type: mime_type.to_string(),
+            size: Some(size),
+            original: None,
+        }
+    }
+
+    #[test]
+    fn checks_string_lengths_in_bytes_and_graphemes() {
+        let rules = StringRules {
+            max_length: Some(8),
+            max_graphemes: Some(2),
+            ..Default::default()
+        };
+        let mut errors = Vec::new();
+        rules.check("text", "👩‍👩‍👧", &mut errors);
+        assert_eq!(
+            errors,
+            vec![ValidationError {
+                path: "text".to_string(),
+                kind: ValidationErrorKind::TooLong { max: 8, actual: 18 },
+            }]
+        );
+
+        let mut errors = Vec::new();
+        rules.check("text", "abc", &mut errors);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].to_string(),
+            "text: must be at most 2 graphemes long, is 3"
+        );
+    }
+
+    #[test]
+    fn checks_formats() {
+        let mut errors = Vec::new();
+        let rules = StringRules {
+            format: Some(Format::Did),
+            ..Default::default()
+        };
+        rules.check("subject", "did:plc:ewvi7nxzyoun6zhxrhs64oiz", &mut errors);
+        rules.check("subject", "atproto.com", &mut errors);
+        assert_eq!(
+            errors,
+            vec![ValidationError {
+                path: "subject".to_string(),
+                kind: ValidationErrorKind::InvalidFormat(Format::Did),
+            }]
+        );
+
+        assert!(Format::Uri.is_valid("https://atproto.com/specs/lexicon"));
+        assert!(Format::Uri.is_valid("mailto:someone@example.com"));
+        assert!(!Format::Uri.is_valid("atproto.com"));
+        assert!(!Format::Uri.is_valid("https://atproto.com/ spaces"));
+        assert!(Format::AtUri
+            .is_valid("at://did:plc:ewvi7nxzyoun6zhxrhs64oiz/app.bsky.feed.post/3l6oveex3ii2l"));
+        assert!(!Format::AtUri.is_valid("https://bsky.app"));
+        assert!(Format::Language.is_valid("pt-BR"));
+        assert!(!Format::Datetime.is_valid("2024-10-15"));
+    }
+
+    #[test]
+    fn checks_arrays_integers_and_blobs() {
+        let mut errors = Vec::new();
+        ArrayRules {
+            max_length: Some(4),
+            ..Default::default()
+        }
+        .check("images", 5, &mut errors);
+        IntegerRules {
+            minimum: Some(1),
+            ..Default::default()
+        }
+        .check("aspectRatio.width", 0, &mut errors);
+        let rules = BlobRules {
+            accept: &["image/*"],
+            max_size: Some(1_000_000),
+        };
+        rules.check("image", &blob("image/png", 1_000), &mut errors);
+        rules.check("image", &blob("video/mp4", 2_000_000), &mut errors);
+        let messages: Vec<String> = errors.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "images: must have at most 4 items, has 5",
+                "aspectRatio.width: must be at least 1, is 0",
+                "image: blob must be at most 1000000 bytes, is 2000000",
+                "image: blob of type \"video/mp4\" is not one of [\"image/*\"]",
+            ]
+        );
+    }
+
+    #[test]
+    fn builds_paths() {
+        assert_eq!(field("", "embed"), "embed");
+        assert_eq!(
+            field(&index(&field("embed", "images"), 2), "alt"),
+            "embed.images[2].alt"
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use crate::did::Did;
+use crate::handle::Handle;
+use anyhow::Result;
+
+/// The authority of an AT URI or an actor parameter, either a DID or a handle.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum AtIdentifier {
+    Did(Did),
+    Handle(Handle),
+}
+
+string_newtype!(AtIdentifier);
+
+impl AtIdentifier {
+    pub fn new(identifier: String) -> Result<Self> {
+        if identifier.starts_with("did:") {
+            Ok(Self::Did(Did::new(identifier)?))
+        } else {
+            Ok(Self::Handle(Handle::new(identifier)?))
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Did(did) => did.as_str(),
+            Self::Handle(handle) => handle.as_str(),
+        }
+    }
+
+    pub fn as_did(&self) -> Option<&Did> {
+        match self {
+            Self::Did(did) => Some(did),
+            Self::Handle(_) => None,
+        }
+    }
+
+    pub fn as_handle(&self) -> Option<&Handle> {
+        match self {
+            Self::Did(_) => None,
+            Self::Handle(handle) => Some(handle),
+        }
+    }
+}
+
+impl From<Did> for AtIdentifier {
+    fn from(did: Did) -> Self {
+        Self::Did(did)
+    }
+}
+
+impl From<Handle> for AtIdentifier {
+    fn from(handle: Handle) -> Self {
+        Self::Handle(handle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::check_interop_vectors;
+
+    #[test]
+    fn test_interop_vectors() {
+        check_interop_vectors(
+            include_str!("../test-vectors/syntax/atidentifier_syntax_valid.txt"),
+            include_str!("../test-vectors/syntax/atidentifier_syntax_invalid.txt"),
+            |identifier| AtIdentifier::new(identifier.to_string()).is_ok(),
+        );
+    }
+
+    #[test]
+    fn test_variants() {
+        let did: AtIdentifier = "did:plc:7iza6de2dwap2sbkpav7c6c6".parse().unwrap();
+        assert!(did.as_did().is_some());
+        assert!(did.as_handle().is_none());
+        let handle: AtIdentifier = "John.Test".parse().unwrap();
+        assert_eq!(handle.as_handle().unwrap().as_str(), "john.test");
+        assert_eq!(
+            serde_json::to_string(&handle).unwrap(),
+            "\"john.test\"".to_string()
+        );
+    }
+}
@@ -1,8 +1,24 @@
-use anyhow::{bail, Result};
+use crate::at_identifier::AtIdentifier;
+use crate::did::Did;
+use crate::handle::{Handle, ResolveHandle};
+use crate::nsid::Nsid;
+use crate::record_key::RecordKey;
+use anyhow::{anyhow, bail, Result};
 use lazy_static::lazy_static;
 use regex::Regex;
+use std::fmt;
+use std::str::FromStr;
 use url::Url;
 
+pub const BSKY_APP_URL: &str = "https://bsky.app";
+
+/// Collections with a page on bsky.app, and the path segment used for them there
+const BSKY_APP_COLLECTIONS: [(&str, &str); 3] = [
+    ("app.bsky.feed.post", "post"),
+    ("app.bsky.feed.generator", "feed"),
+    ("app.bsky.graph.list", "lists"),
+];
+
 pub fn atp_uri_regex(input: &str) -> Option<Vec<&str>> {
     lazy_static! {
         static ref RE: Regex = Regex::new(r"(?i)^(at://)?((?:did:[a-z0-9:%-]+)|(?:[a-z0-9][a-z0-9.:-]*))(/[^?#\s]*)?(\?[^#\s]+)?(#[^\s]+)?$").unwrap();
@@ -42,15 +58,18 @@ pub struct ParsedRelativeOutput {
     pub search_params: Vec<(String, String)>,
 }
 
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+/// An `at://` URI naming a repo, a collection in it or a record. Serializes as the URI string.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct AtUri {
-    pub hash: String,
-    pub host: String,
-    pub pathname: String,
-    pub search_params: Vec<(String, String)>,
+    authority: AtIdentifier,
+    collection: Option<Nsid>,
+    rkey: Option<RecordKey>,
+    search_params: Vec<(String, String)>,
+    hash: String,
 }
 
 impl AtUri {
+    /// Parses `uri`, or resolves it as a path relative to `base` when one is given.
     pub fn new(uri: String, base: Option<String>) -> Result<Self> {
         let parsed: ParsedOutput = match base {
             Some(base) => match parse(&base)? {
@@ -70,11 +89,24 @@ impl AtUri {
                 Some(result) => result,
             },
         };
+        let authority: AtIdentifier = parsed.host.parse()?;
+        let mut segments = parsed
+            .pathname
+            .strip_prefix('/')
+            .unwrap_or(&parsed.pathname)
+            .split('/')
+            .filter(|segment| !segment.is_empty());
+        let collection = segments.next().map(Nsid::from_str).transpose()?;
+        let rkey = segments.next().map(RecordKey::from_str).transpose()?;
+        if segments.next().is_some() {
+            bail!("Too many path segments in at uri: `{uri}`");
+        }
         Ok(Self {
-            hash: parsed.hash,
-            host: parsed.host,
-            pathname: parsed.pathname,
+            authority,
+            collection,
+            rkey,
             search_params: parsed.search_params,
+            hash: parsed.hash.trim_start_matches('#').to_string(),
         })
     }
 
@@ -93,116 +125,203 @@ impl AtUri {
         AtUri::new(str, None)
     }
 
-    pub fn get_protocol(&self) -> String {
-        "at:".to_string()
+    /// The URI of a record, e.g. `at://<did>/app.bsky.feed.post/<rkey>`
+    pub fn record(
+        authority: impl Into<AtIdentifier>,
+        collection: Nsid,
+        rkey: impl Into<RecordKey>,
+    ) -> Self {
+        Self {
+            authority: authority.into(),
+            collection: Some(collection),
+            rkey: Some(rkey.into()),
+            search_params: Vec::new(),
+            hash: String::new(),
+        }
     }
 
-    pub fn get_origin(&self) -> String {
-        format!("at://{}", self.host)
+    /// The URI of a whole collection in a repo
+    pub fn collection_of(authority: impl Into<AtIdentifier>, collection: Nsid) -> Self {
+        Self {
+            authority: authority.into(),
+            collection: Some(collection),
+            rkey: None,
+            search_params: Vec::new(),
+            hash: String::new(),
+        }
     }
 
-    pub fn get_hostname(&self) -> &String {
-        &self.host
+    pub fn authority(&self) -> &AtIdentifier {
+        &self.authority
     }
 
-    pub fn set_hostname(&mut self, v: String) {
-        self.host = v;
+    pub fn collection(&self) -> Option<&Nsid> {
+        self.collection.as_ref()
+    }
+
+    pub fn rkey(&self) -> Option<&RecordKey> {
+        self.rkey.as_ref()
+    }
+
+    pub fn get_origin(&self) -> String {
+        format!("at://{}", self.authority)
+    }
+
+    pub fn search_params(&self) -> &[(String, String)] {
+        &self.search_params
     }
 
     pub fn get_search(&self) -> Result<Option<String>> {
-        let url = Url::parse_with_params("http://example.com", &self.search_params)?;
-        match url.query() {
-            Some(query) => Ok(Some(query.to_string())),
-            None => Ok(None),
+        if self.search_params.is_empty() {
+            return Ok(None);
         }
+        let url = Url::parse_with_params("http://example.com", &self.search_params)?;
+        Ok(url.query().map(|query| query.to_string()))
     }
 
-    pub fn set_search(&mut self, v: String) -> Result<()> {
-        let dummy_url = format!("http://example.com{}", v);
-        let url = Url::parse(&dummy_url)?;
-        let query_pairs: Vec<(String, String)> = url
-            .query_pairs()
-            .map(|pair| (pair.0.to_string(), pair.1.to_string()))
-            .collect();
-        self.search_params = query_pairs;
-        Ok(())
+    pub fn hash(&self) -> &str {
+        &self.hash
     }
 
-    pub fn get_collection(&self) -> String {
-        match &self.pathname.split("/").collect::<Vec<&str>>().first() {
-            Some(collection) => collection.to_string(),
-            None => "".to_string(),
-        }
+    /// The same URI with its authority swapped, e.g. a handle for the DID it resolved to
+    pub fn with_authority(mut self, authority: impl Into<AtIdentifier>) -> Self {
+        self.authority = authority.into();
+        self
     }
 
-    pub fn set_collection(&mut self, v: String) {
-        let mut parts: Vec<String> = self
-            .pathname
-            .split("/")
-            .collect::<Vec<&str>>()
-            .into_iter()
-            .map(|p| p.to_string())
-            .collect::<Vec<String>>();
-        if !parts.is_empty() {
-            parts[0] = v;
-        } else {
-            parts.push(v);
+    /// The bsky.app page for a profile, post, feed or list.
+    pub fn to_bsky_app_url(&self) -> Result<String> {
+        let profile = format!("{BSKY_APP_URL}/profile/{}", self.authority);
+        match (&self.collection, &self.rkey) {
+            (None, None) => Ok(profile),
+            (Some(collection), Some(rkey)) => {
+                match BSKY_APP_COLLECTIONS
+                    .iter()
+                    .find(|(nsid, _)| *nsid == collection.as_str())
+                {
+                    Some((_, page)) => Ok(format!("{profile}/{page}/{rkey}")),
+                    None => bail!("bsky.app has no page for `{collection}` records"),
+                }
+            }
+            _ => bail!("bsky.app has no page for `{self}`"),
         }
-        self.pathname = parts.join("/");
     }
 
-    pub fn get_rkey(&self) -> String {
-        match &self.pathname.split("/").collect::<Vec<&str>>().get(1) {
-            Some(rkey) => rkey.to_string(),
-            None => "".to_string(),
+    /// Parses a bsky.app profile, post, feed or list URL. A handle in the URL is resolved to its
+    /// DID, since at URIs are expected to name repos by DID.
+    pub async fn from_bsky_app_url(url: &str, resolver: &dyn ResolveHandle) -> Result<Self> {
+        let parsed = Url::parse(url)?;
+        if parsed.scheme() != "https" || parsed.host_str() != Some("bsky.app") {
+            bail!("Not a bsky.app URL: `{url}`");
         }
+        let segments: Vec<&str> = parsed
+            .path_segments()
+            .map(|segments| segments.filter(|segment| !segment.is_empty()).collect())
+            .unwrap_or_default();
+        let (actor, collection, rkey) = match segments[..] {
+            ["profile", actor] => (actor, None, None),
+            ["profile", actor, page, rkey] => {
+                let collection = BSKY_APP_COLLECTIONS
+                    .iter()
+                    .find(|(_, known)| *known == page)
+                    .map(|(nsid, _)| Nsid::from_str(nsid))
+                    .ok_or_else(|| anyhow!("Unsupported bsky.app page: `{url}`"))??;
+                (actor, Some(collection), Some(RecordKey::from_str(rkey)?))
+            }
+            _ => bail!("Unsupported bsky.app page: `{url}`"),
+        };
+        let did = match actor.parse::<AtIdentifier>()? {
+            AtIdentifier::Did(did) => did,
+            AtIdentifier::Handle(handle) => match resolver.resolve_handle(&handle).await? {
+                Some(did) => did,
+                None => bail!("Could not resolve handle `{handle}`"),
+            },
+        };
+        Ok(Self {
+            authority: did.into(),
+            collection,
+            rkey,
+            search_params: Vec::new(),
+            hash: String::new(),
+        })
     }
+}
 
-    pub fn set_rkey(&mut self, v: String) {
-        let mut parts: Vec<String> = self
-            .pathname
-            .split("/")
-            .collect::<Vec<&str>>()
-            .into_iter()
-            .map(|p| p.to_string())
-            .collect::<Vec<String>>();
-        if parts.len() > 1 {
-            parts[1] = v;
-        } else if !parts.is_empty() {
-            parts.push(v);
-        } else {
-            parts.push("undefined".to_string());
-            parts.push(v);
+impl fmt::Display for AtUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "at://{}", self.authority)?;
+        if let Some(collection) = &self.collection {
+            write!(f, "/{collection}")?;
+        }
+        if let Some(rkey) = &self.rkey {
+            write!(f, "/{rkey}")?;
+        }
+        if let Ok(Some(search)) = self.get_search() {
+            write!(f, "?{search}")?;
         }
-        self.pathname = parts.join("/");
+        if !self.hash.is_empty() {
+            write!(f, "#{}", self.hash)?;
+        }
+        Ok(())
     }
+}
 
-    pub fn get_href(&self) -> String {
-        self.to_string()
+impl FromStr for AtUri {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::new(s.to_string(), None)
     }
+}
 
-    pub fn to_string(&self) -> String {
-        let mut path = match self.pathname.is_empty() {
-            true => "/".to_string(),
-            false => self.pathname.clone(),
-        };
-        if !path.starts_with("/") {
-            path = format!("/{path}");
+impl TryFrom<String> for AtUri {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        Self::new(value, None)
+    }
+}
+
+impl From<AtUri> for String {
+    fn from(value: AtUri) -> Self {
+        value.to_string()
+    }
+}
+
+impl serde::Serialize for AtUri {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for AtUri {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Self::new(value, None).map_err(serde::de::Error::custom)
+    }
+}
+
+impl From<AtIdentifier> for AtUri {
+    fn from(identifier: AtIdentifier) -> Self {
+        Self {
+            authority: identifier,
+            collection: None,
+            rkey: None,
+            search_params: Vec::new(),
+            hash: String::new(),
         }
-        let qs = match self.get_search() {
-            Ok(Some(search_params))
-                if !search_params.starts_with("?") && !search_params.is_empty() =>
-            {
-                format!("?{search_params}")
-            }
-            Ok(Some(search_params)) => search_params,
-            _ => "".to_string(),
-        };
-        let hash = match self.hash.is_empty() {
-            true => self.hash.clone(),
-            false => format!("#{}", self.hash),
-        };
-        format!("at://{}{}{}{}", self.host, path, qs, hash)
+    }
+}
+
+impl From<Did> for AtUri {
+    fn from(did: Did) -> Self {
+        AtIdentifier::Did(did).into()
+    }
+}
+
+impl From<Handle> for AtUri {
+    fn from(handle: Handle) -> Self {
+        AtIdentifier::Handle(handle).into()
     }
 }
 
@@ -254,3 +373,161 @@ pub fn parse_relative(str: &str) -> Result<Option<ParsedRelativeOutput>> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::collections::HashSet;
+
+    const POST: &str = "at://did:plc:6wthaiuqiys3y7eztkpsdam2/app.bsky.feed.post/3latjcehsho2n";
+
+    struct StaticResolver;
+
+    #[async_trait]
+    impl ResolveHandle for StaticResolver {
+        async fn resolve_handle(&self, handle: &Handle) -> Result<Option<Did>> {
+            match handle.as_str() {
+                "alice.test" => Ok(Some("did:plc:6wthaiuqiys3y7eztkpsdam2".parse()?)),
+                _ => Ok(None),
+            }
+        }
+    }
+
+    #[test]
+    fn test_from_identifiers() {
+        let did: Did = "did:plc:7iza6de2dwap2sbkpav7c6c6".parse().unwrap();
+        assert_eq!(
+            AtUri::from(did).to_string(),
+            "at://did:plc:7iza6de2dwap2sbkpav7c6c6"
+        );
+        let handle: Handle = "john.test".parse().unwrap();
+        let uri = AtUri::from(handle);
+        assert_eq!(uri.authority().as_str(), "john.test");
+        assert_eq!(uri, "at://john.test".parse().unwrap());
+    }
+
+    #[test]
+    fn test_typed_parts() {
+        let uri: AtUri = POST.parse().unwrap();
+        assert_eq!(
+            uri.authority().as_did().unwrap().as_str(),
+            "did:plc:6wthaiuqiys3y7eztkpsdam2"
+        );
+        assert_eq!(uri.collection().unwrap().as_str(), "app.bsky.feed.post");
+        assert_eq!(uri.rkey().unwrap().as_str(), "3latjcehsho2n");
+        assert_eq!(uri.to_string(), POST);
+
+        let built = AtUri::record(
+            uri.authority().clone(),
+            "app.bsky.feed.post".parse().unwrap(),
+            "3latjcehsho2n".parse::<RecordKey>().unwrap(),
+        );
+        assert_eq!(built, uri);
+    }
+
+    #[test]
+    fn test_rejects_invalid_parts() {
+        assert!(
+            "at://did:plc:6wthaiuqiys3y7eztkpsdam2/app.bsky.feed.post/3lat/extra"
+                .parse::<AtUri>()
+                .is_err()
+        );
+        assert!("at://did:plc:6wthaiuqiys3y7eztkpsdam2/not-an-nsid"
+            .parse::<AtUri>()
+            .is_err());
+        assert!("at://not_a_handle/app.bsky.feed.post"
+            .parse::<AtUri>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_relative_to_base() {
+        let uri = AtUri::new(
+            "/app.bsky.feed.post/3latjcehsho2n".to_string(),
+            Some("at://did:plc:6wthaiuqiys3y7eztkpsdam2".to_string()),
+        )
+        .unwrap();
+        assert_eq!(uri.to_string(), POST);
+    }
+
+    #[test]
+    fn test_search_and_hash() {
+        let uri: AtUri = "at://john.test/app.bsky.feed.post?foo=bar#frag"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            uri.search_params(),
+            [("foo".to_string(), "bar".to_string())]
+        );
+        assert_eq!(uri.hash(), "frag");
+        assert_eq!(
+            uri.to_string(),
+            "at://john.test/app.bsky.feed.post?foo=bar#frag"
+        );
+    }
+
+    #[test]
+    fn test_serde_and_hash_as_string() {
+        let uri: AtUri = serde_json::from_str(&format!("\"{POST}\"")).unwrap();
+        assert_eq!(serde_json::to_string(&uri).unwrap(), format!("\"{POST}\""));
+        let uris: HashSet<AtUri> = [uri.clone(), POST.parse().unwrap()].into();
+        assert_eq!(uris.len(), 1);
+    }
+
+    #[test]
+    fn test_to_bsky_app_url() {
+        let uri: AtUri = POST.parse().unwrap();
+        assert_eq!(
+            uri.to_bsky_app_url().unwrap(),
+            "https://bsky.app/profile/did:plc:6wthaiuqiys3y7eztkpsdam2/post/3latjcehsho2n"
+        );
+        let profile = AtUri::from(uri.authority().clone());
+        assert_eq!(
+            profile.to_bsky_app_url().unwrap(),
+            "https://bsky.app/profile/did:plc:6wthaiuqiys3y7eztkpsdam2"
+        );
+        let like: AtUri = "at://john.test/app.bsky.feed.like/3latjcehsho2n"
+            .parse()
+            .unwrap();
+        assert!(like.to_bsky_app_url().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_from_bsky_app_url() {
+        let uri = AtUri::from_bsky_app_url(
+            "https://bsky.app/profile/alice.test/post/3latjcehsho2n",
+            &StaticResolver,
+        )
+        .await
+        .unwrap();
+        assert_eq!(uri.to_string(), POST);
+
+        let list = AtUri::from_bsky_app_url(
+            "https://bsky.app/profile/did:plc:6wthaiuqiys3y7eztkpsdam2/lists/3l6ssqq2psc2a/",
+            &StaticResolver,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            list.to_string(),
+            "at://did:plc:6wthaiuqiys3y7eztkpsdam2/app.bsky.graph.list/3l6ssqq2psc2a"
+        );
+        assert_eq!(
+            AtUri::from_bsky_app_url(&uri.to_bsky_app_url().unwrap(), &StaticResolver)
+                .await
+                .unwrap(),
+            uri
+        );
+
+        for url in [
+            "https://bsky.app/profile/bob.test/post/3latjcehsho2n",
+            "https://bsky.app/profile/alice.test/likes/3latjcehsho2n",
+            "https://example.com/profile/alice.test",
+        ] {
+            assert!(AtUri::from_bsky_app_url(url, &StaticResolver)
+                .await
+                .is_err());
+        }
+    }
+}
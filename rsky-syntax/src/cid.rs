@@ -0,0 +1,52 @@
+use anyhow::{bail, Result};
+
+const CID_MIN_LENGTH: usize = 8;
+const CID_MAX_LENGTH: usize = 256;
+
+/// A CID in its string form. Only the syntax is checked; decoding it is left to a CID library.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CidString(String);
+
+string_newtype!(CidString);
+
+impl CidString {
+    pub fn new(cid: String) -> Result<Self> {
+        ensure_valid_cid(&cid)?;
+        Ok(Self(cid))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+pub fn ensure_valid_cid(cid: &str) -> Result<()> {
+    if cid.len() < CID_MIN_LENGTH || cid.len() > CID_MAX_LENGTH {
+        bail!("CID must be {CID_MIN_LENGTH} to {CID_MAX_LENGTH} characters");
+    }
+    if !cid
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '=')
+    {
+        bail!("Invalid CID: `{cid}`");
+    }
+    if cid.starts_with("Qmb") {
+        bail!("CIDv0 is not allowed: `{cid}`");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::check_interop_vectors;
+
+    #[test]
+    fn test_interop_vectors() {
+        check_interop_vectors(
+            include_str!("../test-vectors/syntax/cid_syntax_valid.txt"),
+            include_str!("../test-vectors/syntax/cid_syntax_invalid.txt"),
+            |cid| CidString::new(cid.to_string()).is_ok(),
+        );
+    }
+}
@@ -0,0 +1,81 @@
+use anyhow::{bail, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+const DATETIME_MAX_LENGTH: usize = 64;
+
+/// Checks `datetime` against the atproto datetime profile: RFC 3339 with uppercase `T` and `Z`,
+/// seconds, and a required timezone, which can't be the "unknown local offset" `-00:00`.
+pub fn ensure_valid_datetime(datetime: &str) -> Result<()> {
+    parse_datetime(datetime).map(|_| ())
+}
+
+pub fn is_valid_datetime(datetime: &str) -> bool {
+    ensure_valid_datetime(datetime).is_ok()
+}
+
+/// Parses a datetime in the atproto profile into UTC.
+pub fn parse_datetime(datetime: &str) -> Result<DateTime<Utc>> {
+    lazy_static! {
+        static ref RE: Regex = Regex::new(
+            r"^[0-9]{4}-[01][0-9]-[0-3][0-9]T[0-2][0-9]:[0-6][0-9]:[0-6][0-9](\.[0-9]{1,20})?(Z|([+-][0-2][0-9]:[0-5][0-9]))$"
+        )
+        .unwrap();
+    }
+    if datetime.len() > DATETIME_MAX_LENGTH {
+        bail!("Datetime is too long ({DATETIME_MAX_LENGTH} chars max)");
+    }
+    if !RE.is_match(datetime) {
+        bail!("Datetime didn't validate via regex: `{datetime}`");
+    }
+    if datetime.ends_with("-00:00") {
+        bail!("Datetime can't use the -00:00 timezone: `{datetime}`");
+    }
+    if datetime.starts_with("000") {
+        bail!("Datetime is too close to year zero: `{datetime}`");
+    }
+    match DateTime::parse_from_rfc3339(datetime) {
+        Ok(parsed) => Ok(parsed.with_timezone(&Utc)),
+        Err(error) => bail!("Invalid datetime `{datetime}`: {error}"),
+    }
+}
+
+/// Rewrites a valid datetime as UTC with millisecond precision, e.g.
+/// `1985-04-12T23:20:50.123Z`, so stored datetimes sort the same as strings and as times.
+pub fn normalize_datetime(datetime: &str) -> Result<String> {
+    Ok(format_datetime(&parse_datetime(datetime)?))
+}
+
+/// The normalized form of `datetime`, in the same format as [`normalize_datetime`].
+pub fn format_datetime(datetime: &DateTime<Utc>) -> String {
+    datetime.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::check_interop_vectors;
+
+    #[test]
+    fn test_interop_vectors() {
+        check_interop_vectors(
+            include_str!("../test-vectors/syntax/datetime_syntax_valid.txt"),
+            include_str!("../test-vectors/syntax/datetime_syntax_invalid.txt"),
+            is_valid_datetime,
+        );
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(
+            normalize_datetime("1985-04-12T23:20:50Z").unwrap(),
+            "1985-04-12T23:20:50.000Z"
+        );
+        assert_eq!(
+            normalize_datetime("1985-04-12T23:20:50.123456-07:00").unwrap(),
+            "1985-04-13T06:20:50.123Z"
+        );
+        assert!(normalize_datetime("1985-04-12").is_err());
+    }
+}
@@ -0,0 +1,90 @@
+use anyhow::{bail, Result};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+pub const DID_MAX_LENGTH: usize = 2048;
+
+/// A DID, checked against the atproto DID syntax. Any method is accepted, not just `plc` and
+/// `web`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Did(String);
+
+string_newtype!(Did);
+
+impl Did {
+    pub fn new(did: String) -> Result<Self> {
+        ensure_valid_did(&did)?;
+        Ok(Self(did))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// e.g. `plc` for `did:plc:ewvi7nxzyoun6zhxrhs64oiz`
+    pub fn method(&self) -> &str {
+        self.0.split(':').nth(1).unwrap_or_default()
+    }
+
+    /// Everything after the method, e.g. `ewvi7nxzyoun6zhxrhs64oiz`
+    pub fn identifier(&self) -> &str {
+        &self.0[5 + self.method().len()..]
+    }
+}
+
+pub fn ensure_valid_did(did: &str) -> Result<()> {
+    lazy_static! {
+        static ref RE: Regex = Regex::new(r"^did:[a-z]+:[a-zA-Z0-9._:%-]*[a-zA-Z0-9._-]$").unwrap();
+    }
+    if did.len() > DID_MAX_LENGTH {
+        bail!("DID is too long ({DID_MAX_LENGTH} chars max)");
+    }
+    if !RE.is_match(did) {
+        bail!("Invalid DID: `{did}`");
+    }
+    // Percent signs are only allowed as part of an escape
+    let bytes = did.as_bytes();
+    for (i, byte) in bytes.iter().enumerate() {
+        if *byte == b'%'
+            && !bytes
+                .get(i + 1..i + 3)
+                .is_some_and(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+        {
+            bail!("Invalid percent-encoding in DID: `{did}`");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::check_interop_vectors;
+
+    #[test]
+    fn test_interop_vectors() {
+        check_interop_vectors(
+            include_str!("../test-vectors/syntax/did_syntax_valid.txt"),
+            include_str!("../test-vectors/syntax/did_syntax_invalid.txt"),
+            |did| Did::new(did.to_string()).is_ok(),
+        );
+    }
+
+    #[test]
+    fn test_method_and_identifier() {
+        let did: Did = "did:web:localhost%3A1234".parse().unwrap();
+        assert_eq!(did.method(), "web");
+        assert_eq!(did.identifier(), "localhost%3A1234");
+    }
+
+    #[test]
+    fn test_serde_as_string() {
+        let did: Did = serde_json::from_str("\"did:plc:7iza6de2dwap2sbkpav7c6c6\"").unwrap();
+        assert_eq!(did.to_string(), "did:plc:7iza6de2dwap2sbkpav7c6c6");
+        assert_eq!(
+            serde_json::to_string(&did).unwrap(),
+            "\"did:plc:7iza6de2dwap2sbkpav7c6c6\""
+        );
+        assert!(serde_json::from_str::<Did>("\"did:plc:\"").is_err());
+    }
+}
@@ -0,0 +1,106 @@
+use crate::did::Did;
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use regex::Regex;
+
+pub const HANDLE_MAX_LENGTH: usize = 253;
+/// What the network shows in place of a handle that no longer verifies
+pub const INVALID_HANDLE: &str = "handle.invalid";
+/// Syntactically valid, but can never be registered as a handle
+pub const DISALLOWED_TLDS: [&str; 8] = [
+    ".local",
+    ".arpa",
+    ".invalid",
+    ".localhost",
+    ".internal",
+    ".example",
+    ".alt",
+    // policy could change some day
+    ".onion",
+    // `.test` is left out so development handles keep working
+];
+
+/// A handle, checked against the atproto handle syntax. Handles are case-insensitive, so they are
+/// normalized to lowercase.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Handle(String);
+
+string_newtype!(Handle);
+
+impl Handle {
+    pub fn new(handle: String) -> Result<Self> {
+        ensure_valid_handle(&handle)?;
+        Ok(Self(handle.to_lowercase()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_invalid_handle(&self) -> bool {
+        self.0 == INVALID_HANDLE
+    }
+
+    /// Whether the handle ends in a TLD that can't be registered, e.g. `.local`
+    pub fn has_disallowed_tld(&self) -> bool {
+        DISALLOWED_TLDS.iter().any(|tld| self.0.ends_with(tld))
+    }
+}
+
+/// Looks up the DID a handle points to, for callers here that need a DID but were given a
+/// handle. `rsky-identity`'s `HandleResolver` implements it.
+#[async_trait]
+pub trait ResolveHandle: Send + Sync {
+    async fn resolve_handle(&self, handle: &Handle) -> Result<Option<Did>>;
+}
+
+pub fn ensure_valid_handle(handle: &str) -> Result<()> {
+    lazy_static! {
+        static ref RE: Regex = Regex::new(
+            r"^([a-zA-Z0-9]([a-zA-Z0-9-]{0,61}[a-zA-Z0-9])?\.)+[a-zA-Z]([a-zA-Z0-9-]{0,61}[a-zA-Z0-9])?$"
+        )
+        .unwrap();
+    }
+    if handle.len() > HANDLE_MAX_LENGTH {
+        bail!("Handle is too long ({HANDLE_MAX_LENGTH} chars max)");
+    }
+    if !RE.is_match(handle) {
+        bail!("Invalid handle: `{handle}`");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::check_interop_vectors;
+
+    #[test]
+    fn test_interop_vectors() {
+        check_interop_vectors(
+            include_str!("../test-vectors/syntax/handle_syntax_valid.txt"),
+            include_str!("../test-vectors/syntax/handle_syntax_invalid.txt"),
+            |handle| Handle::new(handle.to_string()).is_ok(),
+        );
+    }
+
+    #[test]
+    fn test_normalizes_case() {
+        let handle: Handle = "John.Bsky.Social".parse().unwrap();
+        assert_eq!(handle.as_str(), "john.bsky.social");
+        assert_eq!(handle, "john.bsky.social".parse().unwrap());
+    }
+
+    #[test]
+    fn test_special_handles() {
+        let invalid: Handle = INVALID_HANDLE.parse().unwrap();
+        assert!(invalid.is_invalid_handle());
+        assert!(invalid.has_disallowed_tld());
+        let local: Handle = "laptop.local".parse().unwrap();
+        assert!(local.has_disallowed_tld());
+        let handle: Handle = "john.bsky.social".parse().unwrap();
+        assert!(!handle.is_invalid_handle());
+        assert!(!handle.has_disallowed_tld());
+    }
+}
@@ -0,0 +1,49 @@
+use anyhow::{bail, Result};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+/// Checks `lang` is a well-formed BCP-47 language tag, e.g. `en`, `pt-BR` or `zh-Hant`. Whether
+/// the subtags are registered isn't checked.
+pub fn ensure_valid_language(lang: &str) -> Result<()> {
+    lazy_static! {
+        static ref RE: Regex = Regex::new(concat!(
+            r"^(",
+            // grandfathered irregular and regular tags
+            r"(en-GB-oed|i-ami|i-bnn|i-default|i-enochian|i-hak|i-klingon|i-lux|i-mingo|i-navajo|i-pwn|i-tao|i-tay|i-tsu|sgn-BE-FR|sgn-BE-NL|sgn-CH-DE)",
+            r"|(art-lojban|cel-gaulish|no-bok|no-nyn|zh-guoyu|zh-hakka|zh-min|zh-min-nan|zh-xiang)",
+            // language, extlang, script, region, variants, extensions and private use
+            r"|((([A-Za-z]{2,3}(-([A-Za-z]{3}(-[A-Za-z]{3}){0,2}))?)|[A-Za-z]{4}|[A-Za-z]{5,8})",
+            r"(-([A-Za-z]{4}))?",
+            r"(-([A-Za-z]{2}|[0-9]{3}))?",
+            r"(-([A-Za-z0-9]{5,8}|[0-9][A-Za-z0-9]{3}))*",
+            r"(-([0-9A-WY-Za-wy-z](-[A-Za-z0-9]{2,8})+))*",
+            r"(-(x(-[A-Za-z0-9]{1,8})+))?)",
+            r"|(x(-[A-Za-z0-9]{1,8})+)",
+            r")$"
+        ))
+        .unwrap();
+    }
+    if !RE.is_match(lang) {
+        bail!("Invalid language tag: `{lang}`");
+    }
+    Ok(())
+}
+
+pub fn is_valid_language(lang: &str) -> bool {
+    ensure_valid_language(lang).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::check_interop_vectors;
+
+    #[test]
+    fn test_interop_vectors() {
+        check_interop_vectors(
+            include_str!("../test-vectors/syntax/language_syntax_valid.txt"),
+            include_str!("../test-vectors/syntax/language_syntax_invalid.txt"),
+            is_valid_language,
+        );
+    }
+}
@@ -1,5 +1,90 @@
-#[macro_use]
-extern crate serde_derive;
 extern crate serde;
 
+/// Implements the string conversions and serde shared by the identifier newtypes, all of which
+/// validate in `new(String) -> Result<Self>` and expose the value through `as_str()`.
+macro_rules! string_newtype {
+    ($ty:ident) => {
+        impl std::str::FromStr for $ty {
+            type Err = anyhow::Error;
+
+            fn from_str(s: &str) -> anyhow::Result<Self> {
+                Self::new(s.to_string())
+            }
+        }
+
+        impl std::fmt::Display for $ty {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl AsRef<str> for $ty {
+            fn as_ref(&self) -> &str {
+                self.as_str()
+            }
+        }
+
+        impl TryFrom<String> for $ty {
+            type Error = anyhow::Error;
+
+            fn try_from(value: String) -> anyhow::Result<Self> {
+                Self::new(value)
+            }
+        }
+
+        impl From<$ty> for String {
+            fn from(value: $ty) -> Self {
+                value.as_str().to_string()
+            }
+        }
+
+        impl serde::Serialize for $ty {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(self.as_str())
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $ty {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let value = String::deserialize(deserializer)?;
+                Self::new(value).map_err(serde::de::Error::custom)
+            }
+        }
+    };
+}
+
+pub mod at_identifier;
 pub mod aturi;
+pub mod cid;
+pub mod datetime;
+pub mod did;
+pub mod handle;
+pub mod language;
+pub mod nsid;
+pub mod record_key;
+pub mod tid;
+
+pub use at_identifier::AtIdentifier;
+pub use cid::CidString;
+pub use did::Did;
+pub use handle::Handle;
+pub use nsid::Nsid;
+pub use record_key::RecordKey;
+pub use tid::Tid;
+
+/// Checks `is_valid` against the contents of one of the interop `*_syntax_valid.txt` and
+/// `*_syntax_invalid.txt` test-vector pairs, skipping comments and blank lines.
+#[cfg(test)]
+pub(crate) fn check_interop_vectors(valid: &str, invalid: &str, is_valid: impl Fn(&str) -> bool) {
+    fn vectors(contents: &str) -> impl Iterator<Item = &str> {
+        contents
+            .lines()
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+    }
+    for value in vectors(valid) {
+        assert!(is_valid(value), "expected valid: {value}");
+    }
+    for value in vectors(invalid) {
+        assert!(!is_valid(value), "expected invalid: {value}");
+    }
+}
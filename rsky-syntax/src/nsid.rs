@@ -0,0 +1,98 @@
+use anyhow::{bail, Result};
+
+pub const NSID_MAX_LENGTH: usize = 317;
+const NSID_AUTHORITY_MAX_LENGTH: usize = 253;
+const NSID_SEGMENT_MAX_LENGTH: usize = 63;
+
+/// A namespaced identifier such as `app.bsky.feed.post`: a reversed domain authority followed by
+/// a name.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Nsid(String);
+
+string_newtype!(Nsid);
+
+impl Nsid {
+    pub fn new(nsid: String) -> Result<Self> {
+        ensure_valid_nsid(&nsid)?;
+        Ok(Self(nsid))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The authority as a domain, e.g. `feed.bsky.app` for `app.bsky.feed.post`
+    pub fn authority(&self) -> String {
+        let mut segments: Vec<&str> = self.0.split('.').collect();
+        segments.pop();
+        segments.reverse();
+        segments.join(".")
+    }
+
+    /// e.g. `post` for `app.bsky.feed.post`
+    pub fn name(&self) -> &str {
+        self.0.rsplit('.').next().unwrap_or_default()
+    }
+}
+
+pub fn ensure_valid_nsid(nsid: &str) -> Result<()> {
+    if nsid.len() > NSID_MAX_LENGTH {
+        bail!("NSID is too long ({NSID_MAX_LENGTH} chars max)");
+    }
+    let segments: Vec<&str> = nsid.split('.').collect();
+    if segments.len() < 3 {
+        bail!("NSID needs at least three parts: `{nsid}`");
+    }
+    let (name, authority) = segments.split_last().expect("at least three segments");
+    if nsid.len() - name.len() - 1 > NSID_AUTHORITY_MAX_LENGTH {
+        bail!("NSID domain authority is too long ({NSID_AUTHORITY_MAX_LENGTH} chars max)");
+    }
+    for (i, segment) in authority.iter().enumerate() {
+        if segment.is_empty() || segment.len() > NSID_SEGMENT_MAX_LENGTH {
+            bail!("NSID parts must be 1 to {NSID_SEGMENT_MAX_LENGTH} chars: `{nsid}`");
+        }
+        if !segment
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-')
+            || segment.starts_with('-')
+            || segment.ends_with('-')
+        {
+            bail!("Invalid NSID domain authority: `{nsid}`");
+        }
+        // Like a TLD, the first part can't be numeric
+        if i == 0 && segment.starts_with(|c: char| c.is_ascii_digit()) {
+            bail!("NSID can't start with a digit: `{nsid}`");
+        }
+    }
+    if name.is_empty() || name.len() > NSID_SEGMENT_MAX_LENGTH {
+        bail!("NSID parts must be 1 to {NSID_SEGMENT_MAX_LENGTH} chars: `{nsid}`");
+    }
+    if !name.chars().all(|c| c.is_ascii_alphanumeric())
+        || !name.starts_with(|c: char| c.is_ascii_alphabetic())
+    {
+        bail!("Invalid NSID name: `{nsid}`");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::check_interop_vectors;
+
+    #[test]
+    fn test_interop_vectors() {
+        check_interop_vectors(
+            include_str!("../test-vectors/syntax/nsid_syntax_valid.txt"),
+            include_str!("../test-vectors/syntax/nsid_syntax_invalid.txt"),
+            |nsid| Nsid::new(nsid.to_string()).is_ok(),
+        );
+    }
+
+    #[test]
+    fn test_authority_and_name() {
+        let nsid: Nsid = "app.bsky.feed.post".parse().unwrap();
+        assert_eq!(nsid.authority(), "feed.bsky.app");
+        assert_eq!(nsid.name(), "post");
+    }
+}
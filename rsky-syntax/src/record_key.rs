@@ -0,0 +1,70 @@
+use crate::tid::Tid;
+use anyhow::{bail, Result};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+/// The key of a record within a collection. Most are TIDs, but some collections use fixed keys
+/// such as `self`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RecordKey(String);
+
+string_newtype!(RecordKey);
+
+impl RecordKey {
+    pub fn new(rkey: String) -> Result<Self> {
+        ensure_valid_record_key(&rkey)?;
+        Ok(Self(rkey))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The key as a TID, if it is one. Posts, likes and reposts are keyed by TID.
+    pub fn as_tid(&self) -> Option<Tid> {
+        Tid::new(self.0.clone()).ok()
+    }
+}
+
+impl From<Tid> for RecordKey {
+    fn from(tid: Tid) -> Self {
+        Self(tid.into())
+    }
+}
+
+pub fn ensure_valid_record_key(rkey: &str) -> Result<()> {
+    lazy_static! {
+        static ref RE: Regex = Regex::new(r"^[a-zA-Z0-9_~.:-]{1,512}$").unwrap();
+    }
+    if !RE.is_match(rkey) {
+        bail!("Invalid record key: `{rkey}`");
+    }
+    if rkey == "." || rkey == ".." {
+        bail!("Record key can't be `.` or `..`");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::check_interop_vectors;
+
+    #[test]
+    fn test_interop_vectors() {
+        check_interop_vectors(
+            include_str!("../test-vectors/syntax/recordkey_syntax_valid.txt"),
+            include_str!("../test-vectors/syntax/recordkey_syntax_invalid.txt"),
+            |rkey| RecordKey::new(rkey.to_string()).is_ok(),
+        );
+    }
+
+    #[test]
+    fn test_from_tid() {
+        let tid: Tid = "3jzfcijpj2z2a".parse().unwrap();
+        let rkey = RecordKey::from(tid.clone());
+        assert_eq!(rkey.as_str(), "3jzfcijpj2z2a");
+        assert_eq!(rkey.as_tid(), Some(tid));
+        assert_eq!("self".parse::<RecordKey>().unwrap().as_tid(), None);
+    }
+}
@@ -0,0 +1,181 @@
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use rand::Rng;
+use regex::Regex;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+pub const TID_LENGTH: usize = 13;
+const S32_CHARS: &[u8; 32] = b"234567abcdefghijklmnopqrstuvwxyz";
+const CLOCK_ID_BITS: u32 = 10;
+const TIMESTAMP_BITS: u32 = 53;
+
+lazy_static! {
+    static ref LAST_TIMESTAMP: Mutex<u64> = Mutex::new(0);
+    /// Picked once per process, so TIDs generated concurrently elsewhere are unlikely to collide
+    static ref CLOCK_ID: u16 = rand::thread_rng().gen_range(0..1 << CLOCK_ID_BITS);
+}
+
+/// A timestamp identifier: a microsecond timestamp and a 10-bit clock id, written as 13
+/// characters of base32-sortable encoding, so TIDs sort in the order they were created.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Tid(String);
+
+string_newtype!(Tid);
+
+impl Tid {
+    pub fn new(tid: String) -> Result<Self> {
+        ensure_valid_tid(&tid)?;
+        Ok(Self(tid))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// A TID for the current time. Every TID generated by this process is greater than the last,
+    /// even if the clock goes backwards.
+    pub fn next() -> Self {
+        Self::next_from(0)
+    }
+
+    /// Like [`Tid::next`], but also greater than `prev`, e.g. the rev of the last commit.
+    pub fn next_after(prev: &Tid) -> Self {
+        Self::next_from(prev.timestamp() + 1)
+    }
+
+    /// A TID for the current time or `min_timestamp`, whichever is later, after every TID
+    /// generated so far.
+    fn next_from(min_timestamp: u64) -> Self {
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .expect("timestamp in micros since UNIX epoch")
+            .as_micros() as u64;
+        let mut last = LAST_TIMESTAMP
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let timestamp = now.max(*last + 1).max(min_timestamp);
+        *last = timestamp;
+        Self::from_timestamp(timestamp, *CLOCK_ID)
+    }
+
+    /// `timestamp` is in microseconds since the UNIX epoch. Only the low 53 bits of the timestamp
+    /// and 10 bits of the clock id are kept.
+    pub fn from_timestamp(timestamp: u64, clock_id: u16) -> Self {
+        let timestamp = timestamp & ((1 << TIMESTAMP_BITS) - 1);
+        let clock_id = clock_id as u64 & ((1 << CLOCK_ID_BITS) - 1);
+        let mut value = (timestamp << CLOCK_ID_BITS) | clock_id;
+        let mut encoded = [b'2'; TID_LENGTH];
+        for c in encoded.iter_mut().rev() {
+            *c = S32_CHARS[(value & 31) as usize];
+            value >>= 5;
+        }
+        Self(String::from_utf8(encoded.to_vec()).expect("base32-sortable is ASCII"))
+    }
+
+    fn value(&self) -> u64 {
+        self.0.bytes().fold(0, |value, c| {
+            let digit = S32_CHARS
+                .iter()
+                .position(|s32| *s32 == c)
+                .expect("validated TID");
+            (value << 5) | digit as u64
+        })
+    }
+
+    /// Microseconds since the UNIX epoch
+    pub fn timestamp(&self) -> u64 {
+        self.value() >> CLOCK_ID_BITS
+    }
+
+    pub fn clock_id(&self) -> u16 {
+        (self.value() & ((1 << CLOCK_ID_BITS) - 1)) as u16
+    }
+
+    /// When the TID claims to have been generated. Clients generate the TIDs for their record
+    /// keys, so this is only as trustworthy as the client.
+    pub fn created_at(&self) -> DateTime<Utc> {
+        DateTime::from_timestamp_micros(self.timestamp() as i64)
+            .expect("53-bit timestamp is in range")
+    }
+}
+
+pub fn ensure_valid_tid(tid: &str) -> Result<()> {
+    lazy_static! {
+        // The first character is limited so the top bit of the 64-bit value is always zero
+        static ref RE: Regex =
+            Regex::new(r"^[234567abcdefghij][234567abcdefghijklmnopqrstuvwxyz]{12}$").unwrap();
+    }
+    if tid.len() != TID_LENGTH {
+        bail!("TID must be {TID_LENGTH} characters");
+    }
+    if !RE.is_match(tid) {
+        bail!("Invalid TID: `{tid}`");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::check_interop_vectors;
+
+    #[test]
+    fn test_interop_vectors() {
+        check_interop_vectors(
+            include_str!("../test-vectors/syntax/tid_syntax_valid.txt"),
+            include_str!("../test-vectors/syntax/tid_syntax_invalid.txt"),
+            |tid| Tid::new(tid.to_string()).is_ok(),
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let tid = Tid::from_timestamp(1_700_000_000_123_456, 513);
+        assert_eq!(tid.as_str().len(), TID_LENGTH);
+        assert_eq!(tid.timestamp(), 1_700_000_000_123_456);
+        assert_eq!(tid.clock_id(), 513);
+        assert_eq!(tid, tid.as_str().parse().unwrap());
+        assert_eq!(
+            tid.created_at().to_rfc3339(),
+            "2023-11-14T22:13:20.123456+00:00"
+        );
+    }
+
+    #[test]
+    fn test_parses_real_rkey() {
+        let tid: Tid = "3jzfcijpj2z2a".parse().unwrap();
+        assert_eq!(Tid::from_timestamp(tid.timestamp(), tid.clock_id()), tid);
+        assert_eq!(tid.created_at().format("%Y").to_string(), "2023");
+    }
+
+    #[test]
+    fn test_next_is_monotonic() {
+        let mut prev = Tid::next();
+        for _ in 0..1000 {
+            let next = Tid::next();
+            assert!(next > prev);
+            assert!(next.timestamp() > prev.timestamp());
+            prev = next;
+        }
+        let future = Tid::from_timestamp(prev.timestamp() + 60_000_000, 0);
+        assert!(Tid::next_after(&future) > future);
+    }
+
+    #[test]
+    fn test_next_follows_next_after() {
+        let future = Tid::from_timestamp(Tid::next().timestamp() + 60_000_000, 0);
+        let after = Tid::next_after(&future);
+        assert!(after > future);
+        assert!(Tid::next() > after);
+    }
+
+    #[test]
+    fn test_orders_by_timestamp_then_clock_id() {
+        let earlier = Tid::from_timestamp(1_000, 1023);
+        let later = Tid::from_timestamp(1_001, 0);
+        assert!(earlier < later);
+        assert!(Tid::from_timestamp(1_000, 1) < Tid::from_timestamp(1_000, 2));
+    }
+}